@@ -1,13 +1,163 @@
 use std::{fmt, sync::Arc};
-use num_bigint::BigInt;
+use std::cmp::Ordering;
+use num_bigint::{BigInt, Sign};
 use std::collections::HashMap;
 use crate::ParseError;
+use crate::interpreter::{describe_trace, eval_binary_operation, Channel, ErrorValue, Function, RuntimeError, ThreadHandle};
+
+/// Where a construct sits in its source file. `length` counts characters on
+/// `line` starting at `column`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Span {
+    pub file: Arc<String>,
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+}
+
+impl Span {
+    /// Stretches this span up to the end of `other` when both sit on the same line.
+    pub fn to(&self, other: &Span) -> Span {
+        if other.file == self.file && other.line == self.line && other.column >= self.column {
+            Span {
+                length: other.column + other.length - self.column,
+                ..self.clone()
+            }
+        } else {
+            self.clone()
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+impl Statement {
+    pub fn new(kind: StatementKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    /// Calls `visit` on this statement and on every statement nested inside
+    /// it, including the bodies of functions and threads written as values.
+    pub fn walk<E>(&self, visit: &mut impl FnMut(&Statement) -> Result<(), E>) -> Result<(), E> {
+        visit(self)?;
+
+        let mut expressions: Vec<&Expression> = Vec::new();
+        let mut blocks: Vec<&[Statement]> = Vec::new();
+        match &self.kind {
+            StatementKind::VarDeclarationExpr(_, value)
+            | StatementKind::Assignment(_, value)
+            | StatementKind::Expr(value)
+            | StatementKind::Return(value)
+            | StatementKind::Throw(value)
+            | StatementKind::Print(value) => expressions.push(value),
+            StatementKind::PathAssignment { path, value, .. } => {
+                expressions.extend(path.iter().filter_map(PathSegment::index));
+                expressions.push(value);
+            },
+            StatementKind::Remove { path, .. } => expressions.extend(path.iter().filter_map(PathSegment::index)),
+            StatementKind::IfStatement { condition, body, else_branch } => {
+                expressions.push(condition);
+                blocks.push(body);
+                if let Some(else_branch) = else_branch {
+                    else_branch.walk(visit)?;
+                }
+            },
+            StatementKind::ForLoop { init_value, condition, increment_expr, body, .. } => {
+                expressions.extend([init_value, condition, increment_expr]);
+                blocks.push(body);
+            },
+            StatementKind::ForInLoop { iterable, body, .. } => {
+                expressions.push(iterable);
+                blocks.push(body);
+            },
+            StatementKind::Loop { start, end, step, body, .. } => {
+                expressions.extend([start, end]);
+                expressions.extend(step);
+                blocks.push(body);
+            },
+            StatementKind::LoopBlock { body }
+            | StatementKind::Function { body, .. }
+            | StatementKind::AsyncFunction { body, .. }
+            | StatementKind::Spawn { body, .. } => blocks.push(body),
+            StatementKind::WhileLoop { condition, body } => {
+                expressions.push(condition);
+                blocks.push(body);
+            },
+            StatementKind::SwitchStatement { value, cases, default } => {
+                expressions.push(value);
+                for case in cases {
+                    expressions.extend(&case.guard);
+                    blocks.push(&case.body);
+                }
+                blocks.extend(default.as_deref());
+            },
+            StatementKind::CallFunction { args, .. } => expressions.extend(args),
+            StatementKind::SpawnAsync { future, .. } | StatementKind::Await { future } => future.walk(visit)?,
+            StatementKind::ThreadPool { size, tasks } => {
+                expressions.push(size);
+                blocks.push(tasks);
+            },
+            StatementKind::WhenStatement { condition, body, alternatives, default } => {
+                expressions.push(condition);
+                blocks.push(body);
+                for (condition, body) in alternatives {
+                    expressions.push(condition);
+                    blocks.push(body);
+                }
+                blocks.extend(default.as_deref());
+            },
+            StatementKind::TryBlock { try_block, catches, finally } => {
+                blocks.push(try_block);
+                blocks.extend(catches.iter().map(|catch| catch.body.as_slice()));
+                blocks.extend(finally.as_deref());
+            },
+            StatementKind::VarDeclaration(..)
+            | StatementKind::Break
+            | StatementKind::Continue
+            | StatementKind::Read(_)
+            | StatementKind::Import { .. }
+            | StatementKind::Export { .. }
+            | StatementKind::ArrayDeclaration { .. }
+            | StatementKind::StructDeclaration { .. }
+            | StatementKind::EnumDeclaration { .. }
+            | StatementKind::Wait { .. }
+            | StatementKind::Sync { .. } => {},
+        }
+
+        for expression in expressions {
+            expression.walk_statements(visit)?;
+        }
+        for statement in blocks.into_iter().flatten() {
+            statement.walk(visit)?;
+        }
+        Ok(())
+    }
+}
 
 #[derive(Debug, Clone)]
-pub enum Statement {
+pub enum StatementKind {
     VarDeclaration(String, Value),
     VarDeclarationExpr(String, Expression),
     Assignment(String, Expression),
+    PathAssignment {
+        name: String,
+        path: Vec<PathSegment>,
+        value: Expression,
+    },
+    Remove {
+        name: String,
+        path: Vec<PathSegment>,
+    },
     Expr(Expression),
     IfStatement {
         condition: Expression,
@@ -24,13 +174,15 @@ pub enum Statement {
     },
     ForInLoop {
         iterator_var: String,
-        array_name: String,
+        value_var: Option<String>,
+        iterable: Expression,
         body: Vec<Statement>,
     },
     Loop {
         variable: String,
-        start: i32,
-        end: i32,
+        start: Expression,
+        end: Expression,
+        step: Option<Expression>,
         body: Vec<Statement>,
     },
     LoopBlock {
@@ -42,7 +194,7 @@ pub enum Statement {
     },
     SwitchStatement {
         value: Expression,
-        cases: Vec<(Value, Vec<Statement>)>,
+        cases: Vec<MatchCase>,
         default: Option<Vec<Statement>>,
     },
     Break,
@@ -76,6 +228,10 @@ pub enum Statement {
         name: String,
         fields: Vec<(String, Type)>,
     },
+    EnumDeclaration {
+        name: String,
+        variants: Vec<(String, Vec<String>)>,
+    },
     Spawn {
         body: Vec<Statement>,
         thread_name: Option<String>,
@@ -107,20 +263,56 @@ pub enum Statement {
         condition: Expression,
         body: Vec<Statement>,
         alternatives: Vec<(Expression, Vec<Statement>)>,
+        default: Option<Vec<Statement>>,
     },
+    Throw(Expression),
     TryBlock {
         try_block: Vec<Statement>,
-        catch_param: Option<String>,
-        catch_body: Vec<(Statement)>,
+        catches: Vec<CatchClause>,
+        finally: Option<Vec<Statement>>,
     },
 }
 
+/// One `mrglurp` handler. It handles errors of the listed kinds, or every
+/// error when none are listed.
+#[derive(Debug, Clone)]
+pub struct CatchClause {
+    pub param: Option<String>,
+    pub kinds: Vec<String>,
+    pub body: Vec<Statement>,
+}
+
+impl CatchClause {
+    pub fn handles(&self, error: &ErrorValue) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&error.kind)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Type {
     Number,
     Text,
     Array(Box<Type>),
     Struct(String),
+    Function,
+    Bool,
+    Map,
+}
+
+impl Type {
+    /// Whether `value` may be stored in a struct field declared with this type.
+    pub fn accepts(&self, value: &Value) -> bool {
+        match (value, self) {
+            (Value::Number(_) | Value::NumberI64(_) | Value::NumberBig(_) | Value::Float(_), Type::Number)
+            | (Value::Bool(_), Type::Bool)
+            | (Value::Map(_), Type::Map)
+            | (Value::Text(_), Type::Text)
+            | (Value::Function(_), Type::Function) => true,
+            (Value::Array(items), Type::Array(element)) => items.iter().all(|item| element.accepts(item)),
+            (Value::Struct(name, _) | Value::Enum(name, _, _), Type::Struct(expected)) => name == expected,
+            _ => false,
+        }
+    }
 }
 
 impl fmt::Display for Type {
@@ -130,6 +322,9 @@ impl fmt::Display for Type {
             Type::Text => write!(f, "Text"),
             Type::Array(t) => write!(f, "Array<{}>", t),
             Type::Struct(name) => write!(f, "{}", name),
+            Type::Function => write!(f, "Function"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Map => write!(f, "Map"),
         }
     }
 }
@@ -139,12 +334,196 @@ pub enum Value {
     Number(i32),
     NumberI64(i64),
     NumberBig(BigInt),
+    Float(f64),
+    Bool(bool),
     Text(String),
     Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
     Struct(String, Vec<(String, Value)>),
+    /// A variant of a user enum: enum name, variant name and payload.
+    Enum(String, String, Vec<Value>),
     Future(Box<Statement>),
-    Thread(String),
-    Error(Arc<String>),
+    Thread(ThreadHandle),
+    Channel(Channel),
+    Error(Arc<ErrorValue>),
+    Function(Arc<Function>),
+}
+
+impl Value {
+    /// Reads a numeric literal: a float if it has a decimal point, otherwise
+    /// the narrowest integer variant that holds it.
+    pub fn parse_number(text: &str) -> Option<Value> {
+        if text.contains('.') {
+            return text.parse::<f64>().ok().map(Value::Float);
+        }
+        text.parse::<BigInt>().ok().map(Value::from_bigint)
+    }
+
+    /// The narrowest number variant that holds `n`.
+    pub fn from_i64(n: i64) -> Value {
+        match i32::try_from(n) {
+            Ok(n) => Value::Number(n),
+            Err(_) => Value::NumberI64(n),
+        }
+    }
+
+    /// The narrowest number variant that holds `n`.
+    pub fn from_bigint(n: BigInt) -> Value {
+        match i64::try_from(&n) {
+            Ok(n) => Value::from_i64(n),
+            Err(_) => Value::NumberBig(n),
+        }
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, Value::Number(_) | Value::NumberI64(_) | Value::NumberBig(_) | Value::Float(_))
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Number(n) => Some(i64::from(*n)),
+            Value::NumberI64(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn to_bigint(&self) -> Option<BigInt> {
+        match self {
+            Value::Number(n) => Some(BigInt::from(*n)),
+            Value::NumberI64(n) => Some(BigInt::from(*n)),
+            Value::NumberBig(n) => Some(n.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn to_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(f64::from(*n)),
+            Value::NumberI64(n) => Some(*n as f64),
+            Value::NumberBig(n) => n.to_string().parse().ok(),
+            Value::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Whether a value counts as true where a condition is expected:
+    ///
+    /// | value                   | truthy when          |
+    /// |-------------------------|----------------------|
+    /// | `mrgtru` / `mrgfls`     | it is `mrgtru`       |
+    /// | numbers of any kind     | not zero (nor NaN)   |
+    /// | text                    | not empty            |
+    /// | arrays, maps            | not empty            |
+    /// | structs, enums, threads | always               |
+    /// | channels                | always               |
+    /// | errors                  | never                |
+    /// | functions, futures      | an error: call or await them first |
+    pub fn truthiness(&self) -> Result<bool, RuntimeError> {
+        match self {
+            Value::Bool(b) => Ok(*b),
+            Value::Number(n) => Ok(*n != 0),
+            Value::NumberI64(n) => Ok(*n != 0),
+            Value::NumberBig(n) => Ok(n.sign() != Sign::NoSign),
+            Value::Float(n) => Ok(*n != 0.0 && !n.is_nan()),
+            Value::Text(text) => Ok(!text.is_empty()),
+            Value::Array(elements) => Ok(!elements.is_empty()),
+            Value::Map(entries) => Ok(!entries.is_empty()),
+            Value::Struct(..) | Value::Enum(..) | Value::Thread(_) | Value::Channel(_) => Ok(true),
+            Value::Error(_) => Ok(false),
+            Value::Function(_) => Err(RuntimeError::TypeError(
+                "a function is not a condition; did you forget to call it with 'grrrblbl'?".to_string()
+            )),
+            Value::Future(_) => Err(RuntimeError::TypeError(
+                "a future is not a condition; await it with 'mrgargl' first".to_string()
+            )),
+        }
+    }
+
+    /// Map keys are text or numbers.
+    pub fn check_map_key(&self) -> Result<(), RuntimeError> {
+        if self.is_number() || matches!(self, Value::Text(_)) {
+            Ok(())
+        } else {
+            Err(RuntimeError::TypeError(format!("map keys must be text or numbers, not {}", self)))
+        }
+    }
+
+    /// The value stored under `key`, if this is a map holding it.
+    pub fn map_get(&self, key: &Value) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// The element of an array or the value of a map stored under `index`.
+    pub fn index(&self, index: &Value) -> Result<Value, RuntimeError> {
+        match self {
+            Value::Array(items) => Ok(items[Value::array_position(items, index)?].clone()),
+            Value::Map(_) => {
+                index.check_map_key()?;
+                self.map_get(index).cloned().ok_or_else(|| RuntimeError::IndexOutOfBounds(
+                    format!("key '{}' not found in map", index)
+                ))
+            },
+            other => Err(RuntimeError::TypeError(format!("Cannot index into {}", other))),
+        }
+    }
+
+    pub fn field(&self, field: &str) -> Result<Value, RuntimeError> {
+        match self {
+            Value::Struct(struct_name, fields) => fields.iter()
+                .find(|(name, _)| name == field)
+                .map(|(_, value)| value.clone())
+                .ok_or_else(|| RuntimeError::InvalidOperation(
+                    format!("Field '{}' does not exist in struct '{}'", field, struct_name)
+                )),
+            Value::Error(error) => match field {
+                "kind" => Ok(Value::Text(error.kind.clone())),
+                "message" => Ok(Value::Text(error.message.clone())),
+                "value" => Ok(error.value.clone()),
+                "span" => Ok(Value::Text(error.span.as_ref().map(|span| span.to_string()).unwrap_or_default())),
+                "stack" => Ok(Value::Array(describe_trace(&error.stack).into_iter().map(Value::Text).collect())),
+                _ => Err(RuntimeError::InvalidOperation(format!(
+                    "Errors have no field '{}'; they carry kind, message, value, span and stack", field
+                ))),
+            },
+            other => Err(RuntimeError::TypeError(format!("Cannot read field '{}' of {}", field, other))),
+        }
+    }
+
+    /// Checks that `index` is a whole number inside `items`.
+    pub fn array_position(items: &[Value], index: &Value) -> Result<usize, RuntimeError> {
+        let position = index.as_i64().ok_or_else(|| RuntimeError::TypeError(
+            format!("Array index must be a whole number, not {}", index)
+        ))?;
+        usize::try_from(position).ok().filter(|position| *position < items.len()).ok_or_else(|| RuntimeError::IndexOutOfBounds(
+            format!("index {} outside an array of length {}", position, items.len())
+        ))
+    }
+
+    /// Stores `value` under `key`, replacing the old value in place or adding
+    /// the key at the end, so a map iterates in insertion order.
+    pub fn map_insert(entries: &mut Vec<(Value, Value)>, key: Value, value: Value) -> Result<(), RuntimeError> {
+        key.check_map_key()?;
+        match entries.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, slot)) => *slot = value,
+            None => entries.push((key, value)),
+        }
+        Ok(())
+    }
+
+    /// Orders two numbers whatever their widths; an integer meeting a float
+    /// is compared as a float. `None` if either is not a number.
+    fn compare_numbers(&self, other: &Value) -> Option<Ordering> {
+        if matches!(self, Value::Float(_)) || matches!(other, Value::Float(_)) {
+            return self.to_f64()?.partial_cmp(&other.to_f64()?);
+        }
+        match (self.as_i64(), other.as_i64()) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            _ => Some(self.to_bigint()?.cmp(&other.to_bigint()?)),
+        }
+    }
 }
 
 impl fmt::Display for Value {
@@ -153,6 +532,10 @@ impl fmt::Display for Value {
             Value::Number(n) => write!(f, "{}", n),
             Value::NumberI64(n) => write!(f, "{}", n),
             Value::NumberBig(n) => write!(f, "{}", n),
+            Value::Float(n) if n.is_finite() && n.fract() == 0.0 => write!(f, "{:.1}", n),
+            Value::Float(n) => write!(f, "{}", n),
+            Value::Bool(true) => write!(f, "mrgtru"),
+            Value::Bool(false) => write!(f, "mrgfls"),
             Value::Text(s) => write!(f, "{}", s),
             Value::Array(arr) => {
                 write!(f, "[")?;
@@ -164,6 +547,16 @@ impl fmt::Display for Value {
                 }
                 write!(f, "]")
             },
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                write!(f, "}}")
+            },
             Value::Struct(_, fields) => {
                 let mut first = true;
                 for (field, value) in fields {
@@ -175,174 +568,81 @@ impl fmt::Display for Value {
                 }
                 Ok(())
             },
+            Value::Enum(name, variant, payload) if payload.is_empty() => write!(f, "{}.{}", name, variant),
+            Value::Enum(name, variant, payload) => {
+                let payload = payload.iter().map(|value| value.to_string()).collect::<Vec<_>>();
+                write!(f, "{}.{}({})", name, variant, payload.join(", "))
+            },
             Value::Future(_) => write!(f, "<future>"),
-            Value::Thread(name) => write!(f, "<thread:{}>", name),
-            Value::Error(err) => write!(f, "<error: {}>", err),
+            Value::Thread(thread) => write!(f, "<thread:{}>", thread.id),
+            Value::Channel(channel) => write!(f, "<channel:{}>", channel.id),
+            Value::Error(err) => write!(f, "{}: {}", err.kind, err.message),
+            Value::Function(function) => write!(f, "<function({})>", function.params.join(", ")),
         }
     }
 }
 
 impl Expression {
     pub fn eval(&self, env: &HashMap<String, Value>) -> Result<Value, ParseError> {
-        match self {
-            Expression::Literal(value) => Ok(value.clone()),
-            Expression::Variable(name) => {
+        match &self.kind {
+            ExpressionKind::Literal(value) => Ok(value.clone()),
+            ExpressionKind::Variable(name) => {
                 if let Some(value) = env.get(name) {
                     Ok(value.clone())
                 } else {
-                    Err(ParseError::InvalidValue(format!("Variable '{}' lost in the cosmic void", name)))
+                    Err(RuntimeError::UndefinedVariable(name.clone()).into())
                 }
             },
-            Expression::BinaryOp { left, right, op } => {
+            ExpressionKind::BinaryOp { left, right, op } => {
                 let left_val = left.eval(env)?;
                 let right_val = right.eval(env)?;
-                match op {
-                    BinaryOperator::Add => match (&left_val, &right_val) {
-                        (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a + b)),
-                        (Value::Text(a), Value::Text(b)) => Ok(Value::Text(format!("{}{}", a, b))),
-                        (Value::Text(a), Value::Number(b)) => Ok(Value::Text(format!("{}{}", a, b))),
-                        (Value::Number(a), Value::Text(b)) => Ok(Value::Text(format!("{}{}", a, b))),
-                        _ => Err(ParseError::InvalidValue("Invalid addition operation in the cosmic void".to_string())),
-                    },
-                    BinaryOperator::Subtract => match (&left_val, &right_val) {
-                        (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a - b)),
-                        _ => Err(ParseError::InvalidValue("Invalid subtraction operation in the cosmic void".to_string())),
-                    },
-                    BinaryOperator::Multiply => match (&left_val, &right_val) {
-                        (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a * b)),
-                        _ => Err(ParseError::InvalidValue("Invalid multiplication operation in the cosmic void".to_string())),
-                    },
-                    BinaryOperator::Divide => match (&left_val, &right_val) {
-                        (Value::Number(a), Value::Number(b)) => {
-                            if *b == 0 {
-                                Err(ParseError::InvalidValue("Attempted to divide by the void".to_string()))
-                            } else {
-                                Ok(Value::Number(a / b))
-                            }
-                        },
-                        _ => Err(ParseError::InvalidValue("Invalid division operation in the cosmic void".to_string())),
-                    },
-                    BinaryOperator::Modulo => match (&left_val, &right_val) {
-                        (Value::Number(a), Value::Number(b)) => {
-                            if *b == 0 {
-                                Err(ParseError::InvalidValue("Attempted to modulo by the void".to_string()))
-                            } else {
-                                Ok(Value::Number(a % b))
-                            }
-                        },
-                        _ => Err(ParseError::InvalidValue("Invalid modulo operation in the cosmic void".to_string())),
-                    },
-                }
+                eval_binary_operation(&left_val, &right_val, op)
             },
-            Expression::Comparison { left, right, op } => {
+            ExpressionKind::Comparison { left, right, op } => {
                 let left_val = left.eval(env)?;
                 let right_val = right.eval(env)?;
-                let result = match op {
-                    ComparisonOperator::Equals => left_val == right_val,
-                    ComparisonOperator::NotEquals => left_val != right_val,
-                    ComparisonOperator::LessThan => match (&left_val, &right_val) {
-                        (Value::Number(a), Value::Number(b)) => a < b,
-                        _ => return Err(ParseError::InvalidValue("Comparison < only works with numbers in the cosmic void".to_string())),
-                    },
-                    ComparisonOperator::GreaterThan => match (&left_val, &right_val) {
-                        (Value::Number(a), Value::Number(b)) => a > b,
-                        _ => return Err(ParseError::InvalidValue("Comparison > only works with numbers in the cosmic void".to_string())),
-                    },
-                    ComparisonOperator::LessThanOrEqual => match (&left_val, &right_val) {
-                        (Value::Number(a), Value::Number(b)) => a <= b,
-                        _ => return Err(ParseError::InvalidValue("Comparison <= only works with numbers in the cosmic void".to_string())),
-                    },
-                    ComparisonOperator::GreaterThanOrEqual => match (&left_val, &right_val) {
-                        (Value::Number(a), Value::Number(b)) => a >= b,
-                        _ => return Err(ParseError::InvalidValue("Comparison >= only works with numbers in the cosmic void".to_string())),
-                    },
-                };
-                Ok(Value::Number(if result { 1 } else { 0 }))
+                op.apply(&left_val, &right_val).map(Value::Bool).map_err(ParseError::from)
             },
-            Expression::LogicalOp { left, right, op } => {
+            ExpressionKind::LogicalOp { left, right, op } => {
                 let left_val = left.eval(env)?;
-                let left_bool = match &left_val {
-                    Value::Number(n) => *n != 0,
-                    _ => return Err(ParseError::InvalidValue("Logical operations require numeric operands in the cosmic void".to_string())),
-                };
-                
+                let left_bool = left_val.truthiness().map_err(ParseError::from)?;
+
                 match op {
-                    LogicalOperator::Not => Ok(Value::Number(if left_bool { 0 } else { 1 })),
-                    LogicalOperator::And => {
-                        if !left_bool {
-                            return Ok(Value::Number(0));
-                        }
-                        
-                        let right = right.as_ref().ok_or_else(|| 
-                            ParseError::InvalidValue("AND operator requires a right operand in the ritual".to_string())
-                        )?;
-                        
-                        let right_val = right.eval(env)?;
-                        let right_bool = match &right_val {
-                            Value::Number(n) => *n != 0,
-                            _ => return Err(ParseError::InvalidValue("Logical operations require numeric operands in the cosmic void".to_string())),
-                        };
-                        
-                        Ok(Value::Number(if left_bool && right_bool { 1 } else { 0 }))
-                    },
-                    LogicalOperator::Or => {
-                        if left_bool {
-                            return Ok(Value::Number(1));
+                    LogicalOperator::Not => Ok(Value::Bool(!left_bool)),
+                    LogicalOperator::And | LogicalOperator::Or => {
+                        if left_bool == matches!(op, LogicalOperator::Or) {
+                            return Ok(Value::Bool(left_bool));
                         }
-                        
-                        let right = right.as_ref().ok_or_else(|| 
-                            ParseError::InvalidValue("OR operator requires a right operand in the ritual".to_string())
+
+                        let right = right.as_ref().ok_or_else(||
+                            RuntimeError::InvalidOperation(format!("'{}' requires a right operand in the ritual", op))
                         )?;
-                        
-                        let right_val = right.eval(env)?;
-                        let right_bool = match &right_val {
-                            Value::Number(n) => *n != 0,
-                            _ => return Err(ParseError::InvalidValue("Logical operations require numeric operands in the cosmic void".to_string())),
-                        };
-                        
-                        Ok(Value::Number(if left_bool || right_bool { 1 } else { 0 }))
+                        Ok(Value::Bool(right.eval(env)?.truthiness().map_err(ParseError::from)?))
                     },
                 }
             },
-            Expression::ArrayAccess { name, index } => {
-                if let Some(Value::Array(arr)) = env.get(name) {
-                    let idx_val = index.eval(env)?;
-                    match idx_val {
-                        Value::Number(idx) => {
-                            if idx < 0 || idx as usize >= arr.len() {
-                                Err(ParseError::InvalidValue(format!("Array index {} out of bounds in the matrix for array '{}'", idx, name)))
-                            } else {
-                                Ok(arr[idx as usize].clone())
-                            }
-                        },
-                        _ => Err(ParseError::InvalidValue("Array index must be a number in the cosmic void".to_string())),
-                    }
-                } else {
-                    Err(ParseError::InvalidValue(format!("Array '{}' not found in the cosmic void", name)))
-                }
+            ExpressionKind::ArrayAccess { target, index } => {
+                let target = target.eval(env)?;
+                let index = index.eval(env)?;
+                target.index(&index).map_err(ParseError::from)
             },
-            Expression::StructAccess { name, field } => {
-                if let Some(Value::Struct(_, fields)) = env.get(name) {
-                    if let Some((_, value)) = fields.iter().find(|(f, _)| f == field) {
-                        Ok(value.clone())
-                    } else {
-                        Err(ParseError::InvalidValue(format!("Field '{}' not found in struct '{}' in the matrix", field, name)))
-                    }
-                } else {
-                    Err(ParseError::InvalidValue(format!("Struct '{}' not found in the cosmic void", name)))
-                }
+            ExpressionKind::StructAccess { target, field } => {
+                target.eval(env)?.field(field).map_err(ParseError::from)
             },
-            Expression::FunctionCall { name, args: _ } => {
+            ExpressionKind::FunctionCall { name, args: _ } => {
                 let function_name = name.clone();
-                Err(ParseError::InvalidValue(format!(
+                Err(RuntimeError::InvalidOperation(format!(
                     "Function call '{}' cannot be evaluated directly in this context of the ritual",
                     function_name
-                )))
+                )).into())
             },
-            Expression::Equals(_, _) => {
-                Err(ParseError::InvalidValue("Equals is not an evaluable expression in the ritual".to_string()))
+            ExpressionKind::CallValue { .. } => {
+                Err(RuntimeError::InvalidOperation("Function calls cannot be evaluated outside the ritual runtime".to_string()).into())
             },
-            Expression::StructInstance { struct_name, fields } => {
+            ExpressionKind::Equals(_, _) => {
+                Err(RuntimeError::InvalidOperation("Equals is not an evaluable expression in the ritual".to_string()).into())
+            },
+            ExpressionKind::StructInstance { struct_name, fields } => {
                 if let Some(Value::Struct(name, existing_fields)) = env.get(struct_name) {
                     let mut new_fields = existing_fields.clone();
                     for (field, value) in fields {
@@ -351,22 +651,47 @@ impl Expression {
                     }
                     Ok(Value::Struct(name.clone(), new_fields))
                 } else {
-                    Err(ParseError::InvalidValue(format!("Struct '{}' not found in the cosmic void", struct_name)))
+                    Err(RuntimeError::TypeError(format!("Struct '{}' not found in the cosmic void", struct_name)).into())
                 }
             },
-            Expression::InOperator { left, right } => {
+            ExpressionKind::InOperator { left, right } => {
                 let left_val = left.eval(env)?;
                 let right_val = right.eval(env)?;
                 
                 match (&left_val, &right_val) {
                     (Value::Text(item), Value::Array(arr)) => {
-                        Ok(Value::Number(if arr.contains(&Value::Text(item.clone())) { 1 } else { 0 }))
+                        Ok(Value::Bool(arr.contains(&Value::Text(item.clone()))))
                     },
-                    (Value::Number(item), Value::Array(arr)) => {
-                        Ok(Value::Number(if arr.contains(&Value::Number(*item)) { 1 } else { 0 }))
+                    (item, Value::Array(arr)) if item.is_number() || matches!(item, Value::Bool(_)) => {
+                        Ok(Value::Bool(arr.contains(item)))
                     },
-                    _ => Err(ParseError::InvalidValue("Operador 'in' só pode ser usado com arrays no reino dos murlocs".to_string())),
+                    (key, map @ Value::Map(_)) => Ok(Value::Bool(map.map_get(key).is_some())),
+                    _ => Err(RuntimeError::TypeError(format!(
+                        "'blgr' looks for a value in an array or a map, not in {}", right_val
+                    )).into()),
+                }
+            },
+            ExpressionKind::Array(elements) => {
+                elements.iter().map(|element| element.eval(env)).collect::<Result<Vec<_>, _>>().map(Value::Array)
+            },
+            ExpressionKind::Function { .. } => {
+                Err(RuntimeError::InvalidOperation("A function cannot be created outside the ritual runtime".to_string()).into())
+            },
+            ExpressionKind::Map(entries) => {
+                let mut map = Vec::new();
+                for (key, value) in entries {
+                    Value::map_insert(&mut map, key.eval(env)?, value.eval(env)?)?;
                 }
+                Ok(Value::Map(map))
+            },
+            ExpressionKind::Await(_) => {
+                Err(RuntimeError::InvalidOperation("A future cannot be awaited outside the ritual runtime".to_string()).into())
+            },
+            ExpressionKind::Read(_) => {
+                Err(RuntimeError::InvalidOperation("Input cannot be read outside the ritual runtime".to_string()).into())
+            },
+            ExpressionKind::Spawn(_) | ExpressionKind::Join(_) => {
+                Err(RuntimeError::InvalidOperation("Threads cannot be started or joined outside the ritual runtime".to_string()).into())
             },
         }
     }
@@ -374,25 +699,73 @@ impl Expression {
 
 impl BinaryOperator {
     pub fn apply(&self, left: &Value, right: &Value) -> Result<Value, ParseError> {
-        match self {
-            BinaryOperator::Add => match (left, right) {
-                (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a + b)),
-                (Value::Text(a), Value::Text(b)) => Ok(Value::Text(a.clone() + b)),
-                _ => Err(ParseError::InvalidValue("Invalid types for Add in the cosmic void".to_string())),
-            },
-            _ => unimplemented!(),
+        if let Some(result) = self.apply_numeric(left, right) {
+            return result.map_err(ParseError::from);
         }
+        match (self, left, right) {
+            (BinaryOperator::Add, Value::Text(a), Value::Text(b)) => Ok(Value::Text(a.clone() + b)),
+            _ => Err(RuntimeError::TypeError(format!("'{}' cannot be applied to {} and {}", self, left, right)).into()),
+        }
+    }
+
+    /// Applies the operator to two numbers of any width. Results that overflow
+    /// grow into a wider variant and shrink back once they fit; a float on
+    /// either side makes the result a float. `None` when either operand is not
+    /// a number.
+    pub fn apply_numeric(&self, left: &Value, right: &Value) -> Option<Result<Value, RuntimeError>> {
+        if matches!(left, Value::Float(_)) || matches!(right, Value::Float(_)) {
+            let (a, b) = (left.to_f64()?, right.to_f64()?);
+            return Some(match self {
+                BinaryOperator::Divide | BinaryOperator::Modulo if b == 0.0 => Err(RuntimeError::DivisionByZero),
+                BinaryOperator::Add => Ok(Value::Float(a + b)),
+                BinaryOperator::Subtract => Ok(Value::Float(a - b)),
+                BinaryOperator::Multiply => Ok(Value::Float(a * b)),
+                BinaryOperator::Divide => Ok(Value::Float(a / b)),
+                BinaryOperator::Modulo => Ok(Value::Float(a % b)),
+            });
+        }
+
+        if let (Some(a), Some(b)) = (left.as_i64(), right.as_i64()) {
+            let result = match self {
+                BinaryOperator::Divide | BinaryOperator::Modulo if b == 0 => return Some(Err(RuntimeError::DivisionByZero)),
+                BinaryOperator::Add => a.checked_add(b),
+                BinaryOperator::Subtract => a.checked_sub(b),
+                BinaryOperator::Multiply => a.checked_mul(b),
+                BinaryOperator::Divide => a.checked_div(b),
+                BinaryOperator::Modulo => a.checked_rem(b),
+            };
+            if let Some(n) = result {
+                return Some(Ok(Value::from_i64(n)));
+            }
+        }
+
+        let (a, b) = (left.to_bigint()?, right.to_bigint()?);
+        Some(match self {
+            BinaryOperator::Divide | BinaryOperator::Modulo if b.sign() == Sign::NoSign => Err(RuntimeError::DivisionByZero),
+            BinaryOperator::Add => Ok(Value::from_bigint(a + b)),
+            BinaryOperator::Subtract => Ok(Value::from_bigint(a - b)),
+            BinaryOperator::Multiply => Ok(Value::from_bigint(a * b)),
+            BinaryOperator::Divide => Ok(Value::from_bigint(a / b)),
+            BinaryOperator::Modulo => Ok(Value::from_bigint(a % b)),
+        })
     }
 }
 
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Value::Number(a), Value::Number(b)) => a == b,
+            (a, b) if a.is_number() && b.is_number() => a.compare_numbers(b) == Some(Ordering::Equal),
+            (Value::Bool(a), Value::Bool(b)) => a == b,
             (Value::Text(a), Value::Text(b)) => a == b,
             (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => {
+                a.len() == b.len() && a.iter().all(|(key, value)| other.map_get(key) == Some(value))
+            },
             (Value::Struct(_, a), Value::Struct(_, b)) => a == b,
+            (Value::Enum(a, x, p), Value::Enum(b, y, q)) => a == b && x == y && p == q,
             (Value::Thread(a), Value::Thread(b)) => a == b,
+            (Value::Channel(a), Value::Channel(b)) => a == b,
+            (Value::Function(a), Value::Function(b)) => Arc::ptr_eq(a, b),
             _ => false,
         }
     }
@@ -400,12 +773,12 @@ impl PartialEq for Value {
 
 impl Statement {
     pub fn eval(&self, _env: &HashMap<String, Value>) -> Result<Value, ParseError> {
-        match self {
-            Statement::CallFunction { name, args: _ } => {
-                Err(ParseError::InvalidValue(format!(
+        match &self.kind {
+            StatementKind::CallFunction { name, args: _ } => {
+                Err(RuntimeError::InvalidOperation(format!(
                     "Function call '{}' cannot be evaluated directly in this context of the ritual",
                     name
-                )))
+                )).into())
             },
             _ => unimplemented!(),
         }
@@ -413,18 +786,73 @@ impl Statement {
 }
 
 impl PartialOrd for Value {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         match (self, other) {
-            (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+            (a, b) if a.is_number() && b.is_number() => a.compare_numbers(b),
             (Value::Text(a), Value::Text(b)) => a.partial_cmp(b),
-            (Value::Thread(a), Value::Thread(b)) => a.partial_cmp(b),
+            (Value::Thread(a), Value::Thread(b)) => a.id.partial_cmp(&b.id),
+            (Value::Channel(a), Value::Channel(b)) => a.id.partial_cmp(&b.id),
             _ => None,
         }
     }
 }
 
 #[derive(Debug, Clone)]
-pub enum Expression {
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
+impl Expression {
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    /// Walks the statements of every function and thread body written
+    /// inside this expression. See [`Statement::walk`].
+    pub fn walk_statements<E>(&self, visit: &mut impl FnMut(&Statement) -> Result<(), E>) -> Result<(), E> {
+        let mut expressions: Vec<&Expression> = Vec::new();
+        match &self.kind {
+            ExpressionKind::BinaryOp { left, right, .. }
+            | ExpressionKind::Comparison { left, right, .. }
+            | ExpressionKind::InOperator { left, right } => expressions.extend([&**left, &**right]),
+            ExpressionKind::LogicalOp { left, right, .. } => {
+                expressions.push(left);
+                expressions.extend(right.as_deref());
+            },
+            ExpressionKind::ArrayAccess { target, index } => expressions.extend([&**target, &**index]),
+            ExpressionKind::StructAccess { target: value, .. }
+            | ExpressionKind::Await(value)
+            | ExpressionKind::Join(value) => expressions.push(value),
+            ExpressionKind::Read(prompt) => expressions.extend(prompt.as_deref()),
+            ExpressionKind::StructInstance { fields, .. } => expressions.extend(fields.iter().map(|(_, value)| value)),
+            ExpressionKind::FunctionCall { args: values, .. } | ExpressionKind::Array(values) => expressions.extend(values),
+            ExpressionKind::CallValue { callee, args } => {
+                expressions.push(callee);
+                expressions.extend(args);
+            },
+            ExpressionKind::Map(entries) => {
+                for (key, value) in entries {
+                    expressions.extend([key, value]);
+                }
+            },
+            ExpressionKind::Function { body, .. } | ExpressionKind::Spawn(body) => {
+                for statement in body {
+                    statement.walk(visit)?;
+                }
+            },
+            ExpressionKind::Equals(..) | ExpressionKind::Literal(_) | ExpressionKind::Variable(_) => {},
+        }
+
+        for expression in expressions {
+            expression.walk_statements(visit)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ExpressionKind {
     Equals(String, i32),
     BinaryOp {
         left: Box<Expression>,
@@ -444,11 +872,11 @@ pub enum Expression {
     Literal(Value),
     Variable(String),
     ArrayAccess {
-        name: String,
+        target: Box<Expression>,
         index: Box<Expression>,
     },
     StructAccess {
-        name: String,
+        target: Box<Expression>,
         field: String,
     },
     StructInstance {
@@ -459,10 +887,29 @@ pub enum Expression {
         name: String,
         args: Vec<Expression>,
     },
+    /// A call to the function a `callee` expression evaluates to, such as
+    /// `grrrblbl fs[0](21)`.
+    CallValue {
+        callee: Box<Expression>,
+        args: Vec<Expression>,
+    },
     InOperator {
         left: Box<Expression>,
         right: Box<Expression>,
     },
+    Array(Vec<Expression>),
+    Map(Vec<(Expression, Expression)>),
+    Function {
+        params: Vec<String>,
+        body: Vec<Statement>,
+    },
+    Await(Box<Expression>),
+    /// `mrglisn`, with an optional prompt shown before reading.
+    Read(Option<Box<Expression>>),
+    /// `splurg` used as a value: starts the body on a thread and yields its handle.
+    Spawn(Vec<Statement>),
+    /// `mrgurl` used as a value: waits for a thread handle, or an array of them.
+    Join(Box<Expression>),
 }
 
 #[derive(Debug, Clone)]
@@ -491,6 +938,166 @@ pub enum LogicalOperator {
     Not,
 }
 
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Modulo => "%",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl ComparisonOperator {
+    /// Compares two values. Ordering values that have no order between them,
+    /// such as text and a number, is a type error; numbers that cannot be
+    /// ordered (NaN) compare false.
+    pub fn apply(&self, left: &Value, right: &Value) -> Result<bool, RuntimeError> {
+        let ordering = match self {
+            ComparisonOperator::Equals => return Ok(left == right),
+            ComparisonOperator::NotEquals => return Ok(left != right),
+            _ => left.partial_cmp(right),
+        };
+        let Some(ordering) = ordering else {
+            if left.is_number() && right.is_number() {
+                return Ok(false);
+            }
+            return Err(RuntimeError::TypeError(format!("'{}' cannot compare {} and {}", self, left, right)));
+        };
+        Ok(match self {
+            ComparisonOperator::LessThan => ordering.is_lt(),
+            ComparisonOperator::GreaterThan => ordering.is_gt(),
+            ComparisonOperator::LessThanOrEqual => ordering.is_le(),
+            ComparisonOperator::GreaterThanOrEqual => ordering.is_ge(),
+            ComparisonOperator::Equals | ComparisonOperator::NotEquals => unreachable!("equality is handled above"),
+        })
+    }
+}
+
+impl fmt::Display for ComparisonOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            ComparisonOperator::Equals => "==",
+            ComparisonOperator::NotEquals => "!=",
+            ComparisonOperator::LessThan => "<",
+            ComparisonOperator::GreaterThan => ">",
+            ComparisonOperator::LessThanOrEqual => "<=",
+            ComparisonOperator::GreaterThanOrEqual => ">=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl fmt::Display for LogicalOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            LogicalOperator::And => "&&",
+            LogicalOperator::Or => "||",
+            LogicalOperator::Not => "!",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// One `grlblgl` arm of a `murrrgh`: it runs when any of its patterns
+/// matches and the guard, if there is one, holds.
+#[derive(Debug, Clone)]
+pub struct MatchCase {
+    pub patterns: Vec<Pattern>,
+    pub guard: Option<Expression>,
+    pub body: Vec<Statement>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// `_`
+    Wildcard,
+    /// A bare name, bound to the matched value.
+    Binding(String),
+    Literal(Value),
+    /// `low..high`, both ends included.
+    Range(Value, Value),
+    /// `Shape.Circle(r)`; without parentheses the payload is not inspected.
+    Variant {
+        enum_name: String,
+        variant: String,
+        fields: Option<Vec<Pattern>>,
+    },
+    /// `Point { x: 0, y }`; fields left out are not inspected.
+    Struct {
+        name: String,
+        fields: Vec<(String, Pattern)>,
+    },
+    /// `[first, second, ..rest]`
+    Array {
+        elements: Vec<Pattern>,
+        rest: Option<Box<Pattern>>,
+    },
+}
+
+impl Pattern {
+    /// Tests `value` against the pattern, pushing what it binds onto
+    /// `bindings`. On a failed match `bindings` may hold partial results.
+    pub fn matches(&self, value: &Value, bindings: &mut Vec<(String, Value)>) -> bool {
+        match (self, value) {
+            (Pattern::Wildcard, _) => true,
+            (Pattern::Binding(name), _) => {
+                bindings.push((name.clone(), value.clone()));
+                true
+            },
+            (Pattern::Literal(literal), _) => value == literal,
+            (Pattern::Range(low, high), _) => low <= value && value <= high,
+            (Pattern::Variant { enum_name, variant, fields }, Value::Enum(name, tag, payload)) => {
+                enum_name == name && variant == tag && match fields {
+                    Some(fields) => fields.len() == payload.len()
+                        && fields.iter().zip(payload).all(|(field, value)| field.matches(value, bindings)),
+                    None => true,
+                }
+            },
+            (Pattern::Struct { name, fields }, Value::Struct(struct_name, values)) => {
+                name == struct_name && fields.iter().all(|(field, pattern)| {
+                    values.iter()
+                        .find(|(name, _)| name == field)
+                        .is_some_and(|(_, value)| pattern.matches(value, bindings))
+                })
+            },
+            (Pattern::Array { elements, rest }, Value::Array(items)) => {
+                let fits = match rest {
+                    Some(_) => items.len() >= elements.len(),
+                    None => items.len() == elements.len(),
+                };
+                fits && elements.iter().zip(items).all(|(pattern, item)| pattern.matches(item, bindings))
+                    && rest.as_ref().is_none_or(|rest| rest.matches(&Value::Array(items[elements.len()..].to_vec()), bindings))
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether the pattern matches every value.
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self, Pattern::Wildcard | Pattern::Binding(_))
+    }
+}
+
+/// One step into a value on the left of an assignment: `[index]` or `.field`.
+#[derive(Debug, Clone)]
+pub enum PathSegment {
+    Index(Expression),
+    Field(String),
+}
+
+impl PathSegment {
+    pub fn index(&self) -> Option<&Expression> {
+        match self {
+            PathSegment::Index(index) => Some(index),
+            PathSegment::Field(_) => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ImportSpecifier {
     Default(String),           // import x from 'y'
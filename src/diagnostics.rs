@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::fs;
+
+use crate::ast::Span;
+use crate::interpreter::{RuntimeError, describe_trace};
+use crate::stdlib;
+use crate::value_parser::ParseError;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const TAB_WIDTH: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Option<Span>,
+    pub label: Option<String>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message.into())
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message.into())
+    }
+
+    fn new(severity: Severity, message: String) -> Self {
+        Self {
+            severity,
+            message,
+            span: None,
+            label: None,
+            notes: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(err: &ParseError) -> Self {
+        let (err, trace) = match err {
+            ParseError::Traced(inner, trace) => (inner.as_ref(), trace.as_slice()),
+            _ => (err, &[][..]),
+        };
+        let (inner, span) = match err {
+            ParseError::Spanned(inner, span) => (inner.as_ref(), Some(span.clone())),
+            _ => (err, None),
+        };
+
+        let mut diagnostic = Diagnostic::error(inner.to_string());
+        diagnostic.span = span;
+        for line in describe_trace(trace) {
+            diagnostic = diagnostic.with_note(line);
+        }
+
+        match inner {
+            ParseError::UnclosedBlock(_) => diagnostic
+                .with_label("opened here")
+                .with_note("the scroll ended while looking for its 'grl'")
+                .with_help("add a 'grl' after the last statement of the block"),
+            ParseError::RuntimeError(RuntimeError::Break | RuntimeError::Continue) => diagnostic
+                .with_help("'flurg' and 'flllurlog' only work inside a loop"),
+            ParseError::RuntimeError(RuntimeError::Return(_)) => diagnostic
+                .with_help("'grrrtn' only works inside a function"),
+            _ => diagnostic,
+        }
+    }
+}
+
+/// Renders diagnostics the way rustc does: a header, the location, the
+/// offending source line with the span underlined, then notes and help.
+pub struct Emitter {
+    color: bool,
+    sources: HashMap<String, String>,
+}
+
+impl Emitter {
+    pub fn new(color: bool) -> Self {
+        Self {
+            color,
+            sources: HashMap::new(),
+        }
+    }
+
+    /// Registers source text for `file`. Files that were never registered are
+    /// looked up among the bundled modules, then read from disk.
+    pub fn add_source(&mut self, file: impl Into<String>, source: impl Into<String>) {
+        self.sources.insert(file.into(), source.into());
+    }
+
+    pub fn emit(&self, diagnostic: &Diagnostic) {
+        eprintln!("{}", self.render(diagnostic));
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let (title, accent) = match diagnostic.severity {
+            Severity::Error => ("error", RED),
+            Severity::Warning => ("warning", YELLOW),
+        };
+
+        let mut out = format!(
+            "{}{}\n",
+            self.paint(accent, title),
+            self.paint(BOLD, &format!(": {}", diagnostic.message))
+        );
+
+        let line_number = diagnostic.span.as_ref().map(|s| s.line.to_string()).unwrap_or_default();
+        let pad = " ".repeat(line_number.len());
+
+        if let Some(span) = &diagnostic.span {
+            out.push_str(&format!("{}{} {}\n", pad, self.paint(BLUE, "-->"), span));
+
+            if let Some(line) = self.source_line(span) {
+                let (text, offset, width) = underline(&line, span);
+                out.push_str(&format!("{} {}\n", pad, self.paint(BLUE, "|")));
+                out.push_str(&format!("{} {} {}\n", self.paint(BLUE, &line_number), self.paint(BLUE, "|"), text));
+
+                let mut marker = self.paint(accent, &"^".repeat(width));
+                if let Some(label) = &diagnostic.label {
+                    marker.push(' ');
+                    marker.push_str(&self.paint(accent, label));
+                }
+                out.push_str(&format!("{} {} {}{}\n", pad, self.paint(BLUE, "|"), " ".repeat(offset), marker));
+
+                if !diagnostic.notes.is_empty() || !diagnostic.help.is_empty() {
+                    out.push_str(&format!("{} {}\n", pad, self.paint(BLUE, "|")));
+                }
+            }
+        }
+
+        for note in &diagnostic.notes {
+            out.push_str(&format!("{} {} {}: {}\n", pad, self.paint(BLUE, "="), self.paint(BOLD, "note"), note));
+        }
+        for help in &diagnostic.help {
+            out.push_str(&format!("{} {} {}: {}\n", pad, self.paint(BLUE, "="), self.paint(BOLD, "help"), help));
+        }
+
+        out
+    }
+
+    fn source_line(&self, span: &Span) -> Option<String> {
+        let line_index = span.line.checked_sub(1)?;
+        match self.sources.get(span.file.as_str()) {
+            Some(source) => source.lines().nth(line_index).map(str::to_string),
+            None => match stdlib::source(span.file.as_str()) {
+                Some(source) => source.lines().nth(line_index).map(str::to_string),
+                None => fs::read_to_string(span.file.as_str()).ok()?.lines().nth(line_index).map(str::to_string),
+            },
+        }
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Expands tabs in `line` and works out where the underline for `span` starts
+/// and how wide it is, in display columns.
+fn underline(line: &str, span: &Span) -> (String, usize, usize) {
+    let width_of = |c: char| if c == '\t' { TAB_WIDTH } else { 1 };
+    let start = span.column.saturating_sub(1);
+
+    let text: String = line
+        .chars()
+        .map(|c| if c == '\t' { " ".repeat(TAB_WIDTH) } else { c.to_string() })
+        .collect();
+    let offset = line.chars().take(start).map(width_of).sum::<usize>()
+        + start.saturating_sub(line.chars().count());
+    let width = line.chars().skip(start).take(span.length).map(width_of).sum::<usize>();
+
+    (text, offset, width.max(1))
+}
@@ -1,12 +1,13 @@
-use crate::ast::{Expression, BinaryOperator, ComparisonOperator, LogicalOperator, Value};
-use crate::lexer::Token;
+use crate::ast::{Expression, ExpressionKind, BinaryOperator, ComparisonOperator, LogicalOperator, Value};
+use crate::lexer::{Token, TokenStream, describe_token, keyword_spelling};
 use crate::value_parser::ParseError;
+use crate::parser::{expect_token, parse_expression_block, parse_function_expression};
 
-pub fn parse_expression(tokens: &[Token], i: &mut usize) -> Result<Expression, ParseError> {
+pub fn parse_expression(tokens: &TokenStream, i: &mut usize) -> Result<Expression, ParseError> {
     parse_logical_or(tokens, i)
 }
 
-fn parse_logical_or(tokens: &[Token], i: &mut usize) -> Result<Expression, ParseError> {
+fn parse_logical_or(tokens: &TokenStream, i: &mut usize) -> Result<Expression, ParseError> {
     let mut expr = parse_logical_and(tokens, i)?;
 
     while *i < tokens.len() {
@@ -14,19 +15,21 @@ fn parse_logical_or(tokens: &[Token], i: &mut usize) -> Result<Expression, Parse
             Token::Or => {
                 *i += 1;
                 let right = parse_logical_and(tokens, i)?;
-                expr = Expression::LogicalOp {
+                let span = expr.span.to(&right.span);
+                expr = Expression::new(ExpressionKind::LogicalOp {
                     left: Box::new(expr),
                     right: Some(Box::new(right)),
                     op: LogicalOperator::Or,
-                };
+                }, span);
             }
             Token::Keyword(kw) if kw == "in" => {
                 *i += 1;
                 let right = parse_logical_and(tokens, i)?;
-                expr = Expression::InOperator {
+                let span = expr.span.to(&right.span);
+                expr = Expression::new(ExpressionKind::InOperator {
                     left: Box::new(expr),
                     right: Box::new(right),
-                };
+                }, span);
             }
             _ => break,
         }
@@ -35,7 +38,7 @@ fn parse_logical_or(tokens: &[Token], i: &mut usize) -> Result<Expression, Parse
     Ok(expr)
 }
 
-fn parse_logical_and(tokens: &[Token], i: &mut usize) -> Result<Expression, ParseError> {
+fn parse_logical_and(tokens: &TokenStream, i: &mut usize) -> Result<Expression, ParseError> {
     let mut expr = parse_comparison(tokens, i)?;
 
     while *i < tokens.len() {
@@ -43,11 +46,12 @@ fn parse_logical_and(tokens: &[Token], i: &mut usize) -> Result<Expression, Pars
             Token::And => {
                 *i += 1;
                 let right = parse_comparison(tokens, i)?;
-                expr = Expression::LogicalOp {
+                let span = expr.span.to(&right.span);
+                expr = Expression::new(ExpressionKind::LogicalOp {
                     left: Box::new(expr),
                     right: Some(Box::new(right)),
                     op: LogicalOperator::And,
-                };
+                }, span);
             }
             _ => break,
         }
@@ -56,7 +60,7 @@ fn parse_logical_and(tokens: &[Token], i: &mut usize) -> Result<Expression, Pars
     Ok(expr)
 }
 
-fn parse_comparison(tokens: &[Token], i: &mut usize) -> Result<Expression, ParseError> {
+fn parse_comparison(tokens: &TokenStream, i: &mut usize) -> Result<Expression, ParseError> {
     let mut expr = parse_addition(tokens, i)?;
 
     while *i < tokens.len() {
@@ -71,17 +75,18 @@ fn parse_comparison(tokens: &[Token], i: &mut usize) -> Result<Expression, Parse
         };
         *i += 1;
         let right = parse_addition(tokens, i)?;
-        expr = Expression::Comparison {
+        let span = expr.span.to(&right.span);
+        expr = Expression::new(ExpressionKind::Comparison {
             left: Box::new(expr),
             right: Box::new(right),
             op,
-        };
+        }, span);
     }
 
     Ok(expr)
 }
 
-fn parse_addition(tokens: &[Token], i: &mut usize) -> Result<Expression, ParseError> {
+fn parse_addition(tokens: &TokenStream, i: &mut usize) -> Result<Expression, ParseError> {
     let mut expr = parse_multiplication(tokens, i)?;
 
     while *i < tokens.len() {
@@ -92,17 +97,18 @@ fn parse_addition(tokens: &[Token], i: &mut usize) -> Result<Expression, ParseEr
         };
         *i += 1;
         let right = parse_multiplication(tokens, i)?;
-        expr = Expression::BinaryOp {
+        let span = expr.span.to(&right.span);
+        expr = Expression::new(ExpressionKind::BinaryOp {
             left: Box::new(expr),
             right: Box::new(right),
             op,
-        };
+        }, span);
     }
 
     Ok(expr)
 }
 
-fn parse_multiplication(tokens: &[Token], i: &mut usize) -> Result<Expression, ParseError> {
+fn parse_multiplication(tokens: &TokenStream, i: &mut usize) -> Result<Expression, ParseError> {
     let mut expr = parse_unary(tokens, i)?;
 
     while *i < tokens.len() {
@@ -114,75 +120,108 @@ fn parse_multiplication(tokens: &[Token], i: &mut usize) -> Result<Expression, P
         };
         *i += 1;
         let right = parse_unary(tokens, i)?;
-        expr = Expression::BinaryOp {
+        let span = expr.span.to(&right.span);
+        expr = Expression::new(ExpressionKind::BinaryOp {
             left: Box::new(expr),
             right: Box::new(right),
             op,
-        };
+        }, span);
     }
 
     Ok(expr)
 }
 
-fn parse_unary(tokens: &[Token], i: &mut usize) -> Result<Expression, ParseError> {
-    match &tokens[*i] {
-        Token::Not => {
+fn parse_unary(tokens: &TokenStream, i: &mut usize) -> Result<Expression, ParseError> {
+    let span = tokens.span(*i);
+    match tokens.get(*i) {
+        Some(Token::Not) => {
             *i += 1;
-            let expr = parse_primary(tokens, i)?;
-            Ok(Expression::LogicalOp {
+            let expr = parse_postfix(tokens, i)?;
+            let span = span.to(&expr.span);
+            Ok(Expression::new(ExpressionKind::LogicalOp {
                 left: Box::new(expr),
                 right: None,
                 op: LogicalOperator::Not,
-            })
+            }, span))
         }
-        Token::Minus => {
+        Some(Token::Minus) => {
             *i += 1;
-            let expr = parse_primary(tokens, i)?;
-            Ok(Expression::BinaryOp {
-                left: Box::new(Expression::Literal(Value::Number(0))),
+            let expr = parse_postfix(tokens, i)?;
+            let span = span.to(&expr.span);
+            Ok(Expression::new(ExpressionKind::BinaryOp {
+                left: Box::new(Expression::new(ExpressionKind::Literal(Value::Number(0)), span.clone())),
                 right: Box::new(expr),
                 op: BinaryOperator::Subtract,
-            })
+            }, span))
+        }
+        _ => parse_postfix(tokens, i),
+    }
+}
+
+/// Applies `[index]` and `.field` to whatever precedes them, so accesses
+/// chain (`clan[0].name`) and work on any operand (`grrrblbl f(x)[0]`).
+fn parse_postfix(tokens: &TokenStream, i: &mut usize) -> Result<Expression, ParseError> {
+    let mut expr = parse_primary(tokens, i)?;
+
+    loop {
+        match tokens.get(*i) {
+            Some(Token::LeftBracket) => {
+                let index = parse_index(tokens, i)?;
+                let span = expr.span.to(&tokens.span(*i - 1));
+                expr = Expression::new(ExpressionKind::ArrayAccess {
+                    target: Box::new(expr),
+                    index: Box::new(index),
+                }, span);
+            }
+            Some(Token::Dot) => {
+                *i += 1;
+                let field = match tokens.get(*i) {
+                    Some(Token::Identifier(field)) => field.clone(),
+                    tok => return Err(ParseError::UnexpectedToken(format!("Expected a field name after '.', found {}", describe_token(tok))).with_span(&tokens.span(*i))),
+                };
+                *i += 1;
+                let span = expr.span.to(&tokens.span(*i - 1));
+                expr = Expression::new(ExpressionKind::StructAccess {
+                    target: Box::new(expr),
+                    field,
+                }, span);
+            }
+            _ => return Ok(expr),
         }
-        _ => parse_primary(tokens, i),
     }
 }
 
-fn parse_primary(tokens: &[Token], i: &mut usize) -> Result<Expression, ParseError> {
+fn parse_primary(tokens: &TokenStream, i: &mut usize) -> Result<Expression, ParseError> {
     if *i >= tokens.len() {
-        return Err(ParseError::UnexpectedToken(format!("Unexpected end of tokens in the cosmic void at position {}", i)))
+        return Err(ParseError::UnexpectedToken("Expected a value, found end of file".to_string()).with_span(&tokens.span(*i)))
     }
 
+    let start = *i;
+    let span = tokens.span(start);
     match &tokens[*i] {
         Token::Number(n) => {
             *i += 1;
-            if let Ok(num) = n.parse::<i32>() {
-                Ok(Expression::Literal(Value::Number(num)))
-            } else if let Ok(num) = n.parse::<i64>() {
-                Ok(Expression::Literal(Value::NumberI64(num)))
-            } else if let Ok(num) = n.parse::<num_bigint::BigInt>() {
-                Ok(Expression::Literal(Value::NumberBig(num)))
-            } else {
-                Err(ParseError::InvalidValue(format!("Invalid number in the cosmic void: {} at position {}", n, i)))
+            match Value::parse_number(n) {
+                Some(num) => Ok(Expression::new(ExpressionKind::Literal(num), span)),
+                None => Err(ParseError::InvalidValue(format!("Invalid number in the cosmic void: {}", n)).with_span(&span)),
             }
         },
         Token::StringLiteral(s) => {
             *i += 1;
-            Ok(Expression::Literal(Value::Text(s.clone())))
+            Ok(Expression::new(ExpressionKind::Literal(Value::Text(s.clone())), span))
         },
         Token::Keyword(kw) => {
             *i += 1;
             match kw.as_str() {
                 "call" => {
                     if *i >= tokens.len() {
-                        return Err(ParseError::UnexpectedToken(format!("Unexpected end after 'grrrblbl' in the ritual at position {}", i)))
+                        return Err(ParseError::UnexpectedToken("Expected a function name after 'grrrblbl', found end of file".to_string()).with_span(&tokens.span(*i)))
                     }
                     
-                    let func_name = match &tokens[*i] {
-                        Token::Identifier(name) => name.clone(),
-                        tok => return Err(ParseError::UnexpectedToken(format!("Expected identifier after 'grrrblbl', found {:?} in the ritual at position {}", tok, i))),
-                    };
-                    *i += 1;
+                    let func_name = parse_function_name(tokens, i)?;
+                    if matches!(tokens.get(*i), Some(Token::LeftBracket)) {
+                        return parse_value_call(tokens, i, &func_name, start);
+                    }
                     
                     let mut args = Vec::new();
                     
@@ -191,35 +230,42 @@ fn parse_primary(tokens: &[Token], i: &mut usize) -> Result<Expression, ParseErr
                         *i += 1;
                     }
                     
+                    let mut closed = false;
                     while *i < tokens.len() {
                         if has_parens && matches!(&tokens[*i], Token::RightParen) {
                             *i += 1;
+                            closed = true;
                             break;
                         }
+
+                        if has_parens {
+                            if matches!(&tokens[*i], Token::Comma) {
+                                *i += 1;
+                            } else {
+                                args.push(parse_expression(tokens, i)?);
+                            }
+                            continue;
+                        }
                         
                         if !has_parens && (*i >= tokens.len() || matches!(&tokens[*i], Token::Keyword(_))) {
                             break;
                         }
                         
+                        let arg_span = tokens.span(*i);
                         match &tokens[*i] {
                             Token::Identifier(var_name) => {
-                                args.push(Expression::Variable(var_name.clone()));
+                                args.push(Expression::new(ExpressionKind::Variable(var_name.clone()), arg_span));
                                 *i += 1;
                             },
                             Token::Number(num) => {
-                                if let Ok(n) = num.parse::<i32>() {
-                                    args.push(Expression::Literal(Value::Number(n)));
-                                } else if let Ok(n) = num.parse::<i64>() {
-                                    args.push(Expression::Literal(Value::NumberI64(n)));
-                                } else if let Ok(n) = num.parse::<num_bigint::BigInt>() {
-                                    args.push(Expression::Literal(Value::NumberBig(n)));
-                                } else {
-                                    return Err(ParseError::InvalidValue(format!("Invalid number in the cosmic void: {} at position {}", num, i)));
+                                match Value::parse_number(num) {
+                                    Some(n) => args.push(Expression::new(ExpressionKind::Literal(n), arg_span)),
+                                    None => return Err(ParseError::InvalidValue(format!("Invalid number in the cosmic void: {}", num)).with_span(&tokens.span(*i))),
                                 }
                                 *i += 1;
                             },
                             Token::StringLiteral(text) => {
-                                args.push(Expression::Literal(Value::Text(text.clone())));
+                                args.push(Expression::new(ExpressionKind::Literal(Value::Text(text.clone())), arg_span));
                                 *i += 1;
                             },
                             Token::Comma => {
@@ -228,61 +274,126 @@ fn parse_primary(tokens: &[Token], i: &mut usize) -> Result<Expression, ParseErr
                             _ => break,
                         }
                     }
+
+                    if has_parens && !closed {
+                        return Err(ParseError::MissingToken("Missing ')' to close the arguments in the ritual".to_string()).with_span(&tokens.span(*i)));
+                    }
                     
-                    Ok(Expression::FunctionCall { name: func_name, args })
+                    Ok(Expression::new(ExpressionKind::FunctionCall { name: func_name, args }, tokens.span_from(start, *i)))
+                }
+                "true" | "false" => {
+                    Ok(Expression::new(ExpressionKind::Literal(Value::Bool(kw == "true")), span))
+                }
+                "read" => {
+                    let prompt = match tokens.get(*i) {
+                        Some(Token::StringLiteral(_) | Token::LeftParen) => Some(Box::new(parse_primary(tokens, i)?)),
+                        _ => None,
+                    };
+                    Ok(Expression::new(ExpressionKind::Read(prompt), tokens.span_from(start, *i)))
                 }
-                _ => Err(ParseError::UnexpectedToken(format!("Unexpected keyword in the cosmic void: {} at position {}", kw, i))),
+                "await" => {
+                    let future = parse_unary(tokens, i)?;
+                    Ok(Expression::new(ExpressionKind::Await(Box::new(future)), tokens.span_from(start, *i)))
+                }
+                "spawn" => {
+                    let body = parse_expression_block(tokens, i)?;
+                    Ok(Expression::new(ExpressionKind::Spawn(body), span))
+                }
+                "wait" => {
+                    let thread = parse_unary(tokens, i)?;
+                    Ok(Expression::new(ExpressionKind::Join(Box::new(thread)), tokens.span_from(start, *i)))
+                }
+                "fn" => {
+                    let (params, body) = parse_function_expression(tokens, i)?;
+                    Ok(Expression::new(ExpressionKind::Function { params, body }, span))
+                }
+                _ => Err(ParseError::UnexpectedToken(format!("'{}' cannot start an expression", keyword_spelling(kw))).with_span(&span)),
             }
         },
         Token::Identifier(name) => {
             *i += 1;
-            if *i < tokens.len() && matches!(&tokens[*i], Token::Dot) {
-                *i += 1;
-                let field_name = match &tokens[*i] {
-                    Token::Identifier(field) => field.clone(),
-                    _ => return Err(ParseError::UnexpectedToken(format!("Esperado nome do campo após '.', encontrado {:?}", tokens[*i]))),
-                };
-                *i += 1;
-                Ok(Expression::StructAccess {
-                    name: name.clone(),
-                    field: field_name,
-                })
-            } else if *i < tokens.len() && matches!(&tokens[*i], Token::LeftBrace) {
+            // `ns.Point { ... }` builds a struct reached through a namespace import.
+            let mut struct_name = name.clone();
+            if let (Some(Token::Dot), Some(Token::Identifier(member)), Some(Token::LeftBrace)) =
+                (tokens.get(*i), tokens.get(*i + 1), tokens.get(*i + 2))
+            {
+                struct_name = format!("{}.{}", name, member);
+                *i += 2;
+            }
+            if *i < tokens.len() && matches!(&tokens[*i], Token::LeftBrace) {
                 *i += 1;
                 let mut fields = Vec::new();
                 
-                while *i < tokens.len() {
-                    if matches!(&tokens[*i], Token::RightBrace) {
-                        *i += 1;
-                        break;
+                while !matches!(tokens.get(*i), Some(Token::RightBrace)) {
+                    if *i >= tokens.len() {
+                        return Err(ParseError::MissingToken("Missing '}' to close the struct in the ritual".to_string()).with_span(&tokens.span(*i)));
                     }
-                    
+
                     let field_name = match &tokens[*i] {
                         Token::Identifier(name) => name.clone(),
-                        _ => return Err(ParseError::UnexpectedToken(format!("Expected field name, found {:?} in the matrix at position {}", tokens[*i], i))),
+                        _ => return Err(ParseError::UnexpectedToken(format!("Expected a field name, found {}", describe_token(Some(&tokens[*i])))).with_span(&tokens.span(*i))),
                     };
                     *i += 1;
                     
-                    expect_token_type(tokens, i, "Colon")?;
+                    expect_token(tokens, i, Token::Colon)?;
                     
                     let field_value = parse_expression(tokens, i)?;
                     fields.push((field_name, field_value));
                     
-                    if matches!(&tokens[*i], Token::Comma) {
-                        *i += 1;
-                    } else if !matches!(&tokens[*i], Token::RightBrace) {
-                        return Err(ParseError::UnexpectedToken(format!("Expected ',' or '}}', found {:?} in the matrix at position {}", tokens[*i], i)));
+                    match tokens.get(*i) {
+                        Some(Token::Comma) => *i += 1,
+                        Some(Token::RightBrace) => {}
+                        None => return Err(ParseError::MissingToken("Missing '}' to close the struct in the ritual".to_string()).with_span(&tokens.span(*i))),
+                        Some(tok) => return Err(ParseError::UnexpectedToken(format!("Expected ',' or '}}', found {}", describe_token(Some(tok)))).with_span(&tokens.span(*i))),
                     }
                 }
+                *i += 1;
                 
-                Ok(Expression::StructInstance {
-                    struct_name: name.clone(),
+                Ok(Expression::new(ExpressionKind::StructInstance {
+                    struct_name,
                     fields,
-                })
+                }, tokens.span_from(start, *i)))
             } else {
-                Ok(Expression::Variable(name.clone()))
+                Ok(Expression::new(ExpressionKind::Variable(name.clone()), span))
             }
         },
+        Token::LeftBrace => {
+            *i += 1;
+            let mut entries = Vec::new();
+            while !matches!(tokens.get(*i), Some(Token::RightBrace)) {
+                if *i >= tokens.len() {
+                    return Err(ParseError::MissingToken("Missing '}' to close map in the ritual".to_string()).with_span(&span));
+                }
+                let key = parse_expression(tokens, i)?;
+                expect_token(tokens, i, Token::Colon)?;
+                let value = parse_expression(tokens, i)?;
+                entries.push((key, value));
+                match tokens.get(*i) {
+                    Some(Token::Comma) => *i += 1,
+                    Some(Token::RightBrace) => {}
+                    tok => return Err(ParseError::UnexpectedToken(format!("Expected ',' or '}}', found {}", describe_token(tok))).with_span(&tokens.span(*i))),
+                }
+            }
+            *i += 1;
+            Ok(Expression::new(ExpressionKind::Map(entries), tokens.span_from(start, *i)))
+        },
+        Token::LeftBracket => {
+            *i += 1;
+            let mut elements = Vec::new();
+            while !matches!(tokens.get(*i), Some(Token::RightBracket)) {
+                if *i >= tokens.len() {
+                    return Err(ParseError::MissingToken("Missing ']' to close array in the ritual".to_string()).with_span(&span));
+                }
+                elements.push(parse_expression(tokens, i)?);
+                match tokens.get(*i) {
+                    Some(Token::Comma) => *i += 1,
+                    Some(Token::RightBracket) => {}
+                    tok => return Err(ParseError::UnexpectedToken(format!("Expected ',' or ']', found {}", describe_token(tok))).with_span(&tokens.span(*i))),
+                }
+            }
+            *i += 1;
+            Ok(Expression::new(ExpressionKind::Array(elements), tokens.span_from(start, *i)))
+        },
         Token::LeftParen => {
             let start_pos = *i;
             *i += 1;
@@ -291,36 +402,97 @@ fn parse_primary(tokens: &[Token], i: &mut usize) -> Result<Expression, ParseErr
                 *i += 1;
                 Ok(expr)
             } else {
-                Err(ParseError::MissingToken(format!("Missing ')' to close expression in the ritual at position {}", start_pos)))
+                Err(ParseError::MissingToken("Missing ')' to close expression in the ritual".to_string()).with_span(&tokens.span(start_pos)))
             }
         },
-        _ => Err(ParseError::InvalidValue(format!("Unexpected token in the cosmic void: {:?} at position {}", tokens[*i], i))),
+        tok => Err(ParseError::UnexpectedToken(format!("Expected a value, found {}", describe_token(Some(tok)))).with_span(&tokens.span(*i))),
     }
 }
 
-fn expect_token_type(tokens: &[Token], i: &mut usize, expected_type: &str) -> Result<(), ParseError> {
-    if *i >= tokens.len() {
-        return Err(ParseError::UnexpectedToken(format!("Unexpected end, expected {} in the ritual at position {}", expected_type, i)))
+/// Reads a bracketed index or key such as `[2]` or `["name"]`.
+pub fn parse_index(tokens: &TokenStream, i: &mut usize) -> Result<Expression, ParseError> {
+    expect_token(tokens, i, Token::LeftBracket)?;
+    let index = parse_expression(tokens, i)?;
+    expect_token(tokens, i, Token::RightBracket)?;
+    Ok(index)
+}
+
+/// Reads the name after `grrrblbl`, which is qualified (`ns.name`) for
+/// functions reached through a namespace import.
+pub fn parse_function_name(tokens: &TokenStream, i: &mut usize) -> Result<String, ParseError> {
+    let mut name = match tokens.get(*i) {
+        Some(Token::Identifier(name)) => name.clone(),
+        tok => return Err(ParseError::UnexpectedToken(format!("Expected a function name after 'grrrblbl', found {}", describe_token(tok))).with_span(&tokens.span(*i))),
+    };
+    *i += 1;
+
+    if matches!(tokens.get(*i), Some(Token::Dot)) && matches!(tokens.get(*i + 1), Some(Token::Identifier(_))) {
+        if let Some(Token::Identifier(member)) = tokens.get(*i + 1) {
+            name = format!("{}.{}", name, member);
+        }
+        *i += 2;
     }
 
-    let matches = match (&tokens[*i], expected_type) {
-        (Token::LeftParen, "LeftParen") => true,
-        (Token::RightParen, "RightParen") => true,
-        (Token::LeftBracket, "LeftBracket") => true,
-        (Token::RightBracket, "RightBracket") => true,
-        (Token::LeftBrace, "LeftBrace") => true,
-        (Token::RightBrace, "RightBrace") => true,
-        (Token::Semicolon, "Semicolon") => true,
-        (Token::Colon, "Colon") => true,
-        (Token::Comma, "Comma") => true,
-        (Token::Assign, "Equals") => true,
-        _ => false,
+    Ok(name)
+}
+
+/// Reads the rest of a call whose target is reached through an index, as in
+/// `grrrblbl fs[0](21)` or `grrrblbl clan[1].greet("hi")`: the steps from
+/// `name` to the function, then its arguments in parentheses.
+pub fn parse_value_call(tokens: &TokenStream, i: &mut usize, name: &str, start: usize) -> Result<Expression, ParseError> {
+    let name_span = tokens.span(*i - 1);
+    let mut callee = match name.split_once('.') {
+        Some((owner, field)) => Expression::new(ExpressionKind::StructAccess {
+            target: Box::new(Expression::new(ExpressionKind::Variable(owner.to_string()), name_span.clone())),
+            field: field.to_string(),
+        }, name_span),
+        None => Expression::new(ExpressionKind::Variable(name.to_string()), name_span),
     };
 
-    if matches {
-        *i += 1;
-        Ok(())
-    } else {
-        Err(ParseError::UnexpectedToken(format!("Expected {}, found {:?} in the ritual at position {}", expected_type, tokens[*i], i)))
+    loop {
+        match tokens.get(*i) {
+            Some(Token::LeftBracket) => {
+                let index = parse_index(tokens, i)?;
+                let span = callee.span.to(&tokens.span(*i - 1));
+                callee = Expression::new(ExpressionKind::ArrayAccess {
+                    target: Box::new(callee),
+                    index: Box::new(index),
+                }, span);
+            }
+            Some(Token::Dot) => {
+                *i += 1;
+                let field = match tokens.get(*i) {
+                    Some(Token::Identifier(field)) => field.clone(),
+                    tok => return Err(ParseError::UnexpectedToken(format!("Expected a field name after '.', found {}", describe_token(tok))).with_span(&tokens.span(*i))),
+                };
+                *i += 1;
+                let span = callee.span.to(&tokens.span(*i - 1));
+                callee = Expression::new(ExpressionKind::StructAccess {
+                    target: Box::new(callee),
+                    field,
+                }, span);
+            }
+            Some(Token::LeftParen) => break,
+            tok => return Err(ParseError::UnexpectedToken(
+                format!("Expected '(' with the arguments of the function to call, found {}", describe_token(tok))
+            ).with_span(&tokens.span(*i))),
+        }
     }
-} 
\ No newline at end of file
+
+    *i += 1;
+    let mut args = Vec::new();
+    while !matches!(tokens.get(*i), Some(Token::RightParen)) {
+        if *i >= tokens.len() {
+            return Err(ParseError::MissingToken("Missing ')' to close the arguments in the ritual".to_string()).with_span(&tokens.span(start)));
+        }
+        args.push(parse_expression(tokens, i)?);
+        match tokens.get(*i) {
+            Some(Token::Comma) => *i += 1,
+            Some(Token::RightParen) => {}
+            tok => return Err(ParseError::UnexpectedToken(format!("Expected ',' or ')', found {}", describe_token(tok))).with_span(&tokens.span(*i))),
+        }
+    }
+    *i += 1;
+
+    Ok(Expression::new(ExpressionKind::CallValue { callee: Box::new(callee), args }, tokens.span_from(start, *i)))
+}
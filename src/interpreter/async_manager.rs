@@ -1,12 +1,19 @@
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::{Duration, Instant};
 use tokio::task::JoinHandle;
+use crate::ast::Value;
 use crate::interpreter::error::{RuntimeError, RuntimeResult};
+use crate::interpreter::runtime::run_to_completion;
 use log;
 
+type ThreadTable = HashMap<String, JoinHandle<RuntimeResult<Value>>>;
+
+#[derive(Clone)]
 pub struct AsyncManager {
-    pub threads: Arc<Mutex<HashMap<String, JoinHandle<RuntimeResult<()>>>>>,
+    pub threads: Arc<Mutex<ThreadTable>>,
     thread_timeouts: Arc<Mutex<HashMap<String, Instant>>>,
     thread_timeout: Duration,
 }
@@ -20,10 +27,20 @@ impl AsyncManager {
         }
     }
 
-    pub fn register_thread(&self, name: Option<String>, handle: JoinHandle<RuntimeResult<()>>) -> RuntimeResult<()> {
+    fn lock_threads(&self) -> Result<MutexGuard<'_, ThreadTable>, RuntimeError> {
+        self.threads.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock threads: {}", e)))
+    }
+
+    fn lock_timeouts(&self) -> Result<MutexGuard<'_, HashMap<String, Instant>>, RuntimeError> {
+        self.thread_timeouts.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock thread timeouts: {}", e)))
+    }
+
+    pub fn register_thread(&self, name: Option<String>, handle: JoinHandle<RuntimeResult<Value>>) -> RuntimeResult<()> {
         if let Some(name) = name {
-            let mut threads = self.threads.lock().unwrap();
-            let mut timeouts = self.thread_timeouts.lock().unwrap();
+            let mut threads = self.lock_threads()?;
+            let mut timeouts = self.lock_timeouts()?;
             
             if threads.contains_key(&name) {
                 log::warn!("Thread '{}' already exists, replacing old thread", name);
@@ -34,8 +51,8 @@ impl AsyncManager {
             threads.insert(name.clone(), handle);
             timeouts.insert(name, Instant::now());
         } else {
-            let mut threads = self.threads.lock().unwrap();
-            let mut timeouts = self.thread_timeouts.lock().unwrap();
+            let mut threads = self.lock_threads()?;
+            let mut timeouts = self.lock_timeouts()?;
             let name = format!("anonymous_{}", threads.len());
             threads.insert(name.clone(), handle);
             timeouts.insert(name, Instant::now());
@@ -44,8 +61,8 @@ impl AsyncManager {
     }
 
     pub fn cleanup_stale_threads(&self) -> RuntimeResult<()> {
-        let mut threads = self.threads.lock().unwrap();
-        let mut timeouts = self.thread_timeouts.lock().unwrap();
+        let mut threads = self.lock_threads()?;
+        let mut timeouts = self.lock_timeouts()?;
         let now = Instant::now();
         let mut to_remove = Vec::new();
 
@@ -65,9 +82,9 @@ impl AsyncManager {
         Ok(())
     }
 
-    pub fn unregister_thread(&self, name: &str) -> RuntimeResult<Option<JoinHandle<RuntimeResult<()>>>> {
-        let mut threads = self.threads.lock().unwrap();
-        let mut timeouts = self.thread_timeouts.lock().unwrap();
+    pub fn unregister_thread(&self, name: &str) -> RuntimeResult<Option<JoinHandle<RuntimeResult<Value>>>> {
+        let mut threads = self.lock_threads()?;
+        let mut timeouts = self.lock_timeouts()?;
         
         if !threads.contains_key(name) {
             return Err(RuntimeError::AsyncError(
@@ -79,16 +96,16 @@ impl AsyncManager {
         Ok(threads.remove(name))
     }
 
-    pub fn has_thread(&self, name: &str) -> bool {
-        self.threads.lock().unwrap().contains_key(name)
+    pub fn has_thread(&self, name: &str) -> RuntimeResult<bool> {
+        Ok(self.lock_threads()?.contains_key(name))
     }
 
-    pub fn list_threads(&self) -> Vec<String> {
-        self.threads.lock().unwrap().keys().cloned().collect()
+    pub fn list_threads(&self) -> RuntimeResult<Vec<String>> {
+        Ok(self.lock_threads()?.keys().cloned().collect())
     }
 
     pub fn is_thread_running(&self, name: &str) -> RuntimeResult<bool> {
-        let threads = self.threads.lock().unwrap();
+        let threads = self.lock_threads()?;
         if let Some(handle) = threads.get(name) {
             Ok(!handle.is_finished())
         } else {
@@ -99,7 +116,7 @@ impl AsyncManager {
     }
 
     pub fn get_thread_status(&self, name: &str) -> RuntimeResult<String> {
-        let threads = self.threads.lock().unwrap();
+        let threads = self.lock_threads()?;
         if let Some(handle) = threads.get(name) {
             if handle.is_finished() {
                 Ok("completed".to_string())
@@ -112,4 +129,55 @@ impl AsyncManager {
             ).into())
         }
     }
-} 
\ No newline at end of file
+} 
+static NEXT_THREAD_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// A thread started by a `splurg` expression. Copies of a handle refer to the
+/// same thread: the first join waits for it, later joins get the same result.
+#[derive(Clone)]
+pub struct ThreadHandle {
+    pub id: usize,
+    state: Arc<Mutex<ThreadState>>,
+}
+
+enum ThreadState {
+    Running(JoinHandle<RuntimeResult<Value>>),
+    Finished(RuntimeResult<Value>),
+}
+
+impl ThreadHandle {
+    pub fn new(handle: JoinHandle<RuntimeResult<Value>>) -> Self {
+        Self {
+            id: NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed),
+            state: Arc::new(Mutex::new(ThreadState::Running(handle))),
+        }
+    }
+
+    /// Waits for the thread and returns what its body returned.
+    pub fn join(&self) -> RuntimeResult<Value> {
+        let mut state = self.state.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock thread {}: {}", self.id, e)))?;
+        if let ThreadState::Running(handle) = &mut *state {
+            let result = run_to_completion(handle)
+                .map_err(|e| RuntimeError::AsyncError(format!("Error waiting for thread: {}", e)).into())
+                .and_then(|result| result);
+            *state = ThreadState::Finished(result);
+        }
+        match &*state {
+            ThreadState::Finished(result) => result.clone(),
+            ThreadState::Running(_) => unreachable!("a joined thread is finished"),
+        }
+    }
+}
+
+impl PartialEq for ThreadHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+impl fmt::Debug for ThreadHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ThreadHandle({})", self.id)
+    }
+}
@@ -0,0 +1,118 @@
+//! Functions every program can call without importing anything. A function
+//! the program defines with the same name takes their place.
+
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use num_bigint::BigInt;
+
+use crate::ast::Value;
+use crate::interpreter::channel::Channel;
+use crate::interpreter::error::RuntimeError;
+
+/// Builtins are only called with a number of arguments their arity allows.
+type Builtin = fn(&[Value]) -> Result<Value, RuntimeError>;
+
+const BUILTINS: &[(&str, RangeInclusive<usize>, Builtin)] = &[
+    ("to_number", 1..=1, |args| to_number(&args[0])),
+    ("to_whole", 1..=1, |args| to_whole(&args[0])),
+    ("is_number", 1..=1, |args| is_number(&args[0])),
+    ("channel", 0..=1, channel),
+    ("send", 2..=2, |args| as_channel(&args[0])?.send(args[1].clone()).map(|_| Value::Number(0))),
+    ("recv", 1..=1, recv),
+    ("try_recv", 1..=1, |args| as_channel(&args[0])?.try_recv().map(received)),
+    ("recv_timeout", 2..=2, recv_timeout),
+    ("close", 1..=1, |args| as_channel(&args[0])?.close().map(|_| Value::Number(0))),
+];
+
+/// Runs builtin `name`, or returns `None` when there is no such builtin.
+pub fn call(name: &str, args: &[Value]) -> Option<Result<Value, RuntimeError>> {
+    let (_, arity, function) = BUILTINS.iter().find(|(builtin, _, _)| *builtin == name)?;
+    if !arity.contains(&args.len()) {
+        let expected = if arity.start() == arity.end() {
+            arity.start().to_string()
+        } else {
+            format!("{} to {}", arity.start(), arity.end())
+        };
+        return Some(Err(RuntimeError::InvalidOperation(format!(
+            "Function '{}' expects {} arguments, but got {}",
+            name, expected, args.len()
+        ))));
+    }
+    Some(function(args))
+}
+
+/// Reads text such as `"42"` or `" -3.5 "` as a number; numbers pass through.
+fn to_number(value: &Value) -> Result<Value, RuntimeError> {
+    match value {
+        Value::Text(text) => Value::parse_number(text.trim()).ok_or_else(|| RuntimeError::TypeError(
+            format!("'{}' is not a number", text)
+        )),
+        number if number.is_number() => Ok(number.clone()),
+        other => Err(RuntimeError::TypeError(format!("Cannot read {} as a number", other))),
+    }
+}
+
+/// Like `to_number`, but drops any fractional part.
+fn to_whole(value: &Value) -> Result<Value, RuntimeError> {
+    match to_number(value)? {
+        Value::Float(n) if n.is_finite() => Ok(Value::from_bigint(
+            n.trunc().to_string().parse::<BigInt>().expect("a truncated finite float prints as an integer")
+        )),
+        Value::Float(n) => Err(RuntimeError::TypeError(format!("{} has no whole part", n))),
+        whole => Ok(whole),
+    }
+}
+
+fn is_number(value: &Value) -> Result<Value, RuntimeError> {
+    Ok(Value::Bool(match value {
+        Value::Text(text) => Value::parse_number(text.trim()).is_some(),
+        other => other.is_number(),
+    }))
+}
+
+/// `channel()` makes an unbounded channel, `channel(n)` one holding at most `n` values.
+fn channel(args: &[Value]) -> Result<Value, RuntimeError> {
+    let capacity = match args {
+        [] => None,
+        [capacity] => match capacity.as_i64() {
+            Some(n) if n > 0 => Some(n as usize),
+            _ => return Err(RuntimeError::InvalidOperation(format!(
+                "A channel must hold at least one value, not {}", capacity
+            ))),
+        },
+        _ => unreachable!("arity is checked before the call"),
+    };
+    Ok(Value::Channel(Channel::new(capacity)))
+}
+
+fn as_channel(value: &Value) -> Result<&Channel, RuntimeError> {
+    match value {
+        Value::Channel(channel) => Ok(channel),
+        other => Err(RuntimeError::TypeError(format!("Expected a channel, found {}", other))),
+    }
+}
+
+/// What the non-blocking receives give back: `[value]`, or `[]` when nothing came.
+fn received(value: Option<Value>) -> Value {
+    Value::Array(value.into_iter().collect())
+}
+
+fn recv(args: &[Value]) -> Result<Value, RuntimeError> {
+    let channel = as_channel(&args[0])?;
+    channel.recv()?.ok_or_else(|| RuntimeError::InvalidOperation(format!(
+        "Channel {} is closed and has nothing left to receive", channel.id
+    )))
+}
+
+/// Waits at most the given number of milliseconds.
+fn recv_timeout(args: &[Value]) -> Result<Value, RuntimeError> {
+    let channel = as_channel(&args[0])?;
+    let millis = match args[1].as_i64() {
+        Some(ms) if ms >= 0 => ms as u64,
+        _ => return Err(RuntimeError::TypeError(format!(
+            "A receive timeout must be a whole number of milliseconds, not {}", args[1]
+        ))),
+    };
+    channel.recv_timeout(Duration::from_millis(millis)).map(received)
+}
@@ -0,0 +1,76 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::ast::Span;
+use crate::interpreter::error::RuntimeError;
+
+/// One active call: the function that was entered and where it was called.
+#[derive(Debug, Clone)]
+pub struct CallFrame {
+    pub function: String,
+    pub call_site: Span,
+}
+
+impl fmt::Display for CallFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "in '{}' called at {}", self.function, self.call_site)
+    }
+}
+
+/// The calls currently running on one murloc thread, outermost first.
+/// Runtimes derived for blocks and function frames share the same stack;
+/// spawned threads start with an empty one.
+#[derive(Clone, Default)]
+pub struct CallStack {
+    frames: Arc<Mutex<Vec<CallFrame>>>,
+}
+
+impl CallStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enters `frame`, refusing once `limit` calls are already running.
+    pub fn push(&self, frame: CallFrame, limit: usize) -> Result<(), RuntimeError> {
+        let mut frames = self.frames.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock call stack: {}", e)))?;
+        if frames.len() >= limit {
+            return Err(RuntimeError::RecursionLimit(limit));
+        }
+        frames.push(frame);
+        Ok(())
+    }
+
+    pub fn pop(&self) {
+        if let Ok(mut frames) = self.frames.lock() {
+            frames.pop();
+        }
+    }
+
+    /// The running calls, innermost first, as they appear in a stack trace.
+    pub fn trace(&self) -> Vec<CallFrame> {
+        self.frames.lock()
+            .map(|frames| frames.iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Renders a trace one line per frame, folding runs of the same frame (as
+/// deep recursion produces) into a single line with a repeat count.
+pub fn describe_trace(trace: &[CallFrame]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut index = 0;
+    while index < trace.len() {
+        let frame = &trace[index];
+        let repeats = trace[index + 1..]
+            .iter()
+            .take_while(|next| next.function == frame.function && next.call_site == frame.call_site)
+            .count();
+        lines.push(frame.to_string());
+        if repeats > 0 {
+            lines.push(format!("... the same call {} more times", repeats));
+        }
+        index += repeats + 1;
+    }
+    lines
+}
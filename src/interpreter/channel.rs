@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::ast::Value;
+use crate::interpreter::error::RuntimeError;
+
+static NEXT_CHANNEL_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// A queue of values that threads pass to each other. Copies of a channel
+/// share the same queue. A bounded channel makes senders wait while it is
+/// full; once closed it takes no more values but still gives back the ones
+/// already sent.
+#[derive(Clone)]
+pub struct Channel {
+    pub id: usize,
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    /// Signalled whenever a value is sent or taken, and when the channel closes.
+    changed: Condvar,
+}
+
+struct State {
+    queue: VecDeque<Value>,
+    capacity: Option<usize>,
+    closed: bool,
+}
+
+impl Channel {
+    /// A channel holding at most `capacity` values, or any number for `None`.
+    pub fn new(capacity: Option<usize>) -> Self {
+        Self {
+            id: NEXT_CHANNEL_ID.fetch_add(1, Ordering::Relaxed),
+            shared: Arc::new(Shared {
+                state: Mutex::new(State { queue: VecDeque::new(), capacity, closed: false }),
+                changed: Condvar::new(),
+            }),
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, State>, RuntimeError> {
+        self.shared.state.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock channel {}: {}", self.id, e)))
+    }
+
+    fn wait<'a>(&self, state: MutexGuard<'a, State>) -> Result<MutexGuard<'a, State>, RuntimeError> {
+        self.shared.changed.wait(state)
+            .map_err(|e| RuntimeError::LockError(format!("Failed to wait on channel {}: {}", self.id, e)))
+    }
+
+    /// Sends `value`, waiting for room if the channel is full.
+    pub fn send(&self, value: Value) -> Result<(), RuntimeError> {
+        let mut state = self.lock()?;
+        while !state.closed && state.capacity.is_some_and(|capacity| state.queue.len() >= capacity) {
+            state = self.wait(state)?;
+        }
+        if state.closed {
+            return Err(RuntimeError::InvalidOperation(format!("Cannot send on channel {}: it is closed", self.id)));
+        }
+        state.queue.push_back(value);
+        self.shared.changed.notify_all();
+        Ok(())
+    }
+
+    /// Takes the next value, waiting for one to be sent. Gives `None` once the
+    /// channel is closed and empty.
+    pub fn recv(&self) -> Result<Option<Value>, RuntimeError> {
+        let mut state = self.lock()?;
+        while state.queue.is_empty() && !state.closed {
+            state = self.wait(state)?;
+        }
+        Ok(self.take(&mut state))
+    }
+
+    /// Takes the next value if one is waiting, without blocking.
+    pub fn try_recv(&self) -> Result<Option<Value>, RuntimeError> {
+        let mut state = self.lock()?;
+        Ok(self.take(&mut state))
+    }
+
+    /// Like [`Channel::recv`], but gives up with `None` after `timeout`.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Option<Value>, RuntimeError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.lock()?;
+        while state.queue.is_empty() && !state.closed {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            state = self.shared.changed.wait_timeout(state, remaining)
+                .map_err(|e| RuntimeError::LockError(format!("Failed to wait on channel {}: {}", self.id, e)))?
+                .0;
+        }
+        Ok(self.take(&mut state))
+    }
+
+    /// Closes the channel. Closing it again does nothing.
+    pub fn close(&self) -> Result<(), RuntimeError> {
+        self.lock()?.closed = true;
+        self.shared.changed.notify_all();
+        Ok(())
+    }
+
+    fn take(&self, state: &mut State) -> Option<Value> {
+        let value = state.queue.pop_front();
+        if value.is_some() {
+            self.shared.changed.notify_all();
+        }
+        value
+    }
+
+    /// Every value received until the channel is closed and empty.
+    pub fn iter(&self) -> impl Iterator<Item = Result<Value, RuntimeError>> + Send + use<> {
+        let channel = self.clone();
+        std::iter::from_fn(move || channel.recv().transpose())
+    }
+}
+
+impl PartialEq for Channel {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+}
+
+impl fmt::Debug for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Channel({})", self.id)
+    }
+}
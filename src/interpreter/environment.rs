@@ -1,162 +1,171 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::sync::{Arc, Mutex};
 use crate::ast::{Statement, Value, Expression, Type};
 use crate::interpreter::error::{RuntimeError, RuntimeResult};
 use crate::interpreter::evaluator::evaluate_expression;
-use crate::value_parser::ParseError;
+use crate::interpreter::scope::Scope;
+
+pub type FunctionTable = HashMap<String, Arc<Function>>;
+pub type StructTable = HashMap<String, Vec<(String, Type)>>;
+/// Each enum's variants with the names of their payload values.
+pub type EnumTable = HashMap<String, Vec<(String, Vec<String>)>>;
+
+#[derive(Clone)]
+pub struct Function {
+    pub params: Vec<String>,
+    pub body: Vec<Statement>,
+    /// The environment of the module that defined the function, when it was
+    /// imported from another file. Its body runs there so the module's private
+    /// helpers and globals stay reachable.
+    pub module: Option<Environment>,
+    /// The scope the function was defined in. Its body runs in a frame nested
+    /// there, so it keeps seeing the variables around its definition.
+    pub captured: Option<Scope>,
+}
+
+impl Function {
+    /// A fresh frame for one call, with the parameters still unbound.
+    pub fn frame(&self, caller: &Environment) -> Environment {
+        let home = self.module.as_ref().unwrap_or(caller);
+        match &self.captured {
+            Some(scope) => Environment {
+                variables: scope.child(),
+                ..home.clone()
+            },
+            None => home.function_frame(),
+        }
+    }
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("params", &self.params)
+            .finish_non_exhaustive()
+    }
+}
+
 pub struct Environment {
-    pub variables: Arc<Mutex<HashMap<String, Value>>>,
-    pub functions: Arc<Mutex<HashMap<String, (Vec<String>, Vec<Statement>)>>>,
-    pub structs: Arc<Mutex<HashMap<String, Vec<(String, Type)>>>>,
+    pub variables: Scope,
+    pub functions: Arc<Mutex<FunctionTable>>,
+    pub structs: Arc<Mutex<StructTable>>,
+    pub enums: Arc<Mutex<EnumTable>>,
     pub exports: Arc<Mutex<HashMap<String, bool>>>,
 }
 
 impl Environment {
     pub fn new() -> Self {
         Self {
-            variables: Arc::new(Mutex::new(HashMap::new())),
+            variables: Scope::new(),
             functions: Arc::new(Mutex::new(HashMap::new())),
             structs: Arc::new(Mutex::new(HashMap::new())),
+            enums: Arc::new(Mutex::new(HashMap::new())),
             exports: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
-    pub fn get_var(&self, name: &str) -> RuntimeResult<Value> {
-        self.variables
-            .lock()
-            .unwrap()
-            .get(name)
-            .cloned()
-            .ok_or_else(|| RuntimeError::VariableNotFound(format!("Variable '{}' lost in the cosmic void", name)).into())
+    /// The same definitions with a new block scope nested in the current one.
+    pub fn child(&self) -> Environment {
+        Environment {
+            variables: self.variables.child(),
+            ..self.clone()
+        }
     }
 
-    pub fn set_var(&self, name: String, value: Value) {
-        self.variables.lock().unwrap().insert(name, value);
+    /// A fresh frame for a function body, nested directly in the globals so
+    /// the caller's locals stay out of reach.
+    pub fn function_frame(&self) -> Environment {
+        Environment {
+            variables: self.variables.global().child(),
+            ..self.clone()
+        }
     }
 
-    pub fn get_function(&self, name: &str) -> RuntimeResult<(Vec<String>, Vec<Statement>)> {
-        self.functions
-            .lock()
-            .unwrap()
-            .get(name)
-            .cloned()
-            .ok_or_else(|| RuntimeError::InvalidOperation(format!("Function '{}' not found in the ritual book", name)).into())
+    /// The payload names of `enum_name.variant`, or `None` when no enum is
+    /// called `enum_name`.
+    pub fn enum_variant(&self, enum_name: &str, variant: &str) -> RuntimeResult<Option<Vec<String>>> {
+        let enums = self.enums.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock enums: {}", e)))?;
+        let Some(variants) = enums.get(enum_name) else {
+            return Ok(None);
+        };
+        match variants.iter().find(|(name, _)| name == variant) {
+            Some((_, fields)) => Ok(Some(fields.clone())),
+            None => Err(RuntimeError::InvalidOperation(
+                format!("Enum '{}' has no variant '{}'", enum_name, variant)
+            ).into()),
+        }
     }
 
-    pub fn get_function_sync(&self, name: &str) -> Option<(Vec<String>, Vec<Statement>)> {
-        self.functions
-            .lock()
-            .unwrap()
-            .get(name)
-            .cloned()
+    pub fn get_var(&self, name: &str) -> RuntimeResult<Value> {
+        self.variables
+            .get(name)?
+            .ok_or_else(|| RuntimeError::UndefinedVariable(name.to_string()).into())
     }
 
-    pub fn set_function(&self, name: String, args: Vec<String>, body: Vec<Statement>) {
-        self.functions.lock().unwrap().insert(name, (args, body));
-    }
-    
-    pub fn with_locked_vars<F, R>(&self, f: F) -> R
-    where
-        F: FnOnce(&mut HashMap<String, Value>) -> R,
-    {
-        let mut guard = self.variables.lock().unwrap();
-        f(&mut guard)
-    }
-    
-    pub fn evaluate(&self, expr: &Expression) -> RuntimeResult<Value> {
-        evaluate_expression(expr, &self.variables.lock().unwrap(), None)
+    /// Declares `name` in the innermost scope, shadowing outer variables.
+    pub fn set_var(&self, name: String, value: Value) -> RuntimeResult<()> {
+        self.variables.declare(name, value)?;
+        Ok(())
     }
-    
-    pub fn evaluate_with_runtime(&self, expr: &Expression, runtime: &super::runtime::MurlocRuntime) -> RuntimeResult<Value> {
-        match expr {
-            Expression::FunctionCall { name, args } => {
-                let evaluated_args = args.iter()
-                    .map(|arg| self.evaluate(arg))
-                    .collect::<Result<Vec<Value>, ParseError>>()?;
-                
-                match self.execute_sync_function(name, evaluated_args) {
-                    Ok(result) => Ok(result),
-                    Err(_) => {
-                        evaluate_expression(expr, &self.variables.lock().unwrap(), Some(runtime))
-                    }
-                }
-            },
-            _ => evaluate_expression(expr, &self.variables.lock().unwrap(), Some(runtime))
+
+    /// Assigns to the nearest declared `name`, wherever in the chain it lives.
+    pub fn assign_var(&self, name: &str, value: Value) -> RuntimeResult<()> {
+        if self.variables.assign(name, value)? {
+            Ok(())
+        } else {
+            Err(RuntimeError::UndefinedVariable(name.to_string()).into())
         }
     }
 
-    pub fn execute_sync_function(&self, name: &str, args: Vec<Value>) -> RuntimeResult<Value> {
-        let (param_names, body) = self.get_function_sync(name)
-            .ok_or_else(|| RuntimeError::InvalidOperation(format!("Function '{}' not found in the ritual book", name)))?;
+    pub fn get_function(&self, name: &str) -> RuntimeResult<Arc<Function>> {
+        self.get_function_sync(name)?
+            .ok_or_else(|| RuntimeError::UndefinedFunction(name.to_string()).into())
+    }
 
-        if args.len() != param_names.len() {
-            return Err(RuntimeError::InvalidOperation(format!(
-                "Function '{}' expects {} arguments, but got {}",
-                name, param_names.len(), args.len()
-            )).into());
+    /// Looks `name` up as a variable holding a function first, so parameters
+    /// and locals shadow named functions, then in the function table, then as
+    /// a function stored in a struct field (`chief.greet`).
+    pub fn get_function_sync(&self, name: &str) -> RuntimeResult<Option<Arc<Function>>> {
+        if let Some(Value::Function(function)) = self.variables.get(name)? {
+            return Ok(Some(function));
         }
 
-        let mut function_env = self.variables.lock()
-            .map_err(|e| RuntimeError::LockError(format!("Failed to lock variables: {}", e)))?
-            .clone();
-        
-        for (param, arg) in param_names.iter().zip(args.iter()) {
-            function_env.insert(param.clone(), arg.clone());
-        }
-        
-        let mut result = Value::Number(0);
-        for stmt in body {
-            match stmt {
-                Statement::Return(expr) => {
-                    result = evaluate_expression(&expr, &function_env, None)?;
-                    break;
-                },
-                Statement::VarDeclaration(name, value) => {
-                    function_env.insert(name, value);
-                },
-                Statement::VarDeclarationExpr(name, expr) => {
-                    let value = evaluate_expression(&expr, &function_env, None)?;
-                    function_env.insert(name, value);
-                },
-                Statement::Assignment(name, expr) => {
-                    let value = evaluate_expression(&expr, &function_env, None)?;
-                    function_env.insert(name, value);
-                },
-                Statement::Expr(expr) => {
-                    evaluate_expression(&expr, &function_env, None)?;
-                },
-                _ => continue,
-            }
+        let functions = self.functions.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock functions: {}", e)))?;
+        if let Some(function) = functions.get(name) {
+            return Ok(Some(function.clone()));
         }
-        
-        Ok(result)
+        drop(functions);
+
+        let Some((owner, field)) = name.split_once('.') else {
+            return Ok(None);
+        };
+        Ok(match self.variables.get(owner)? {
+            Some(Value::Struct(_, fields)) => fields.into_iter().find_map(|(name, value)| match value {
+                Value::Function(function) if name == field => Some(function),
+                _ => None,
+            }),
+            _ => None,
+        })
+    }
+
+    /// Defines a named function that closes over the current scope.
+    pub fn set_function(&self, name: String, args: Vec<String>, body: Vec<Statement>) -> RuntimeResult<()> {
+        let function = Function { params: args, body, module: None, captured: Some(self.variables.clone()) };
+        self.functions.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock functions: {}", e)))?
+            .insert(name, Arc::new(function));
+        Ok(())
     }
-
-    pub fn execute_async_function(&self, name: &str, args: Vec<Value>) -> RuntimeResult<Value> {
-        let (param_names, body) = self.get_function(name)?;
-
-        if args.len() != param_names.len() {
-            return Err(RuntimeError::InvalidOperation(format!(
-                "Function '{}' expects {} arguments, but got {}",
-                name, param_names.len(), args.len()
-            )).into());
-        }
-
-        Ok(Value::Future(Box::new(Statement::Function {
-            name: name.to_string(),
-            args: param_names,
-            body,
-            parent_scope: None,
-        })))
+    
+    pub fn evaluate(&self, expr: &Expression) -> RuntimeResult<Value> {
+        evaluate_expression(expr, &self.variables, None)
     }
-
-    pub fn is_async_function(&self, name: &str) -> bool {
-        self.functions.lock().unwrap()
-            .get(name)
-            .map(|(_, body)| {
-                body.iter().any(|stmt| matches!(stmt, Statement::AsyncFunction { .. }))
-            })
-            .unwrap_or(false)
+    
+    pub fn evaluate_with_runtime(&self, expr: &Expression, runtime: &super::runtime::MurlocRuntime) -> RuntimeResult<Value> {
+        evaluate_expression(expr, &self.variables, Some(runtime))
     }
 
     pub fn add_export(&self, name: String, is_default: bool) -> Result<(), RuntimeError> {
@@ -172,19 +181,34 @@ impl Environment {
         Ok(exports.contains_key(name))
     }
 
+    pub fn default_export(&self) -> Result<Option<String>, RuntimeError> {
+        let exports = self.exports.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock exports: {}", e)))?;
+        Ok(exports.iter().find(|(_, is_default)| **is_default).map(|(name, _)| name.clone()))
+    }
+
+    pub fn export_names(&self) -> Result<Vec<String>, RuntimeError> {
+        let exports = self.exports.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock exports: {}", e)))?;
+        let mut names: Vec<String> = exports.keys().cloned().collect();
+        names.sort();
+        Ok(names)
+    }
+
     pub fn is_default_export(&self, name: &str) -> Result<bool, RuntimeError> {
         let exports = self.exports.lock()
             .map_err(|e| RuntimeError::LockError(format!("Failed to lock exports: {}", e)))?;
-        Ok(exports.get(name).map_or(false, |&is_default| is_default))
+        Ok(exports.get(name).is_some_and(|&is_default| is_default))
     }
 }
 
 impl Clone for Environment {
     fn clone(&self) -> Self {
         Self {
-            variables: Arc::clone(&self.variables),
+            variables: self.variables.clone(),
             functions: Arc::clone(&self.functions),
             structs: Arc::clone(&self.structs),
+            enums: Arc::clone(&self.enums),
             exports: Arc::clone(&self.exports),
         }
     }
@@ -1,4 +1,5 @@
-use crate::ast::Value;
+use crate::ast::{Span, Value};
+use crate::interpreter::call_stack::CallFrame;
 use crate::value_parser::ParseError;
 use std::fmt;
 
@@ -11,32 +12,109 @@ pub enum RuntimeError {
     IndexOutOfBounds(String),
     FileError(String),
     DivisionByZero,
-    VariableNotFound(String),
     AsyncError(String),
     Return(Value),
     LexerError(String),
     LockError(String),
+    RecursionLimit(usize),
+    /// A value raised with `blurgh`.
+    Thrown(Value),
     Break,
     Continue,
 }
 
 impl From<RuntimeError> for ParseError {
     fn from(err: RuntimeError) -> Self {
-        match err {
-            RuntimeError::Return(value) => ParseError::RuntimeError(RuntimeError::Return(value.clone())),
-            RuntimeError::LexerError(msg) => ParseError::InvalidValue(format!("BLRGHH! Unreadable glyphs in the kelp scroll: {}", msg)),
-            RuntimeError::VariableNotFound(name) => ParseError::InvalidValue(format!("Lost rune '{}' — perhaps eaten by deep sea worms?", name)),
-            RuntimeError::UndefinedVariable(name) => ParseError::InvalidValue(format!("'{}' floats undefined in the tide. Summon it, fool!", name)),
-            RuntimeError::UndefinedFunction(name) => ParseError::InvalidValue(format!("Spell '{}' not found in the sacred bubble texts!", name)),
-            RuntimeError::TypeError(msg) => ParseError::InvalidValue(format!("Glub! Type spirits are angry: {}", msg)),
-            RuntimeError::DivisionByZero => ParseError::InvalidValue("You dare divide by the abyss?! Void screams back!".to_string()),
-            RuntimeError::InvalidOperation(msg) => ParseError::InvalidValue(format!("Forbidden dance of operations: {}", msg)),
-            RuntimeError::AsyncError(msg) => ParseError::InvalidValue(format!("Temporal rift detected in async currents: {}", msg)),
-            RuntimeError::IndexOutOfBounds(msg) => ParseError::InvalidValue(format!("You swam beyond the coral bounds! Index chaos: {}", msg)),
-            RuntimeError::FileError(msg) => ParseError::InvalidValue(format!("Scroll drowned! File error in the shell archive: {}", msg)),
-            RuntimeError::LockError(msg) => ParseError::InvalidValue(format!("Lock error: {}", msg)),
-            RuntimeError::Break => ParseError::RuntimeError(RuntimeError::Break),
-            RuntimeError::Continue => ParseError::RuntimeError(RuntimeError::Continue),
+        ParseError::RuntimeError(err)
+    }
+}
+
+impl RuntimeError {
+    /// The name a `mrglurp` handler sees as the error's `kind`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RuntimeError::InvalidOperation(_) => "InvalidOperation",
+            RuntimeError::TypeError(_) => "TypeError",
+            RuntimeError::UndefinedVariable(_) => "UndefinedVariable",
+            RuntimeError::UndefinedFunction(_) => "UndefinedFunction",
+            RuntimeError::IndexOutOfBounds(_) => "IndexOutOfBounds",
+            RuntimeError::FileError(_) => "FileError",
+            RuntimeError::DivisionByZero => "DivisionByZero",
+            RuntimeError::AsyncError(_) => "AsyncError",
+            RuntimeError::Return(_) => "Return",
+            RuntimeError::LexerError(_) => "LexerError",
+            RuntimeError::LockError(_) => "LockError",
+            RuntimeError::RecursionLimit(_) => "RecursionLimit",
+            RuntimeError::Thrown(_) => "Thrown",
+            RuntimeError::Break => "Break",
+            RuntimeError::Continue => "Continue",
+        }
+    }
+
+    /// What went wrong, without the murloc flavor of [`fmt::Display`].
+    pub fn message(&self) -> String {
+        match self {
+            RuntimeError::InvalidOperation(msg)
+            | RuntimeError::TypeError(msg)
+            | RuntimeError::UndefinedVariable(msg)
+            | RuntimeError::UndefinedFunction(msg)
+            | RuntimeError::IndexOutOfBounds(msg)
+            | RuntimeError::FileError(msg)
+            | RuntimeError::AsyncError(msg)
+            | RuntimeError::LexerError(msg)
+            | RuntimeError::LockError(msg) => msg.clone(),
+            RuntimeError::DivisionByZero => "division by zero".to_string(),
+            RuntimeError::RecursionLimit(limit) => format!("more than {} calls were running", limit),
+            RuntimeError::Thrown(value) => value.to_string(),
+            RuntimeError::Return(_) | RuntimeError::Break | RuntimeError::Continue => self.to_string(),
+        }
+    }
+}
+
+/// What a `mrglurp` binds: the kind of error, its message, where it was
+/// raised and the calls that were running.
+#[derive(Debug, Clone)]
+pub struct ErrorValue {
+    pub kind: String,
+    pub message: String,
+    /// What was thrown with `blurgh`; the message for errors the runtime raised.
+    pub value: Value,
+    pub span: Option<Span>,
+    pub stack: Vec<CallFrame>,
+}
+
+impl ErrorValue {
+    /// Describes a caught error. A rethrown error value comes back as it was,
+    /// still pointing at where it was first raised.
+    pub fn from_error(err: &ParseError) -> ErrorValue {
+        let (kind, message, value) = match err.innermost() {
+            ParseError::RuntimeError(RuntimeError::Thrown(Value::Error(error))) => return (**error).clone(),
+            ParseError::RuntimeError(RuntimeError::Thrown(value)) => {
+                let message = match value {
+                    Value::Text(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                ("Thrown", message, value.clone())
+            },
+            ParseError::RuntimeError(runtime_error) => {
+                let message = runtime_error.message();
+                (runtime_error.kind(), message.clone(), Value::Text(message))
+            },
+            // Only an imported module can fail to parse once the program runs.
+            ParseError::InvalidValue(msg)
+            | ParseError::InvalidType(msg)
+            | ParseError::InvalidArrayType(msg)
+            | ParseError::UnexpectedToken(msg)
+            | ParseError::MissingToken(msg)
+            | ParseError::UnclosedBlock(msg) => ("SyntaxError", msg.clone(), Value::Text(msg.clone())),
+            ParseError::Spanned(..) | ParseError::Traced(..) => unreachable!("innermost looks through spans and traces"),
+        };
+        ErrorValue {
+            kind: kind.to_string(),
+            message,
+            value,
+            span: err.span().cloned(),
+            stack: err.trace().to_vec(),
         }
     }
 }
@@ -44,9 +122,8 @@ impl From<RuntimeError> for ParseError {
 impl fmt::Display for RuntimeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            RuntimeError::Return(value) => write!(f, "GLLBLRK! Ritual interrupted. Offering returned: {:?}", value),
+            RuntimeError::Return(value) => write!(f, "GLLBLRK! Ritual interrupted. Offering returned: {}", value),
             RuntimeError::LexerError(msg) => write!(f, "BLRGHH! Unreadable glyphs in the kelp scroll: {}", msg),
-            RuntimeError::VariableNotFound(name) => write!(f, "Lost rune '{}' — perhaps eaten by deep sea worms?", name),
             RuntimeError::UndefinedVariable(name) => write!(f, "'{}' floats undefined in the tide. Summon it, fool!", name),
             RuntimeError::UndefinedFunction(name) => write!(f, "Spell '{}' not found in the sacred bubble texts!", name),
             RuntimeError::TypeError(msg) => write!(f, "Glub! Type spirits are angry: {}", msg),
@@ -56,6 +133,8 @@ impl fmt::Display for RuntimeError {
             RuntimeError::IndexOutOfBounds(msg) => write!(f, "You swam beyond the coral bounds! Index chaos: {}", msg),
             RuntimeError::FileError(msg) => write!(f, "Scroll drowned! File error in the shell archive: {}", msg),
             RuntimeError::LockError(msg) => write!(f, "Lock error: {}", msg),
+            RuntimeError::RecursionLimit(limit) => write!(f, "The ritual sank too deep! More than {} calls stacked in the tide", limit),
+            RuntimeError::Thrown(value) => write!(f, "Uncaught error thrown into the tide: {}", value),
             RuntimeError::Break => write!(f, "Loop ritual broken!"),
             RuntimeError::Continue => write!(f, "Loop ritual continued!"),
         }
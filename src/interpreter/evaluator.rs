@@ -1,155 +1,158 @@
-use std::collections::HashMap;
-use crate::ast::{Value, Expression, BinaryOperator, ComparisonOperator, LogicalOperator, Type};
+use std::sync::Arc;
+use crate::ast::{Value, Span, Statement, StatementKind, Expression, ExpressionKind, BinaryOperator, LogicalOperator};
 use crate::value_parser::ParseError;
 use crate::interpreter::error::{RuntimeError, RuntimeResult};
+use crate::interpreter::async_manager::ThreadHandle;
+use crate::interpreter::environment::Function;
 use crate::interpreter::runtime::MurlocRuntime;
+use crate::interpreter::scope::Scope;
 
-pub fn evaluate_condition(condition: &Expression, env: &HashMap<String, Value>, runtime: Option<&MurlocRuntime>) -> bool {
-    match evaluate_expression(condition, env, runtime) {
-        Ok(Value::Number(n)) => {
-            n != 0
-        },
-        Ok(_v) => {
-            panic!("Invalid condition in the ritual: condition must evaluate to a number")
-        },
-        Err(e) => {
-            panic!("Error evaluating condition: {}", e)
-        },
-    }
+/// Evaluates `condition` and reads it as true or false following
+/// [`Value::truthiness`].
+pub fn evaluate_condition(condition: &Expression, env: &Scope, runtime: Option<&MurlocRuntime>) -> RuntimeResult<bool> {
+    let value = evaluate_expression(condition, env, runtime)?;
+    value.truthiness()
+        .map_err(|err| ParseError::from(err).with_span(&condition.span))
+}
+
+pub fn evaluate_expression(expr: &Expression, env: &Scope, runtime: Option<&MurlocRuntime>) -> RuntimeResult<Value> {
+    evaluate_expression_kind(&expr.kind, &expr.span, env, runtime).map_err(|e| e.with_span(&expr.span))
 }
 
-pub fn evaluate_expression(expr: &Expression, env: &HashMap<String, Value>, runtime: Option<&MurlocRuntime>) -> RuntimeResult<Value> {
+fn evaluate_expression_kind(expr: &ExpressionKind, span: &Span, env: &Scope, runtime: Option<&MurlocRuntime>) -> RuntimeResult<Value> {
     match expr {
-        Expression::Equals(_name, value) => {
+        ExpressionKind::Equals(_name, value) => {
             Ok(Value::Number(*value))
         },
-        Expression::BinaryOp { left, right, op } => {
+        ExpressionKind::BinaryOp { left, right, op } => {
             let left_val = evaluate_expression(left, env, runtime)?;
             let right_val = evaluate_expression(right, env, runtime)?;
             eval_binary_operation(&left_val, &right_val, op)
         },
-        Expression::Comparison { left, right, op } => {
+        ExpressionKind::Comparison { left, right, op } => {
             let left_val = evaluate_expression(left, env, runtime)?;
             let right_val = evaluate_expression(right, env, runtime)?;
             
-            let result = match op {
-                ComparisonOperator::Equals => left_val == right_val,
-                ComparisonOperator::NotEquals => left_val != right_val,
-                ComparisonOperator::LessThan => left_val < right_val,
-                ComparisonOperator::GreaterThan => left_val > right_val,
-                ComparisonOperator::LessThanOrEqual => left_val <= right_val,
-                ComparisonOperator::GreaterThanOrEqual => left_val >= right_val,
-            };
-            
-            Ok(Value::Number(if result { 1 } else { 0 }))
+            op.apply(&left_val, &right_val).map(Value::Bool).map_err(ParseError::from)
         },
-        Expression::LogicalOp { left, right, op } => {
-            let left_val = evaluate_expression(left, env, runtime)?;
-            
+        ExpressionKind::LogicalOp { left, right, op } => {
+            let left_val = evaluate_condition(left, env, runtime)?;
+
             match op {
                 LogicalOperator::Not => {
                     if right.is_some() {
-                        return Err(ParseError::InvalidValue("'Not' operator must not have a right operand".to_string()));
-                    }
-                    match left_val {
-                        Value::Number(a) => Ok(Value::Number(if a == 0 { 1 } else { 0 })),
-                        _ => Err(ParseError::InvalidValue("'Not' logical operand must be a number".to_string())),
+                        return Err(RuntimeError::InvalidOperation("'Not' operator must not have a right operand".to_string()).into());
                     }
+                    Ok(Value::Bool(!left_val))
                 },
-                _ => {
-                    let right_val = evaluate_expression(right.as_ref().unwrap(), env, runtime)?;
-                    match (left_val, right_val) {
-                        (Value::Number(a), Value::Number(b)) => {
-                            let result = match op {
-                                LogicalOperator::And => a != 0 && b != 0,
-                                LogicalOperator::Or => a != 0 || b != 0,
-                                LogicalOperator::Not => unreachable!(),
-                            };
-                            Ok(Value::Number(if result { 1 } else { 0 }))
-                        },
-                        _ => Err(ParseError::InvalidValue("Logical operands must be numbers".to_string())),
+                LogicalOperator::And | LogicalOperator::Or => {
+                    if left_val == matches!(op, LogicalOperator::Or) {
+                        return Ok(Value::Bool(left_val));
                     }
+                    let right = right.as_ref().ok_or_else(|| RuntimeError::InvalidOperation(
+                        format!("'{}' requires a right operand in the ritual", op)
+                    ))?;
+                    Ok(Value::Bool(evaluate_condition(right, env, runtime)?))
                 }
             }
         },
-        Expression::Literal(value) => {
+        ExpressionKind::Literal(value) => {
             Ok(value.clone())
         },
-        Expression::Variable(name) => {
-            if let Some(value) = env.get(name) {
-                Ok(value.clone())
+        ExpressionKind::Variable(name) => {
+            if let Some(value) = env.get(name)? {
+                Ok(value)
+            } else if let Some(function) = runtime.map(|rt| rt.env.get_function_sync(name)).transpose()?.flatten() {
+                Ok(Value::Function(function))
             } else {
-                Err(ParseError::InvalidValue(format!("Variable '{}' not found in the cosmic void", name)))
+                Err(RuntimeError::UndefinedVariable(name.clone()).into())
             }
         },
-        Expression::ArrayAccess { name, index } => {
-            if let Some(Value::Array(arr)) = env.get(name) {
-                if let Ok(Value::Number(idx)) = evaluate_expression(index, env, runtime) {
-                    let idx = idx as usize;
-                    if idx < arr.len() {
-                        Ok(arr[idx].clone())
-                    } else {
-                        Err(ParseError::InvalidValue(format!("Array index {} out of bounds in the matrix", idx)))
-                    }
-                } else {
-                    Err(ParseError::InvalidValue("Invalid array index format".to_string()))
-                }
-            } else {
-                Err(ParseError::InvalidValue(format!("Array '{}' not found in the cosmic void", name)))
-            }
+        ExpressionKind::ArrayAccess { target, index } => {
+            let target = evaluate_expression(target, env, runtime)?;
+            let index = evaluate_expression(index, env, runtime)?;
+            Ok(target.index(&index)?)
         },
-        Expression::StructAccess { name, field } => {
-            if let Some(Value::Struct(_, fields)) = env.get(name) {
-                if let Some((_, value)) = fields.iter().find(|(f, _)| f == field) {
-                    Ok(value.clone())
-                } else {
-                    Err(ParseError::InvalidValue(format!("Field '{}' not found in struct '{}'", field, name)))
+        ExpressionKind::StructAccess { target, field } => {
+            if let ExpressionKind::Variable(name) = &target.kind
+                && env.get(name)?.is_none()
+                && let Some(rt) = runtime
+                && let Some(fields) = rt.env.enum_variant(name, field)?
+            {
+                if !fields.is_empty() {
+                    return Err(RuntimeError::InvalidOperation(format!(
+                        "Variant '{}.{}' carries {} values; build it with 'grrrblbl {}.{}(...)'",
+                        name, field, fields.len(), name, field
+                    )).into());
                 }
-            } else {
-                Err(ParseError::InvalidValue(format!("Struct '{}' not found in the cosmic void", name)))
+                return Ok(Value::Enum(name.clone(), field.clone(), Vec::new()));
+            }
+
+            let value = evaluate_expression(target, env, runtime)?;
+            match value.field(field) {
+                Ok(value) => Ok(value),
+                Err(error) => {
+                    // Functions of a namespace import live in the function
+                    // table as `ns.name` rather than as fields.
+                    if let ExpressionKind::Variable(name) = &target.kind
+                        && let Some(function) = runtime.map(|rt| rt.env.get_function_sync(&format!("{}.{}", name, field))).transpose()?.flatten()
+                    {
+                        return Ok(Value::Function(function));
+                    }
+                    Err(error.into())
+                },
             }
         },
-        Expression::FunctionCall { name, args } => {
+        ExpressionKind::FunctionCall { name, args } => {
             if let Some(rt) = runtime {
-                rt.call_function_from_expression(name, args.clone())
+                let args = args.iter()
+                    .map(|arg| evaluate_expression(arg, env, runtime))
+                    .collect::<RuntimeResult<Vec<Value>>>()?;
+                rt.call_function(name, args, span)
             } else {
-                Err(ParseError::InvalidValue(format!(
+                Err(RuntimeError::InvalidOperation(format!(
                     "Function '{}' requires runtime for execution",
                     name
-                )))
+                )).into())
             }
         },
-        Expression::StructInstance { struct_name, fields } => {
+        ExpressionKind::CallValue { callee, args } => {
+            let Some(rt) = runtime else {
+                return Err(RuntimeError::InvalidOperation("Function calls require runtime for execution".to_string()).into());
+            };
+            let function = match evaluate_expression(callee, env, runtime)? {
+                Value::Function(function) => function,
+                other => return Err(RuntimeError::TypeError(format!("Cannot call {}: it is not a function", other)).into()),
+            };
+            let args = args.iter()
+                .map(|arg| evaluate_expression(arg, env, runtime))
+                .collect::<RuntimeResult<Vec<Value>>>()?;
+            rt.call_value(function, args, span)
+        },
+        ExpressionKind::StructInstance { struct_name, fields } => {
             if let Some(rt) = runtime {
-                let structs = rt.env.structs.lock().unwrap();
+                let structs = rt.env.structs.lock()
+                    .map_err(|e| RuntimeError::LockError(format!("Failed to lock structs: {}", e)))?;
                 if let Some(struct_fields) = structs.get(struct_name) {
                     let mut new_fields = Vec::new();
                     
                     for (field_name, field_expr) in fields {
                         if !struct_fields.iter().any(|(name, _)| name == field_name) {
-                            return Err(ParseError::InvalidValue(format!(
+                            return Err(RuntimeError::InvalidOperation(format!(
                                 "Field '{}' does not exist in struct '{}'",
                                 field_name, struct_name
-                            )));
+                            )).into());
                         }
                         
                         let field_value = evaluate_expression(field_expr, env, runtime)?;
                         
-                        if let Some((_, expected_type)) = struct_fields.iter().find(|(name, _)| name == field_name) {
-                            let type_matches = match (&field_value, expected_type) {
-                                (Value::Number(_), Type::Number) => true,
-                                (Value::Text(_), Type::Text) => true,
-                                (Value::Array(_), Type::Array(_)) => true,
-                                (Value::Struct(_, _), Type::Struct(_)) => true,
-                                _ => false
-                            };
-                            
-                            if !type_matches {
-                                return Err(ParseError::InvalidValue(format!(
-                                    "Type mismatch in struct '{}' field '{}': expected {}, found {}",
-                                    struct_name, field_name, expected_type, field_value
-                                )));
-                            }
+                        if let Some((_, expected_type)) = struct_fields.iter().find(|(name, _)| name == field_name)
+                            && !expected_type.accepts(&field_value)
+                        {
+                            return Err(RuntimeError::TypeError(format!(
+                                "Type mismatch in struct '{}' field '{}': expected {}, found {}",
+                                struct_name, field_name, expected_type, field_value
+                            )).into());
                         }
                         
                         new_fields.push((field_name.clone(), field_value));
@@ -157,55 +160,96 @@ pub fn evaluate_expression(expr: &Expression, env: &HashMap<String, Value>, runt
                     
                     Ok(Value::Struct(struct_name.clone(), new_fields))
                 } else {
-                    Err(ParseError::InvalidValue(format!("Type '{}' not found in the cosmic void", struct_name)))
+                    Err(RuntimeError::TypeError(format!("Type '{}' not found in the cosmic void", struct_name)).into())
                 }
             } else {
-                Err(ParseError::InvalidValue("Runtime required to create struct instance".to_string()))
+                Err(RuntimeError::InvalidOperation("Runtime required to create struct instance".to_string()).into())
             }
         },
-        Expression::InOperator { left, right } => {
+        ExpressionKind::InOperator { left, right } => {
             let left_val = evaluate_expression(left, env, runtime)?;
             let right_val = evaluate_expression(right, env, runtime)?;
             
             match (&left_val, &right_val) {
                 (Value::Text(item), Value::Array(arr)) => {
-                    Ok(Value::Number(if arr.contains(&Value::Text(item.clone())) { 1 } else { 0 }))
+                    Ok(Value::Bool(arr.contains(&Value::Text(item.clone()))))
                 },
-                (Value::Number(item), Value::Array(arr)) => {
-                    Ok(Value::Number(if arr.contains(&Value::Number(*item)) { 1 } else { 0 }))
+                (item, Value::Array(arr)) if item.is_number() || matches!(item, Value::Bool(_)) => {
+                    Ok(Value::Bool(arr.contains(item)))
                 },
-                _ => Err(ParseError::InvalidValue("Operador 'in' só pode ser usado com arrays no reino dos murlocs".to_string())),
+                (key, map @ Value::Map(_)) => Ok(Value::Bool(map.map_get(key).is_some())),
+                _ => Err(RuntimeError::TypeError(format!(
+                    "'blgr' looks for a value in an array or a map, not in {}", right_val
+                )).into()),
             }
         },
+        ExpressionKind::Array(elements) => {
+            elements.iter()
+                .map(|element| evaluate_expression(element, env, runtime))
+                .collect::<RuntimeResult<Vec<Value>>>()
+                .map(Value::Array)
+        },
+        ExpressionKind::Map(entries) => {
+            let mut map = Vec::new();
+            for (key, value) in entries {
+                let key = evaluate_expression(key, env, runtime)?;
+                let value = evaluate_expression(value, env, runtime)?;
+                Value::map_insert(&mut map, key, value)?;
+            }
+            Ok(Value::Map(map))
+        },
+        ExpressionKind::Function { params, body } => {
+            Ok(Value::Function(Arc::new(Function {
+                params: params.clone(),
+                body: body.clone(),
+                module: runtime.map(|rt| rt.env.clone()),
+                captured: Some(env.clone()),
+            })))
+        },
+        ExpressionKind::Await(future) => {
+            let rt = runtime.ok_or_else(|| RuntimeError::InvalidOperation(
+                "Await requires runtime for execution".to_string()
+            ))?;
+            let future = Statement::new(StatementKind::Expr((**future).clone()), span.clone());
+            rt.await_future(&future)
+        },
+        ExpressionKind::Read(prompt) => {
+            let rt = runtime.ok_or_else(|| RuntimeError::InvalidOperation(
+                "Reading input requires runtime for execution".to_string()
+            ))?;
+            let prompt = prompt.as_ref().map(|prompt| evaluate_expression(prompt, env, runtime)).transpose()?;
+            rt.read_input(prompt.as_ref())
+        },
+        ExpressionKind::Spawn(body) => {
+            let rt = runtime.ok_or_else(|| RuntimeError::InvalidOperation(
+                "Starting a thread requires runtime for execution".to_string()
+            ))?;
+            Ok(Value::Thread(ThreadHandle::new(rt.spawn_thread(body))))
+        },
+        ExpressionKind::Join(thread) => join_threads(evaluate_expression(thread, env, runtime)?),
     }
 }
 
-pub fn fish_value_sort(values: &mut [Value]) {
-    values.sort_by(|a, b| a.to_string().cmp(&b.to_string()));
+/// Waits for a thread handle and gives back its result, or for every handle
+/// in an array and gives back their results in order.
+pub fn join_threads(value: Value) -> RuntimeResult<Value> {
+    match value {
+        Value::Thread(thread) => thread.join(),
+        Value::Array(threads) => threads.into_iter().map(join_threads).collect::<RuntimeResult<Vec<_>>>().map(Value::Array),
+        other => Err(RuntimeError::TypeError(format!("Only threads can be waited for, found {}", other)).into()),
+    }
 }
 
 pub fn eval_binary_operation(left_val: &Value, right_val: &Value, op: &BinaryOperator) -> RuntimeResult<Value> {
+    if let Some(result) = op.apply_numeric(left_val, right_val) {
+        return result.map_err(ParseError::from);
+    }
     match (left_val, right_val) {
-        (Value::Number(a), Value::Number(b)) => {
-            match op {
-                BinaryOperator::Add => Ok(Value::Number(a + b)),
-                BinaryOperator::Subtract => Ok(Value::Number(a - b)),
-                BinaryOperator::Multiply => Ok(Value::Number(a * b)),
-                BinaryOperator::Divide => {
-                    if *b == 0 {
-                        Err(RuntimeError::DivisionByZero.into())
-                    } else {
-                        Ok(Value::Number(a / b))
-                    }
-                },
-                BinaryOperator::Modulo => Ok(Value::Number(a % b)),
-            }
-        },
         (Value::Text(a), Value::Text(b)) if matches!(op, BinaryOperator::Add) => 
             Ok(Value::Text(format!("{}{}", a, b))),
-        (Value::Text(a), Value::Number(b)) if matches!(op, BinaryOperator::Add) => 
+        (Value::Text(a), b) if (b.is_number() || matches!(b, Value::Bool(_) | Value::Enum(..) | Value::Error(_))) && matches!(op, BinaryOperator::Add) =>
             Ok(Value::Text(format!("{}{}", a, b))),
-        (Value::Number(a), Value::Text(b)) if matches!(op, BinaryOperator::Add) => 
+        (a, Value::Text(b)) if (a.is_number() || matches!(a, Value::Bool(_) | Value::Enum(..) | Value::Error(_))) && matches!(op, BinaryOperator::Add) =>
             Ok(Value::Text(format!("{}{}", a, b))),
         (Value::Text(a), Value::Struct(_, fields)) if matches!(op, BinaryOperator::Add) => {
             Ok(Value::Text(format!("{}{}", a, Value::Struct(String::new(), fields.clone()))))
@@ -213,6 +257,6 @@ pub fn eval_binary_operation(left_val: &Value, right_val: &Value, op: &BinaryOpe
         (Value::Struct(_, fields), Value::Text(b)) if matches!(op, BinaryOperator::Add) => {
             Ok(Value::Text(format!("{}{}", Value::Struct(String::new(), fields.clone()), b)))
         },
-        _ => Err(RuntimeError::InvalidOperation(format!("Invalid operation in the cosmic void: cannot perform {:?} between {:?} and {:?}", op, left_val, right_val)).into()),
+        _ => Err(RuntimeError::InvalidOperation(format!("'{}' cannot be applied to {} and {}", op, left_val, right_val)).into()),
     }
 } 
\ No newline at end of file
@@ -0,0 +1,50 @@
+use std::io::{self, BufRead};
+use std::sync::{Arc, Mutex};
+
+use crate::interpreter::error::RuntimeError;
+
+/// Where `mrglisn` reads its lines from. Any buffered reader works, so a
+/// script's input can come from a file or a string as well as the console.
+pub trait InputSource: Send {
+    /// The next line without its line ending, or `None` once input runs out.
+    fn next_line(&mut self) -> io::Result<Option<String>>;
+}
+
+impl<R: BufRead + Send> InputSource for R {
+    fn next_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if self.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let trimmed = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(trimmed);
+        Ok(Some(line))
+    }
+}
+
+/// The input source of a program, shared by every runtime and thread it
+/// starts.
+#[derive(Clone)]
+pub struct Input {
+    source: Arc<Mutex<Box<dyn InputSource>>>,
+}
+
+impl Input {
+    pub fn new(source: impl InputSource + 'static) -> Self {
+        Self {
+            source: Arc::new(Mutex::new(Box::new(source))),
+        }
+    }
+
+    pub fn stdin() -> Self {
+        Self::new(io::BufReader::new(io::stdin()))
+    }
+
+    pub fn read_line(&self) -> Result<String, RuntimeError> {
+        let mut source = self.source.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock input: {}", e)))?;
+        source.next_line()
+            .map_err(|e| RuntimeError::InvalidOperation(format!("Failed to read input: {}", e)))?
+            .ok_or_else(|| RuntimeError::InvalidOperation("The tide brought no more input".to_string()))
+    }
+}
@@ -2,8 +2,20 @@ mod error;
 mod environment;
 mod evaluator;
 mod async_manager;
+mod builtins;
+mod call_stack;
+mod channel;
+mod input;
+mod modules;
+mod scope;
 mod runtime;
 
 pub use error::*;
-pub use runtime::MurlocRuntime;
-pub use evaluator::{evaluate_expression, eval_binary_operation, fish_value_sort}; 
\ No newline at end of file
+pub use runtime::{MurlocRuntime, DEFAULT_MAX_RECURSION_DEPTH, MAX_RECURSION_DEPTH};
+pub use scope::Scope;
+pub use call_stack::{CallFrame, CallStack, describe_trace};
+pub use input::{Input, InputSource};
+pub use async_manager::ThreadHandle;
+pub use channel::Channel;
+pub use environment::Function;
+pub use evaluator::{evaluate_expression, eval_binary_operation}; 
\ No newline at end of file
@@ -0,0 +1,128 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+use crate::interpreter::environment::Environment;
+use crate::interpreter::error::RuntimeError;
+use crate::stdlib;
+
+#[derive(Clone)]
+pub struct Module {
+    pub path: PathBuf,
+    pub env: Environment,
+}
+
+/// Modules shared by every runtime of a program, keyed by canonical path, so
+/// each file runs once no matter how often it is imported.
+#[derive(Default)]
+pub struct ModuleCache {
+    state: Mutex<CacheState>,
+    /// Signalled whenever a module stops loading, whether it loaded or not.
+    finished: Condvar,
+}
+
+#[derive(Default)]
+struct CacheState {
+    loaded: HashMap<PathBuf, Module>,
+    loading: HashSet<PathBuf>,
+}
+
+impl ModuleCache {
+    fn lock(&self) -> Result<MutexGuard<'_, CacheState>, RuntimeError> {
+        self.state.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock modules: {}", e)))
+    }
+
+    /// Returns the module at `path` if it is loaded, waiting while another
+    /// thread loads it. Otherwise claims it for the caller, which must then
+    /// call [`ModuleCache::finish_loading`]. `chain` lists the modules whose
+    /// loading led to this import; finding `path` among them is a cycle.
+    pub fn start_loading(&self, path: &Path, chain: &[PathBuf]) -> Result<Option<Module>, RuntimeError> {
+        if let Some(position) = chain.iter().position(|loading| loading == path) {
+            let cycle = chain[position..]
+                .iter()
+                .map(|loading| loading.display().to_string())
+                .chain(std::iter::once(path.display().to_string()))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(RuntimeError::InvalidOperation(format!("Import cycle in the tide: {}", cycle)));
+        }
+
+        let mut state = self.lock()?;
+        loop {
+            if let Some(module) = state.loaded.get(path) {
+                return Ok(Some(module.clone()));
+            }
+            if state.loading.insert(path.to_path_buf()) {
+                return Ok(None);
+            }
+            state = self.finished.wait(state)
+                .map_err(|e| RuntimeError::LockError(format!("Failed to wait for module '{}': {}", path.display(), e)))?;
+        }
+    }
+
+    pub fn finish_loading(&self, path: &Path, module: Option<Module>) -> Result<(), RuntimeError> {
+        let mut state = self.lock()?;
+        state.loading.remove(path);
+        if let Some(module) = module {
+            state.loaded.insert(path.to_path_buf(), module);
+        }
+        self.finished.notify_all();
+        Ok(())
+    }
+}
+
+/// Resolves an import path by looking next to the importing file, then in
+/// each directory of `MURLANG_PATH`, then in `$MURLANG_HOME/lib`, and finally
+/// among the modules bundled with the binary. A missing extension defaults to
+/// `.mur`.
+pub fn resolve(importer: &str, path: &str) -> Result<PathBuf, RuntimeError> {
+    let base = Path::new(importer).parent().unwrap_or(Path::new("")).to_path_buf();
+    let mut searched = Vec::new();
+
+    for dir in std::iter::once(base).chain(search_path()) {
+        let mut candidate = dir.join(path);
+        if candidate.extension().is_none() {
+            candidate.set_extension("mur");
+        }
+        if candidate.is_file()
+            && let Ok(found) = candidate.canonicalize()
+        {
+            return Ok(found);
+        }
+        searched.push(candidate.display().to_string());
+    }
+
+    if let Some(bundled) = stdlib::find(path) {
+        return Ok(PathBuf::from(bundled));
+    }
+
+    Err(RuntimeError::FileError(format!(
+        "Module '{}' not found in the reef (searched: {})",
+        path,
+        searched.join(", ")
+    )))
+}
+
+/// Reads the source of a resolved module, whether it lives on disk or in the
+/// binary.
+pub fn read(path: &Path) -> io::Result<String> {
+    match path.to_str().and_then(stdlib::source) {
+        Some(source) => Ok(source.to_string()),
+        None => fs::read_to_string(path),
+    }
+}
+
+fn search_path() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(paths) = env::var_os("MURLANG_PATH") {
+        dirs.extend(env::split_paths(&paths).filter(|dir| !dir.as_os_str().is_empty()));
+    }
+    if let Some(home) = env::var_os("MURLANG_HOME") {
+        dirs.push(PathBuf::from(home).join("lib"));
+    }
+    dirs
+}
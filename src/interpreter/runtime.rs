@@ -1,51 +1,165 @@
 use std::collections::HashMap;
-use std::pin::Pin;
+use std::pin::{pin, Pin};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
 use std::sync::{Arc, Mutex};
-use std::fs;
-use std::io;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use tokio::runtime::Runtime;
+use tokio::task::JoinHandle;
 use log::{warn, error};
 use std::time::Duration;
 
-use crate::ast::{Statement, Value, Expression, Type, ImportSpecifier};
+use crate::ast::{Statement, StatementKind, Value, BinaryOperator, ImportSpecifier, MatchCase, PathSegment, Pattern, Span};
 use crate::value_parser::ParseError;
+use crate::lexer::TokenStream;
 
-use crate::interpreter::environment::Environment;
-use crate::interpreter::async_manager::AsyncManager;
-use crate::interpreter::error::{RuntimeError, RuntimeResult};
-use crate::interpreter::evaluator::{evaluate_condition, evaluate_expression};
+use crate::interpreter::environment::{EnumTable, Environment, Function};
+use crate::interpreter::async_manager::{AsyncManager, ThreadHandle};
+use crate::interpreter::builtins;
+use crate::interpreter::call_stack::{CallFrame, CallStack};
+use crate::interpreter::input::{Input, InputSource};
+use crate::interpreter::modules::{self, Module, ModuleCache};
+use crate::interpreter::error::{ErrorValue, RuntimeError, RuntimeResult};
+use crate::interpreter::evaluator::{evaluate_condition, join_threads};
+use crate::interpreter::scope::Scope;
+
+pub const DEFAULT_MAX_RECURSION_DEPTH: usize = 500;
+
+/// Native stack reserved per murloc call, so the depth limit trips long
+/// before the thread itself runs out of stack. Unoptimized builds need a lot
+/// more of it.
+const STACK_PER_CALL: usize = if cfg!(debug_assertions) { 512 * 1024 } else { 128 * 1024 };
+const BASE_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// The most stack the interpreter thread may ask for, which bounds how deep
+/// `--max-depth` can go.
+const MAX_STACK_SIZE: usize = 4 * 1024 * 1024 * 1024;
+pub const MAX_RECURSION_DEPTH: usize = (MAX_STACK_SIZE - BASE_STACK_SIZE) / STACK_PER_CALL;
 
 pub struct MurlocRuntime {
     pub env: Environment,
     pub async_manager: AsyncManager,
-    pub recursion_depth: Arc<Mutex<usize>>,
+    pub call_stack: CallStack,
     pub max_recursion_depth: usize,
     pub runtime: Arc<Runtime>,
+    pub modules: Arc<ModuleCache>,
+    /// The modules being loaded that led to this runtime, outermost first.
+    pub import_chain: Arc<Vec<PathBuf>>,
+    pub input: Input,
+}
+
+impl Default for MurlocRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MurlocRuntime {
     pub fn new() -> Self {
-        let runtime = tokio::runtime::Builder::new_multi_thread()
-            .worker_threads(4)
-            .enable_all()
-            .build()
-            .expect("Failed to create Tokio runtime");
-        
-        Self {
+        Self::with_max_recursion_depth(DEFAULT_MAX_RECURSION_DEPTH).expect("Failed to create Tokio runtime")
+    }
+
+    /// A runtime where at most `depth` calls may be running at once before a
+    /// call fails. Only the thread running the program gets stack for all of
+    /// them; threads the program starts stop at [`DEFAULT_MAX_RECURSION_DEPTH`].
+    pub fn with_max_recursion_depth(depth: usize) -> RuntimeResult<Self> {
+        if depth == 0 || depth > MAX_RECURSION_DEPTH {
+            return Err(RuntimeError::InvalidOperation(format!(
+                "The call depth must be between 1 and {}, not {}", MAX_RECURSION_DEPTH, depth
+            )).into());
+        }
+        Ok(Self {
             env: Environment::new(),
             async_manager: AsyncManager::new(),
-            recursion_depth: Arc::new(Mutex::new(0)),
-            max_recursion_depth: 500,
-            runtime: Arc::new(runtime),
+            call_stack: CallStack::new(),
+            max_recursion_depth: depth,
+            runtime: Arc::new(build_tokio_runtime(thread_depth(depth))?),
+            modules: Arc::new(ModuleCache::default()),
+            import_chain: Arc::new(Vec::new()),
+            input: Input::stdin(),
+        })
+    }
+
+    /// Reads `mrglisn` input from `source` instead of standard input.
+    pub fn with_input(mut self, source: impl InputSource + 'static) -> Self {
+        self.input = Input::new(source);
+        self
+    }
+
+    fn with_env(&self, env: Environment) -> MurlocRuntime {
+        MurlocRuntime {
+            env,
+            async_manager: self.async_manager.clone(),
+            call_stack: self.call_stack.clone(),
+            max_recursion_depth: self.max_recursion_depth,
+            runtime: self.runtime.clone(),
+            modules: self.modules.clone(),
+            import_chain: self.import_chain.clone(),
+            input: self.input.clone(),
         }
     }
 
+    /// Runs a program on a thread of its own, sized for the recursion limit.
     pub fn run(&self, statements: Vec<Statement>) -> Result<(), ParseError> {
-        self.runtime.block_on(async {
-            self.exec_block_impl(&statements).await
+        let entry = statements.first().and_then(|statement| Path::new(statement.span.file.as_str()).canonicalize().ok());
+        thread::scope(|scope| {
+            thread::Builder::new()
+                .name("murloc-main".to_string())
+                .stack_size(stack_size(self.max_recursion_depth))
+                .spawn_scoped(scope, || self.runtime.block_on(self.exec_entry(&statements, entry)))
+                .map_err(|e| RuntimeError::InvalidOperation(format!("Failed to start main thread: {}", e)))?
+                .join()
+                .map_err(|e| RuntimeError::InvalidOperation(format!("Main thread panicked: {:?}", e)))?
         })
     }
 
+    /// Runs the program's own file as the first module being loaded, so a
+    /// module that imports it back is reported as a cycle through it rather
+    /// than running it a second time. A program that is not a file on disk
+    /// just runs.
+    async fn exec_entry(&self, statements: &[Statement], path: Option<PathBuf>) -> RuntimeResult<()> {
+        let Some(path) = path else {
+            return self.exec_program(statements).await;
+        };
+
+        self.modules.start_loading(&path, &[])?;
+        let entry = MurlocRuntime {
+            import_chain: Arc::new(vec![path.clone()]),
+            ..self.with_env(self.env.clone())
+        };
+        let result = entry.exec_program(statements).await;
+
+        self.modules.finish_loading(&path, Some(Module { path: path.clone(), env: self.env.clone() }))?;
+        result
+    }
+
+    /// Runs a whole file. Its top-level imports run first, so every enum it
+    /// can name is known when its `murrrgh`s are checked, before anything
+    /// else runs.
+    async fn exec_program(&self, statements: &[Statement]) -> RuntimeResult<()> {
+        let (imports, rest): (Vec<&Statement>, Vec<&Statement>) = statements.iter()
+            .partition(|statement| matches!(statement.kind, StatementKind::Import { .. }));
+        for statement in imports {
+            Box::pin(self.execute_statement(statement)).await
+                .map_err(|err| err.with_span(&statement.span))?;
+        }
+
+        self.check_matches(statements)?;
+
+        for statement in rest {
+            Box::pin(self.execute_statement(statement)).await
+                .map_err(|err| err.with_span(&statement.span).with_trace(|| self.call_stack.trace()))?;
+        }
+        Ok(())
+    }
+
+    /// Runs `statements` in a new block scope, so their declarations end
+    /// with the block.
+    async fn exec_scoped(&self, statements: &[Statement]) -> RuntimeResult<()> {
+        self.with_env(self.env.child()).exec_block_impl(statements).await
+    }
+
     pub fn execute_statement_boxed<'a>(
         &'a self,
         statement: &'a Statement,
@@ -60,16 +174,8 @@ impl MurlocRuntime {
         Self: Send + Sync,
     {
         for statement in statements {
-            let result = Box::pin(self.execute_statement(statement)).await;
-            
-            if let Err(err) = result {
-                match &err {
-                    ParseError::RuntimeError(RuntimeError::Return(_)) => {
-                        return Err(err);
-                    },
-                    _ => return Err(err),
-                }
-            }
+            Box::pin(self.execute_statement(statement)).await
+                .map_err(|err| err.with_span(&statement.span).with_trace(|| self.call_stack.trace()))?;
         }
         Ok(())
     }
@@ -78,55 +184,18 @@ impl MurlocRuntime {
     where
         Self: Send + Sync,
     {
-        match statement {
-            Statement::AsyncFunction { name, args, body, parent_scope: _ } => {
-                self.env.set_function(name.to_string(), args.clone(), body.clone());
+        match &statement.kind {
+            StatementKind::AsyncFunction { name, args, body, parent_scope } => {
+                self.define_function(name, args, body, parent_scope)?;
                 Ok::<(), ParseError>(())
             },
-            Statement::Spawn { body, thread_name } => {
-                let vars_shared = self.env.variables.clone();
-                let funcs_shared = self.env.functions.clone();
-                let structs_shared = self.env.structs.clone();
-                
-                let runtime_clone = self.runtime.clone();
-                let body_clone = body.clone();
-                let recursion_depth_clone = self.recursion_depth.clone();
-                
-                let handle = self.runtime.spawn_blocking(move || {
-                    let runtime_for_block_on = runtime_clone.clone();
-                    let thread_runtime = MurlocRuntime {
-                        env: Environment {
-                            variables: vars_shared,
-                            functions: funcs_shared,
-                            structs: structs_shared,
-                            exports: Arc::new(Mutex::new(HashMap::new())),
-                        },
-                        async_manager: AsyncManager::new(),
-                        recursion_depth: recursion_depth_clone,
-                        max_recursion_depth: 500,
-                        runtime: runtime_clone,
-                    };
-                    
-                    match runtime_for_block_on.block_on(async {
-                        tokio::time::timeout(
-                            Duration::from_secs(30),
-                            thread_runtime.exec_block_impl(&body_clone)
-                        ).await
-                    }) {
-                        Ok(result) => result,
-                        Err(_) => Err(RuntimeError::AsyncError("Thread timeout after 30 seconds".to_string()).into())
-                    }
-                });
-                
+            StatementKind::Spawn { body, thread_name } => {
+                let handle = self.spawn_thread(body);
                 self.async_manager.register_thread(thread_name.clone(), handle)?;
                 Ok(())
             },
-            Statement::SpawnAsync { future, thread_name } => {
-                let vars_copy = {
-                    let vars = self.env.variables.lock()
-                        .map_err(|e| RuntimeError::LockError(format!("Failed to lock variables: {}", e)))?;
-                    vars.clone()
-                };
+            StatementKind::SpawnAsync { future, thread_name } => {
+                let vars_copy = self.env.variables.flatten()?;
                 
                 let funcs_copy = {
                     let funcs = self.env.functions.lock()
@@ -139,55 +208,68 @@ impl MurlocRuntime {
                         .map_err(|e| RuntimeError::LockError(format!("Failed to lock structs: {}", e)))?;
                     structs.clone()
                 };
+
+                let enums_copy = {
+                    let enums = self.env.enums.lock()
+                        .map_err(|e| RuntimeError::LockError(format!("Failed to lock enums: {}", e)))?;
+                    enums.clone()
+                };
                 
                 let runtime_clone = self.runtime.clone();
                 let future_clone = (**future).clone();
-                let recursion_depth_clone = self.recursion_depth.clone();
+                let max_recursion_depth = thread_depth(self.max_recursion_depth);
+                let modules_clone = self.modules.clone();
+                let import_chain = self.import_chain.clone();
+                let input_clone = self.input.clone();
                 
                 let handle = self.runtime.spawn_blocking(move || {
                     let runtime_for_block_on = runtime_clone.clone();
                     let thread_runtime = MurlocRuntime {
                         env: Environment {
-                            variables: Arc::new(Mutex::new(vars_copy)),
+                            variables: Scope::from_vars(vars_copy),
                             functions: Arc::new(Mutex::new(funcs_copy)),
                             structs: Arc::new(Mutex::new(structs_copy)),
+                            enums: Arc::new(Mutex::new(enums_copy)),
                             exports: Arc::new(Mutex::new(HashMap::new())),
                         },
                         async_manager: AsyncManager::new(),
-                        recursion_depth: recursion_depth_clone,
-                        max_recursion_depth: 500,
+                        call_stack: CallStack::new(),
+                        max_recursion_depth,
                         runtime: runtime_clone,
+                        modules: modules_clone,
+                        import_chain,
+                        input: input_clone,
                     };
                     
-                    runtime_for_block_on.block_on(thread_runtime.exec_block_impl(&[future_clone]))
+                    runtime_for_block_on.block_on(thread_runtime.resolve_future(&future_clone))
                 });
-                
-                self.async_manager.register_thread(thread_name.clone(), handle)?;
-                Ok(())
+
+                // `grrr fut = argl grrrblbl f()` binds a handle that `mrgargl fut` waits on.
+                match thread_name {
+                    Some(name) => self.env.set_var(name.clone(), Value::Thread(ThreadHandle::new(handle))),
+                    None => self.async_manager.register_thread(None, handle),
+                }
             },
-            Statement::ThreadPool { size, tasks } => {
-                let size_value = match self.env.evaluate(size)? {
+            StatementKind::ThreadPool { size, tasks } => {
+                let size_value = match self.env.evaluate_with_runtime(size, self)? {
                     Value::Number(n) => n as usize,
                     _ => return Err(RuntimeError::TypeError("Thread pool size must be a number".to_string()).into()),
                 };
                 
                 let pool = rayon::ThreadPoolBuilder::new()
                     .num_threads(size_value)
+                    .stack_size(stack_size(thread_depth(self.max_recursion_depth)))
                     .build()
                     .map_err(|e| RuntimeError::InvalidOperation(format!("Failed to create thread pool: {}", e)))?;
                 
                 pool.install(|| {
                     for task in tasks {
                         let task_clone = task.clone();
+                        let runtime_clone = self.thread_runtime();
                         
                         pool.spawn(move || {
-                            let rtm = Runtime::new()
-                                .map_err(|e| RuntimeError::InvalidOperation(format!("Failed to create runtime: {}", e)))
-                                .expect("Failed to create runtime");
-                            let runtime_clone = MurlocRuntime::new();
-                            
-                            if let Err(e) = rtm.block_on(runtime_clone.execute_statement(&task_clone)) {
-                                error!("Failed to execute task in pool: {:?}", e);
+                            if let Err(e) = run_to_completion(runtime_clone.execute_statement(&task_clone)) {
+                                error!("Failed to execute task in pool: {}", e);
                             }
                         });
                     }
@@ -195,75 +277,23 @@ impl MurlocRuntime {
                 
                 Ok(())
             },
-            Statement::Wait { thread_names } => {
-                self.wait_for_threads(thread_names)?;
-                Ok(())
-            },
-            Statement::Await { future } => {
-                let future_clone = (**future).clone();
-                
-                let result_variables = Arc::new(Mutex::new(HashMap::new()));
-                
-                let env_clone = Environment {
-                    variables: self.env.variables.clone(),
-                    functions: self.env.functions.clone(),
-                    structs: self.env.structs.clone(),
-                    exports: self.env.exports.clone(),
-                };
-                
-                let runtime_clone = self.runtime.clone();
-                let max_recursion = self.max_recursion_depth;
-                let recursion_depth = self.recursion_depth.clone();
-  
-                {
-                    let current_depth = recursion_depth.lock()
-                        .map_err(|e| RuntimeError::LockError(format!("Failed to lock recursion depth: {}", e)))?;
-                    if *current_depth > max_recursion / 2 {
-                        return Err(RuntimeError::InvalidOperation(
-                            format!("Excessive recursion detected while awaiting future. Current depth: {}", 
-                                   *current_depth)
-                        ).into());
+            StatementKind::Wait { thread_names } => {
+                // Variables holding thread handles are joined; other names are threads started by `splurg name`.
+                let mut named = Vec::new();
+                for name in thread_names {
+                    match self.env.get_var(name) {
+                        Ok(value @ (Value::Thread(_) | Value::Array(_))) => { join_threads(value)?; },
+                        _ => named.push(name.clone()),
                     }
                 }
-                
-                let result_vars_clone = result_variables.clone();
-                
-                let thread_runtime = MurlocRuntime {
-                    env: env_clone,
-                    async_manager: AsyncManager::new(),
-                    recursion_depth: recursion_depth,
-                    max_recursion_depth: max_recursion,
-                    runtime: runtime_clone.clone(),
-                };
-                
-                let result = std::thread::spawn(move || {
-                    runtime_clone.block_on(async move {
-                        let result = thread_runtime.exec_block_impl(&[future_clone]).await;
-                        
-                        if result.is_ok() {
-                            let vars = thread_runtime.env.variables.lock()
-                                .map_err(|e| RuntimeError::LockError(format!("Failed to lock variables: {}", e)))?;
-                            if let Some(return_val) = vars.get("retorno") {
-                                let mut result_vars = result_vars_clone.lock()
-                                    .map_err(|e| RuntimeError::LockError(format!("Failed to lock result variables: {}", e)))?;
-                                result_vars.insert("retorno".to_string(), return_val.clone());
-                            }
-                        }
-                        
-                        result
-                    })
-                }).join()
-                .map_err(|e| RuntimeError::InvalidOperation(format!("Failed to join thread: {:?}", e)))?;
-                
-                if let Ok(()) = result {
-                    let result_vars = result_variables.lock()
-                        .map_err(|e| RuntimeError::LockError(format!("Failed to lock result variables: {}", e)))?;
-                    if let Some(return_val) = result_vars.get("retorno") {
-                        self.env.set_var("retorno".to_string(), return_val.clone());
-                    }
+                if !named.is_empty() {
+                    self.wait_for_threads(&named)?;
                 }
-                
-                result
+                Ok(())
+            },
+            StatementKind::Await { future } => {
+                self.await_future(future)?;
+                Ok(())
             },
             _ => self.execute_non_async_statement(statement).await,
         }
@@ -273,110 +303,34 @@ impl MurlocRuntime {
     where
         Self: Send + Sync,
     {
-        match statement {
-            Statement::Import { path, imports } => {
-                let contents = fs::read_to_string(path)
-                    .map_err(|e| RuntimeError::InvalidOperation(format!("Error importing '{}': {}", path, e)))?;
-                
-                let spanned_tokens = crate::lexer::tokenize(&contents)
-                    .map_err(|e| RuntimeError::LexerError(format!("At file {}: {}", path, e.message)))?;
-                
-                let tokens: Vec<crate::lexer::Token> = spanned_tokens.iter().map(|t| t.token.clone()).collect();
-                
-                let positions: Vec<(usize, usize)> = spanned_tokens.iter().map(|t| (t.line, t.column)).collect();
-                
-                let imported_stmts = crate::parser::parse(tokens).map_err(|e| {
-                    match &e {
-                        ParseError::UnexpectedToken(msg) => {
-                            if let Some((line, column)) = positions.get(0) {
-                                ParseError::InvalidValue(format!("Error at line {}, column {}: {}", line, column, msg))
-                            } else {
-                                ParseError::InvalidValue(format!("Syntax error: {}", msg))
-                            }
-                        },
-                        _ => ParseError::InvalidValue(format!("Parse error: {}", e))
-                    }
-                })?;
-
-                let module_env = Environment::new();
-                let module_runtime = MurlocRuntime {
-                    env: module_env,
-                    async_manager: AsyncManager::new(),
-                    recursion_depth: self.recursion_depth.clone(),
-                    max_recursion_depth: self.max_recursion_depth,
-                    runtime: self.runtime.clone(),
-                };
-
-                module_runtime.exec_block_impl(&imported_stmts).await?;
-
+        match &statement.kind {
+            StatementKind::Import { path, imports } => {
+                let module = self.load_module(&statement.span.file, path).await?;
                 for import in imports {
-                    match import {
-                        ImportSpecifier::Default(name) => {
-                            let exports = module_runtime.env.exports.lock()
-                                .map_err(|e| RuntimeError::LockError(format!("Failed to lock exports: {}", e)))?;
-                            
-                            if let Some((export_name, _)) = exports.iter().find(|(_, is_default)| **is_default) {
-                                if let Ok(value) = module_runtime.env.get_var(export_name) {
-                                    self.env.set_var(name.clone(), value);
-                                }
-                            } else {
-                                return Err(RuntimeError::InvalidOperation(
-                                    format!("No default export found in module '{}'", path)
-                                ).into());
-                            }
-                        },
-                        ImportSpecifier::Named(original, alias) => {
-                            if module_runtime.env.is_exported(&original)? {
-                                if let Ok(value) = module_runtime.env.get_var(&original) {
-                                    self.env.set_var(alias.clone(), value);
-                                }
-                            } else {
-                                return Err(RuntimeError::InvalidOperation(
-                                    format!("Export '{}' not found in module '{}'", original, path)
-                                ).into());
-                            }
-                        },
-                        ImportSpecifier::Namespace(namespace) => {
-                            let exports = module_runtime.env.exports.lock()
-                                .map_err(|e| RuntimeError::LockError(format!("Failed to lock exports: {}", e)))?;
-                            
-                            let mut namespace_vars = HashMap::new();
-                            for (export_name, _) in exports.iter() {
-                                if let Ok(value) = module_runtime.env.get_var(export_name) {
-                                    namespace_vars.insert(export_name.clone(), value);
-                                }
-                            }
-                            
-                            self.env.set_var(namespace.clone(), Value::Struct(namespace.to_string(), namespace_vars.into_iter().collect()));
-                        },
-                        ImportSpecifier::Specific(name) => {
-                            if module_runtime.env.is_exported(&name)? {
-                                if let Ok(value) = module_runtime.env.get_var(&name) {
-                                    self.env.set_var(name.clone(), value);
-                                }
-                            } else {
-                                return Err(RuntimeError::InvalidOperation(
-                                    format!("Export '{}' not found in module '{}'", name, path)
-                                ).into());
-                            }
-                        },
-                    }
+                    self.bind_import(&module, path, import)?;
                 }
                 Ok(())
             },
-            Statement::Export { name, is_default } => {
+            StatementKind::Export { name, is_default } => {
+                if *is_default
+                    && let Some(existing) = self.env.default_export()?
+                    && existing != *name {
+                        return Err(RuntimeError::InvalidOperation(
+                            format!("Module already has a default export '{}'", existing)
+                        ).into());
+                    }
                 self.env.add_export(name.clone(), *is_default)?;
                 Ok(())
             },
-            Statement::Function { name, args, body, parent_scope: _ } => {
-                self.env.set_function(name.to_string(), args.clone(), body.clone());
+            StatementKind::Function { name, args, body, parent_scope } => {
+                self.define_function(name, args, body, parent_scope)?;
                 Ok(())
             },
-            Statement::VarDeclaration(name, value) => {
-                self.env.set_var(name.to_string(), value.clone());
+            StatementKind::VarDeclaration(name, value) => {
+                self.env.set_var(name.to_string(), value.clone())?;
                 Ok(())
             },
-            Statement::VarDeclarationExpr(name, expr) => {
+            StatementKind::VarDeclarationExpr(name, expr) => {
                 let value = self.env.evaluate_with_runtime(expr, self)?;
                 
                 if let Value::Struct(struct_name, fields) = &value {
@@ -397,59 +351,59 @@ impl MurlocRuntime {
                     }
                 }
                 
-                self.env.set_var(name.to_string(), value);
+                self.env.set_var(name.to_string(), value)?;
                 Ok(())
             },
-            Statement::Assignment(name, expr) => {
+            StatementKind::Assignment(name, expr) => {
                 let value = self.env.evaluate_with_runtime(expr, self)?;
-                self.env.set_var(name.to_string(), value.clone());
-                Ok(())
+                self.env.assign_var(name, value)
             },
-            Statement::CallFunction { name, args } => {
-                let (params, body) = self.env.get_function(name)?;
-
-                let mut local_vars: HashMap<String, Value> = HashMap::new();
-
-                if !args.is_empty() {
-                    if args.len() != params.len() {
-                        warn!("[WARN] Number of arguments ({}) different from number of parameters ({}) for function '{}'",
-                                args.len(), params.len(), name);
-                    }
-                    
-                    let env_vars = self.env.variables.lock().unwrap();
-                    
-                    for (i, arg) in args.iter().enumerate() {
-                        if i < params.len() {
-                            let arg_value = evaluate_expression(arg, &env_vars, Some(self))?;
-                            local_vars.insert(params[i].clone(), arg_value.clone());
-                        }
-                    }
-                }
-                
-                Box::pin(self.call_function_impl(name, local_vars, &body)).await
+            StatementKind::PathAssignment { name, path, value } => {
+                let mut keys = self.resolve_path(path)?;
+                let value = self.env.evaluate_with_runtime(value, self)?;
+                let last = keys.pop().expect("assignment path is never empty");
+                let updated = self.update_path(self.env.get_var(name)?, &keys, |target| self.set_element(target, &last, value))?;
+                self.env.assign_var(name, updated)
             },
-            Statement::IfStatement { condition, body, else_branch } => {
-                if evaluate_condition(condition, &self.env.variables.lock().unwrap(), Some(self)) {
-                    self.exec_block_impl(body).await?;
+            StatementKind::Remove { name, path } => {
+                let mut keys = self.resolve_path(path)?;
+                let Some(PathKey::Index(key)) = keys.pop() else {
+                    unreachable!("the parser only accepts removal of a map key")
+                };
+                let updated = self.update_path(self.env.get_var(name)?, &keys, |target| match target {
+                    Value::Map(mut entries) => {
+                        let position = entries.iter().position(|(k, _)| *k == key).ok_or_else(|| RuntimeError::IndexOutOfBounds(
+                            format!("key '{}' not found in map '{}'", key, name)
+                        ))?;
+                        entries.remove(position);
+                        Ok(Value::Map(entries))
+                    },
+                    other => Err(RuntimeError::TypeError(format!("'{}' is not a map but {}", name, other)).into()),
+                })?;
+                self.env.assign_var(name, updated)
+            },
+            StatementKind::CallFunction { name, args } => {
+                let args = args.iter()
+                    .map(|arg| self.env.evaluate_with_runtime(arg, self))
+                    .collect::<RuntimeResult<Vec<Value>>>()?;
+                Box::pin(self.invoke_function(name, args, &statement.span)).await?;
+                Ok(())
+            },
+            StatementKind::IfStatement { condition, body, else_branch } => {
+                if evaluate_condition(condition, &self.env.variables, Some(self))? {
+                    self.exec_scoped(body).await?;
                 } else if let Some(else_stmt) = else_branch {
                     self.execute_statement_boxed(else_stmt).await?;
                 }
                 Ok(())
             },
-            Statement::WhileLoop { condition, body } => {
+            StatementKind::WhileLoop { condition, body } => {
                 loop {
-                    let condition_result = {
-                        let vars = self.env.variables.lock()
-                            .map_err(|e| RuntimeError::LockError(format!("Failed to lock variables: {}", e)))?;
-                        let result = evaluate_condition(condition, &vars, Some(self));
-                        result
-                    };
-
-                    if !condition_result {
+                    if !evaluate_condition(condition, &self.env.variables, Some(self))? {
                         break;
                     }
 
-                    if let Err(e) = self.exec_block_impl(body).await {
+                    if let Err(e) = self.exec_scoped(body).await {
                         match &e {
                             ParseError::RuntimeError(RuntimeError::Break) => break,
                             ParseError::RuntimeError(RuntimeError::Continue) => continue,
@@ -459,111 +413,143 @@ impl MurlocRuntime {
                 }
                 Ok(())
             },
-            Statement::Print(expr) => {
+            StatementKind::Print(expr) => {
                 let value = self.env.evaluate_with_runtime(expr, self)?;
                 println!("[OUTPUT] {}", &value);
                 Ok(())
             },
-            Statement::Return(expr) => {
+            StatementKind::Return(expr) => {
                 let value = self.env.evaluate_with_runtime(expr, self)?;
-                self.env.set_var("retorno".to_string(), value.clone());
-  
-                return Err(RuntimeError::Return(value).into());
-            },
-            Statement::Read(name) => {
-                let mut input = String::new();
-                io::stdin().read_line(&mut input)
-                    .map_err(|e| RuntimeError::InvalidOperation(format!("Failed to read input: {}", e)))?;
-                let mut env = self.env.variables.lock()
-                    .map_err(|e| RuntimeError::LockError(format!("Failed to lock variables: {}", e)))?;
-                env.insert(name.to_string(), Value::Text(input.trim().to_string()));
+                Err(RuntimeError::Return(value).into())
+            },
+            StatementKind::Read(name) => {
+                let line = self.read_input(None)?;
+                self.env.set_var(name.to_string(), line)?;
                 Ok(())
             },
-            Statement::ArrayDeclaration { name, elements } => {
-                self.env.with_locked_vars(|env| {
-                    env.insert(name.to_string(), Value::Array(elements.clone()));
-                });
+            StatementKind::ArrayDeclaration { name, elements } => {
+                self.env.set_var(name.to_string(), Value::Array(elements.clone()))?;
                 Ok(())
             },
-            Statement::StructDeclaration { name, fields } => {
+            StatementKind::StructDeclaration { name, fields } => {
                 let mut structs = self.env.structs.lock()
                     .map_err(|e| RuntimeError::LockError(format!("Failed to lock structs: {}", e)))?;
                 structs.insert(name.to_string(), fields.clone());
                 Ok(())
             },
-            Statement::Loop { variable, start, end, body } => {
-                for i in *start..=*end {
-                    self.env.with_locked_vars(|env| {
-                        env.insert(variable.to_string(), Value::Number(i));
-                    });
-                    
-                    if let Err(e) = self.exec_block_impl(body).await {
+            StatementKind::EnumDeclaration { name, variants } => {
+                let mut enums = self.env.enums.lock()
+                    .map_err(|e| RuntimeError::LockError(format!("Failed to lock enums: {}", e)))?;
+                enums.insert(name.to_string(), variants.clone());
+                Ok(())
+            },
+            StatementKind::Loop { variable, start, end, step, body } => {
+                let start = self.env.evaluate_with_runtime(start, self)?;
+                let end = self.env.evaluate_with_runtime(end, self)?;
+                let step = match step {
+                    Some(step) => self.env.evaluate_with_runtime(step, self)?,
+                    None if end < start => Value::Number(-1),
+                    None => Value::Number(1),
+                };
+                if let Some(bad) = [&start, &end, &step].into_iter().find(|value| !value.is_number()) {
+                    return Err(RuntimeError::TypeError(format!("Range bounds and step must be numbers, not {}", bad)).into());
+                }
+                if step == Value::Number(0) {
+                    return Err(RuntimeError::InvalidOperation("A range cannot step by zero".to_string()).into());
+                }
+
+                let ascending = step > Value::Number(0);
+                let loop_runtime = self.with_env(self.env.child());
+                let mut current = start;
+                while if ascending { current <= end } else { current >= end } {
+                    loop_runtime.env.set_var(variable.to_string(), current.clone())?;
+
+                    if let Err(e) = loop_runtime.exec_scoped(body).await {
                         match &e {
                             ParseError::RuntimeError(RuntimeError::Break) => break,
-                            ParseError::RuntimeError(RuntimeError::Continue) => continue,
+                            ParseError::RuntimeError(RuntimeError::Continue) => {},
                             _ => return Err(e),
                         }
                     }
+
+                    current = BinaryOperator::Add.apply_numeric(&current, &step)
+                        .expect("range bounds are numbers")?;
                 }
                 Ok(())
             },
-            Statement::ForLoop { init_var, init_value, condition, increment_var, increment_expr, body } => {
-                let init_result = self.env.evaluate(init_value)?;
-                self.env.set_var(init_var.to_string(), init_result);
+            StatementKind::ForLoop { init_var, init_value, condition, increment_var, increment_expr, body } => {
+                let loop_runtime = self.with_env(self.env.child());
+                let loop_env = &loop_runtime.env;
+                let init_result = loop_env.evaluate_with_runtime(init_value, &loop_runtime)?;
+                loop_env.set_var(init_var.to_string(), init_result)?;
 
                 loop {
-                    let cond_result = self.env.evaluate(condition)?;
-
-                    let continue_loop = match cond_result {
-                        Value::Number(n) => n != 0,
-                        _ => return Err(RuntimeError::TypeError("Loop condition must return a number".to_string()).into()),
-                    };
-
-                    if !continue_loop {
+                    if !evaluate_condition(condition, &loop_env.variables, Some(&loop_runtime))? {
                         break;
                     }
 
-                    if let Err(e) = self.exec_block_impl(body).await {
+                    if let Err(e) = loop_runtime.exec_scoped(body).await {
                         match &e {
                             ParseError::RuntimeError(RuntimeError::Break) => break,
                             ParseError::RuntimeError(RuntimeError::Continue) => {
-                                let incr_result = self.env.evaluate(increment_expr)?;
-                                self.env.set_var(increment_var.to_string(), incr_result);
+                                let incr_result = loop_env.evaluate_with_runtime(increment_expr, &loop_runtime)?;
+                                loop_env.assign_var(increment_var, incr_result)?;
                                 continue;
                             },
                             _ => return Err(e),
                         }
                     }
 
-                    let incr_result = self.env.evaluate(increment_expr)?;
-                    self.env.set_var(increment_var.to_string(), incr_result);
+                    let incr_result = loop_env.evaluate_with_runtime(increment_expr, &loop_runtime)?;
+                    loop_env.assign_var(increment_var, incr_result)?;
                 }
                 Ok(())
             },
-            Statement::ForInLoop { iterator_var, array_name, body } => {
-                let array = self.env.get_var(array_name)?;
-                
-                match array {
-                    Value::Array(elements) => {
-                        for element in elements {
-                            self.env.set_var(iterator_var.clone(), element.clone());
-                            
-                            if let Err(e) = self.exec_block_impl(body).await {
-                                match &e {
-                                    ParseError::RuntimeError(RuntimeError::Break) => break,
-                                    ParseError::RuntimeError(RuntimeError::Continue) => continue,
-                                    _ => return Err(e),
-                                }
-                            }
+            StatementKind::ForInLoop { iterator_var, value_var, iterable, body } => {
+                let with_index = value_var.is_some();
+                let indexed = move |(index, element)| if with_index {
+                    Ok((Value::from_i64(index as i64), Some(element)))
+                } else {
+                    Ok((element, None))
+                };
+                // A channel is received from lazily, until it is closed and empty.
+                let entries: LoopEntries = match self.env.evaluate_with_runtime(iterable, self)? {
+                    Value::Array(elements) => Box::new(elements.into_iter().enumerate().map(indexed)),
+                    Value::Map(entries) => Box::new(entries.into_iter()
+                        .map(move |(key, value)| Ok((key, with_index.then_some(value))))),
+                    Value::Text(text) => Box::new(text.chars()
+                        .map(|ch| Value::Text(ch.to_string()))
+                        .collect::<Vec<_>>()
+                        .into_iter()
+                        .enumerate()
+                        .map(indexed)),
+                    Value::Channel(channel) => Box::new(channel.iter()
+                        .enumerate()
+                        .map(move |(index, received)| indexed((index, received?)))),
+                    other => return Err(RuntimeError::TypeError(format!("Cannot iterate over {}", other)).into()),
+                };
+
+                let loop_runtime = self.with_env(self.env.child());
+                for entry in entries {
+                    let (item, value) = entry?;
+                    loop_runtime.env.set_var(iterator_var.clone(), item)?;
+                    if let (Some(value_var), Some(value)) = (value_var, value) {
+                        loop_runtime.env.set_var(value_var.clone(), value)?;
+                    }
+
+                    if let Err(e) = loop_runtime.exec_scoped(body).await {
+                        match &e {
+                            ParseError::RuntimeError(RuntimeError::Break) => break,
+                            ParseError::RuntimeError(RuntimeError::Continue) => continue,
+                            _ => return Err(e),
                         }
-                        Ok(())
-                    },
-                    _ => Err(RuntimeError::TypeError(format!("Cannot iterate over non-array value: {}", array_name)).into()),
+                    }
                 }
+                Ok(())
             },
-            Statement::LoopBlock { body } => {
+            StatementKind::LoopBlock { body } => {
                 loop {
-                    if let Err(e) = self.exec_block_impl(body).await {
+                    if let Err(e) = self.exec_scoped(body).await {
                         match &e {
                             ParseError::RuntimeError(RuntimeError::Break) => break,
                             ParseError::RuntimeError(RuntimeError::Continue) => continue,
@@ -573,54 +559,114 @@ impl MurlocRuntime {
                 }
                 Ok(())
             },
-            Statement::SwitchStatement { value, cases, default } => {
-                let val = self.env.evaluate(value)?;
-                let mut matched = false;
-                
-                for (case_value, case_body) in cases {
-                    if &val == case_value {
-                        self.exec_block_impl(case_body).await?;
-                        matched = true;
-                        break;
+            StatementKind::SwitchStatement { value, cases, default } => {
+                let val = self.env.evaluate_with_runtime(value, self)?;
+
+                for case in cases {
+                    for pattern in &case.patterns {
+                        let mut bindings = Vec::new();
+                        if !pattern.matches(&val, &mut bindings) {
+                            continue;
+                        }
+
+                        let case_runtime = self.with_env(self.env.child());
+                        for (name, value) in bindings {
+                            case_runtime.env.set_var(name, value)?;
+                        }
+                        if let Some(guard) = &case.guard
+                            && !evaluate_condition(guard, &case_runtime.env.variables, Some(&case_runtime))?
+                        {
+                            continue;
+                        }
+                        return case_runtime.exec_block_impl(&case.body).await;
                     }
                 }
-                
-                if !matched {
-                    if let Some(default_body) = default {
-                        self.exec_block_impl(default_body).await?;
+
+                if let Some(default_body) = default {
+                    self.exec_scoped(default_body).await?;
+                }
+                Ok(())
+            },
+            StatementKind::WhenStatement { condition, body, alternatives, default } => {
+                // Unlike a grlbrr/blrrgl chain, every branch whose condition
+                // holds runs, and all conditions are read before any branch
+                // can change what they see.
+                let branches = std::iter::once((condition, body))
+                    .chain(alternatives.iter().map(|(condition, body)| (condition, body)))
+                    .map(|(condition, body)| Ok((evaluate_condition(condition, &self.env.variables, Some(self))?, body)))
+                    .collect::<RuntimeResult<Vec<_>>>()?;
+
+                let none_held = !branches.iter().any(|(holds, _)| *holds);
+                for (holds, body) in branches {
+                    if holds {
+                        self.exec_scoped(body).await?;
                     }
                 }
+                if let Some(default) = default.as_ref().filter(|_| none_held) {
+                    self.exec_scoped(default).await?;
+                }
                 Ok(())
             },
-            Statement::TryBlock { try_block, catch_param, catch_body } => {
-                let try_result = self.exec_block_impl(try_block).await;
-            
-                match try_result {
-                    Ok(_) => Ok(()),
-                    Err(err) => {
-                        if let Some(var_name) = catch_param {
-                            let error_value = Value::Text(err.to_string());
-                            self.env.set_var(var_name.to_string(), error_value);
-                        }
-                    
-                        self.exec_block_impl(catch_body).await
+            StatementKind::Throw(expr) => {
+                let value = self.env.evaluate_with_runtime(expr, self)?;
+                // A rethrown error keeps pointing at where it was first raised.
+                let origin = match &value {
+                    Value::Error(error) => Some((error.span.clone(), error.stack.clone())),
+                    _ => None,
+                };
+                let mut err = ParseError::from(RuntimeError::Thrown(value));
+                if let Some((span, stack)) = origin {
+                    if let Some(span) = span {
+                        err = err.with_span(&span);
                     }
+                    err = err.with_trace(|| stack);
                 }
-            },            
-            Statement::Break => {
-                return Err(RuntimeError::Break.into());
+                Err(err)
             },
-            Statement::Continue => {
-                return Err(RuntimeError::Continue.into());
+            StatementKind::TryBlock { try_block, catches, finally } => {
+                let result = match self.exec_scoped(try_block).await {
+                    // Loop and return signals are not errors; they pass through to the loop or call.
+                    Err(err) if !err.is_signal() => {
+                        let error = ErrorValue::from_error(&err);
+                        match catches.iter().find(|clause| clause.handles(&error)) {
+                            Some(clause) => {
+                                let catch_runtime = self.with_env(self.env.child());
+                                if let Some(var_name) = &clause.param {
+                                    catch_runtime.env.set_var(var_name.to_string(), Value::Error(Arc::new(error)))?;
+                                }
+                                catch_runtime.exec_block_impl(&clause.body).await
+                            },
+                            None => Err(err),
+                        }
+                    },
+                    result => result,
+                };
+
+                // The finally block runs however the rest ended; its own error or signal wins.
+                match finally {
+                    Some(finally) => self.exec_scoped(finally).await.and(result),
+                    None => result,
+                }
             },
-            Statement::Sync { name } => {
-                let mut threads = self.async_manager.threads.lock()
-                    .map_err(|e| RuntimeError::LockError(format!("Failed to lock threads: {}", e)))?;
-                if let Some(handle) = threads.remove(name) {
+            StatementKind::Break => {
+                Err(RuntimeError::Break.into())
+            },
+            StatementKind::Continue => {
+                Err(RuntimeError::Continue.into())
+            },
+            StatementKind::Sync { name } => {
+                let handle = self.async_manager.threads.lock()
+                    .map_err(|e| RuntimeError::LockError(format!("Failed to lock threads: {}", e)))?
+                    .remove(name);
+                if let Some(handle) = handle {
                     handle.await.map_err(|e| RuntimeError::AsyncError(format!("Error waiting for thread: {}", e)))??;
                 }
                 Ok(())
             },
+            StatementKind::Expr(expr) => {
+                self.env.evaluate_with_runtime(expr, self)?;
+                Ok(())
+            },
             _ => Ok(())
         }
     }
@@ -632,132 +678,383 @@ impl MurlocRuntime {
         self.exec_block_impl(statements).await
     }
 
-    pub fn call_function_expr(&self, name: &str, args: Vec<Value>) -> RuntimeResult<Value> {
-        match self.env.execute_sync_function(name, args.clone()) {
-            Ok(result) => return Ok(result),
-            Err(_e) => {
-                let (param_names, body) = self.env.get_function(name)?;
+    /// Functions nested in another function are locals of that function's
+    /// frame; the rest go in the module's function table.
+    fn define_function(&self, name: &str, args: &[String], body: &[Statement], parent_scope: &Option<Vec<String>>) -> RuntimeResult<()> {
+        if parent_scope.is_some() {
+            let function = Function {
+                params: args.to_vec(),
+                body: body.to_vec(),
+                module: Some(self.env.clone()),
+                captured: Some(self.env.variables.clone()),
+            };
+            self.env.set_var(name.to_string(), Value::Function(Arc::new(function)))
+        } else {
+            self.env.set_function(name.to_string(), args.to_vec(), body.to_vec())
+        }
+    }
 
-                let mut call_vars = self.env.variables.lock()
-                    .map_err(|e| RuntimeError::LockError(format!("Failed to lock variables: {}", e)))?
-                    .clone();
+    /// Calls a function from inside an expression. Statement calls and
+    /// expression calls share [`Self::invoke_function`], so a body behaves the
+    /// same wherever it is called from.
+    pub fn call_function(&self, name: &str, args: Vec<Value>, call_site: &Span) -> RuntimeResult<Value> {
+        run_to_completion(Box::pin(self.invoke_function(name, args, call_site)))
+    }
 
-                if args.len() != param_names.len() {
-                    warn!("Number of arguments ({}) different from number of parameters ({}) for function '{}'", 
-                            args.len(), param_names.len(), name);
+    /// Calls a function value, such as one picked out of an array.
+    pub fn call_value(&self, function: Arc<Function>, args: Vec<Value>, call_site: &Span) -> RuntimeResult<Value> {
+        run_to_completion(Box::pin(self.invoke("<function>", function, args, call_site)))
+    }
 
-                    let args_to_use = if args.len() > param_names.len() {
-                        args[0..param_names.len()].to_vec()
-                    } else {
-                        args.clone()
-                    };
-                    
-                    for (i, param) in param_names.iter().enumerate() {
-                        if i < args_to_use.len() {
-                            call_vars.insert(param.clone(), args_to_use[i].clone());
-                        } else {
-                            call_vars.insert(param.clone(), Value::Number(0));
-                        }
-                    }
-                } else {
-                    for (param, arg) in param_names.iter().zip(args.iter()) {
-                        call_vars.insert(param.clone(), arg.clone());
+    async fn invoke_function(&self, name: &str, args: Vec<Value>, call_site: &Span) -> RuntimeResult<Value> {
+        let Some(function) = self.env.get_function_sync(name)? else {
+            return self.call_undefined(name, args);
+        };
+        self.invoke(name, function, args, call_site).await
+    }
+
+    /// Runs the whole body of `function` in a fresh frame and returns what it
+    /// hands back with `grrrtn`, or 0 if it never returns. The call sits on
+    /// the call stack as `name` while it runs.
+    async fn invoke(&self, name: &str, function: Arc<Function>, args: Vec<Value>, call_site: &Span) -> RuntimeResult<Value> {
+        if args.len() != function.params.len() {
+            return Err(RuntimeError::InvalidOperation(format!(
+                "Function '{}' expects {} arguments, but got {}",
+                name, function.params.len(), args.len()
+            )).into());
+        }
+
+        let function_env = function.frame(&self.env);
+        for (param, arg) in function.params.iter().zip(args) {
+            function_env.set_var(param.clone(), arg)?;
+        }
+
+        self.call_stack.push(CallFrame { function: name.to_string(), call_site: call_site.clone() }, self.max_recursion_depth)?;
+        let result = self.with_env(function_env).exec_block_impl(&function.body).await;
+        self.call_stack.pop();
+
+        match result {
+            Ok(()) => Ok(Value::Number(0)),
+            Err(ParseError::RuntimeError(RuntimeError::Return(value))) => Ok(value),
+            Err(e) => Err(e),
+        }
+    }
+    
+    /// Handles a call to a name the program never defined as a function: an
+    /// enum variant that carries values, or a builtin.
+    fn call_undefined(&self, name: &str, args: Vec<Value>) -> RuntimeResult<Value> {
+        if let Some((enum_name, variant)) = name.split_once('.')
+            && let Some(fields) = self.env.enum_variant(enum_name, variant)?
+        {
+            if args.len() != fields.len() {
+                return Err(RuntimeError::InvalidOperation(format!(
+                    "Variant '{}' carries {} values, but got {}",
+                    name, fields.len(), args.len()
+                )).into());
+            }
+            return Ok(Value::Enum(enum_name.to_string(), variant.to_string(), args));
+        }
+
+        match builtins::call(name, &args) {
+            Some(result) => Ok(result?),
+            None => Err(RuntimeError::UndefinedFunction(name.to_string()).into()),
+        }
+    }
+
+    /// Reads a line of input as text, showing `prompt` first if there is one.
+    pub fn read_input(&self, prompt: Option<&Value>) -> RuntimeResult<Value> {
+        if let Some(prompt) = prompt {
+            let mut stdout = io::stdout();
+            write!(stdout, "{}", prompt)
+                .and_then(|_| stdout.flush())
+                .map_err(|e| RuntimeError::InvalidOperation(format!("Failed to show prompt: {}", e)))?;
+        }
+        Ok(Value::Text(self.input.read_line()?))
+    }
+
+    /// Runs `future` to completion and hands back what it produced: a call's
+    /// return value, an expression's value, or 0 for any other statement. A
+    /// thread handle, from `splurg` or a named `argl` call, is waited for and
+    /// gives back its result.
+    pub fn await_future(&self, future: &Statement) -> RuntimeResult<Value> {
+        match run_to_completion(Box::pin(self.resolve_future(future)))? {
+            thread @ Value::Thread(_) => join_threads(thread),
+            value => Ok(value),
+        }
+    }
+
+    async fn resolve_future(&self, future: &Statement) -> RuntimeResult<Value> {
+        match &future.kind {
+            StatementKind::CallFunction { name, args } => {
+                let args = args.iter()
+                    .map(|arg| self.env.evaluate_with_runtime(arg, self))
+                    .collect::<RuntimeResult<Vec<Value>>>()?;
+                Box::pin(self.invoke_function(name, args, &future.span)).await
+                    .map_err(|err| err.with_span(&future.span))
+            },
+            StatementKind::Expr(expr) => self.env.evaluate_with_runtime(expr, self),
+            _ => {
+                self.exec_block_impl(std::slice::from_ref(future)).await?;
+                Ok(Value::Number(0))
+            },
+        }
+    }
+
+    async fn load_module(&self, importer: &str, path: &str) -> RuntimeResult<Module> {
+        let resolved = modules::resolve(importer, path)?;
+        if let Some(module) = self.modules.start_loading(&resolved, &self.import_chain)? {
+            return Ok(module);
+        }
+
+        let result = self.execute_module(&resolved).await;
+
+        self.modules.finish_loading(&resolved, result.as_ref().ok().cloned())?;
+        result
+    }
+
+    async fn execute_module(&self, path: &Path) -> RuntimeResult<Module> {
+        let file = path.display().to_string();
+        let contents = modules::read(path)
+            .map_err(|e| RuntimeError::FileError(format!("Error importing '{}': {}", file, e)))?;
+
+        let spanned_tokens = crate::lexer::tokenize(&contents).map_err(|e| e.into_parse_error(&file))?;
+        let statements = crate::parser::parse(TokenStream::new(spanned_tokens, file))
+            .map_err(|mut errors| errors.remove(0))?;
+
+        let mut import_chain = (*self.import_chain).clone();
+        import_chain.push(path.to_path_buf());
+        let module_runtime = MurlocRuntime {
+            import_chain: Arc::new(import_chain),
+            ..self.with_env(Environment::new())
+        };
+        module_runtime.exec_program(&statements).await?;
+
+        Ok(Module { path: path.to_path_buf(), env: module_runtime.env })
+    }
+
+    fn bind_import(&self, module: &Module, path: &str, import: &ImportSpecifier) -> RuntimeResult<()> {
+        match import {
+            ImportSpecifier::Default(local) => {
+                let name = module.env.default_export()?.ok_or_else(|| RuntimeError::InvalidOperation(
+                    format!("No default export found in module '{}'", path)
+                ))?;
+                self.import_name(module, path, &name, local)
+            },
+            ImportSpecifier::Specific(name) => self.import_name(module, path, name, name),
+            ImportSpecifier::Named(original, alias) => self.import_name(module, path, original, alias),
+            ImportSpecifier::Namespace(namespace) => {
+                let mut fields = Vec::new();
+                for name in module.env.export_names()? {
+                    if let Ok(value) = module.env.get_var(&name) {
+                        fields.push((name.clone(), value));
                     }
+                    self.import_definitions(module, &name, &format!("{}.{}", namespace, name))?;
                 }
-                
-                let function_env = Environment {
-                    variables: Arc::new(Mutex::new(call_vars)),
-                    functions: self.env.functions.clone(),
-                    structs: self.env.structs.clone(),
-                    exports: Arc::new(Mutex::new(HashMap::new())),
-                };
-                
-                let vars_arc = function_env.variables.clone();
-                
-                let function_runtime = MurlocRuntime {
-                    env: function_env,
-                    async_manager: AsyncManager::new(),
-                    recursion_depth: self.recursion_depth.clone(),
-                    max_recursion_depth: self.max_recursion_depth,
-                    runtime: self.runtime.clone(),
-                };
+                self.env.set_var(namespace.clone(), Value::Struct(namespace.clone(), fields))?;
+                Ok(())
+            },
+        }
+    }
 
-                let is_async = if let Some(first_stmt) = body.first() {
-                    matches!(first_stmt, Statement::AsyncFunction { .. })
-                } else {
-                    false
-                };
+    fn import_name(&self, module: &Module, path: &str, name: &str, local: &str) -> RuntimeResult<()> {
+        if !module.env.is_exported(name)? {
+            let hint = if module.env.get_var(name).is_ok() || module.env.get_function_sync(name)?.is_some() {
+                format!(" (it is private; add 'shrglr {}' to the module)", name)
+            } else {
+                String::new()
+            };
+            return Err(RuntimeError::InvalidOperation(
+                format!("Export '{}' not found in module '{}'{}", name, path, hint)
+            ).into());
+        }
 
-                let result = if is_async {
-                    std::thread::spawn(move || {
-                        function_runtime.runtime.block_on(function_runtime.exec_block_impl(&body))
-                    }).join().unwrap()
-                } else {
-                    self.runtime.block_on(function_runtime.exec_block_impl(&body))
-                };
-                
-                match result {
-                    Ok(()) => {
-                        let vars = vars_arc.lock().unwrap();
-                        if let Some(return_val) = vars.get("retorno") {
-                            Ok(return_val.clone())
-                        } else {
-                            Ok(Value::Number(0))
-                        }
-                    },
-                    Err(e) => Err(e)
+        let mut found = self.import_definitions(module, name, local)?;
+        if let Ok(value) = module.env.get_var(name) {
+            self.env.set_var(local.to_string(), value)?;
+            found = true;
+        }
+
+        if !found {
+            return Err(RuntimeError::InvalidOperation(
+                format!("'{}' is exported by module '{}' but never defined there", name, path)
+            ).into());
+        }
+        Ok(())
+    }
+
+    /// Copies the function and struct named `name` out of `module` under
+    /// `local`. Returns whether there was anything to copy.
+    fn import_definitions(&self, module: &Module, name: &str, local: &str) -> RuntimeResult<bool> {
+        let mut found = false;
+
+        if let Some(function) = module.env.get_function_sync(name)? {
+            let function = match function.module {
+                Some(_) => function,
+                None => Arc::new(Function { module: Some(module.env.clone()), ..(*function).clone() }),
+            };
+            self.env.functions.lock()
+                .map_err(|e| RuntimeError::LockError(format!("Failed to lock functions: {}", e)))?
+                .insert(local.to_string(), function);
+            found = true;
+        }
+
+        let fields = module.env.structs.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock structs: {}", e)))?
+            .get(name)
+            .cloned();
+        if let Some(fields) = fields {
+            self.env.structs.lock()
+                .map_err(|e| RuntimeError::LockError(format!("Failed to lock structs: {}", e)))?
+                .insert(local.to_string(), fields);
+            found = true;
+        }
+
+        let variants = module.env.enums.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock enums: {}", e)))?
+            .get(name)
+            .cloned();
+        if let Some(variants) = variants {
+            self.env.enums.lock()
+                .map_err(|e| RuntimeError::LockError(format!("Failed to lock enums: {}", e)))?
+                .insert(local.to_string(), variants);
+            found = true;
+        }
+
+        Ok(found)
+    }
+
+    /// Checks every `murrrgh` in `statements`, against the enums they
+    /// declare and the ones this runtime already knows.
+    fn check_matches(&self, statements: &[Statement]) -> RuntimeResult<()> {
+        let mut enums = self.env.enums.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock enums: {}", e)))?
+            .clone();
+        for statement in statements {
+            statement.walk(&mut |statement| {
+                if let StatementKind::EnumDeclaration { name, variants } = &statement.kind {
+                    enums.insert(name.clone(), variants.clone());
                 }
-            }
+                Ok::<(), ParseError>(())
+            })?;
+        }
+
+        for statement in statements {
+            statement.walk(&mut |statement| match &statement.kind {
+                StatementKind::SwitchStatement { cases, default, .. } => check_cases(&enums, cases, default.is_some())
+                    .map_err(|err| ParseError::from(err).with_span(&statement.span)),
+                _ => Ok(()),
+            })?;
         }
+        Ok(())
+    }
+
+    /// Evaluates the indexes of an assignment target, left to right.
+    fn resolve_path(&self, path: &[PathSegment]) -> RuntimeResult<Vec<PathKey>> {
+        path.iter()
+            .map(|segment| match segment {
+                PathSegment::Index(index) => Ok(PathKey::Index(self.env.evaluate_with_runtime(index, self)?)),
+                PathSegment::Field(field) => Ok(PathKey::Field(field.clone())),
+            })
+            .collect()
     }
 
-    async fn call_function_impl(&self, _name: &str, local_vars: HashMap<String, Value>, body: &[Statement]) -> RuntimeResult<()> 
+    /// Rebuilds `target` with `update` applied to the value at the end of
+    /// `path`, writing every step back so field types are checked on the way up.
+    fn update_path<F>(&self, target: Value, path: &[PathKey], update: F) -> RuntimeResult<Value>
     where
-        Self: Send + Sync,
+        F: FnOnce(Value) -> RuntimeResult<Value>,
     {
-        let current_vars = self.env.variables.lock().unwrap().clone();
-        
-        for (param, value) in local_vars.iter() {
-            self.env.set_var(param.clone(), value.clone());
-        }
-        
-        let result = self.exec_block_impl(body).await;
-        
-        let retorno = if let Ok(ret) = self.env.get_var("return") {
-            Some(ret)
-        } else {
-            None
+        let Some((key, rest)) = path.split_first() else {
+            return update(target);
         };
-        
-        let mut vars = self.env.variables.lock().unwrap();
-        *vars = current_vars;
-        
-        if let Some(ret) = retorno {
-            vars.insert("return".to_string(), ret.clone());
-        }
-        
-        match result {
-            Err(ParseError::RuntimeError(RuntimeError::Return(_))) => Ok(()),
-            Err(e) => Err(e),
-            Ok(()) => Ok(())
+        let child = get_element(&target, key)?;
+        let child = self.update_path(child, rest, update)?;
+        self.set_element(target, key, child)
+    }
+
+    fn set_element(&self, target: Value, key: &PathKey, value: Value) -> RuntimeResult<Value> {
+        match (target, key) {
+            (Value::Array(mut items), PathKey::Index(index)) => {
+                let position = Value::array_position(&items, index)?;
+                items[position] = value;
+                Ok(Value::Array(items))
+            },
+            (Value::Map(mut entries), PathKey::Index(index)) => {
+                Value::map_insert(&mut entries, index.clone(), value)?;
+                Ok(Value::Map(entries))
+            },
+            (Value::Struct(struct_name, mut fields), PathKey::Field(field)) => {
+                let declared = {
+                    let structs = self.env.structs.lock()
+                        .map_err(|e| RuntimeError::LockError(format!("Failed to lock structs: {}", e)))?;
+                    structs.get(&struct_name)
+                        .and_then(|declared| declared.iter().find(|(name, _)| name == field))
+                        .map(|(_, field_type)| field_type.clone())
+                };
+                if let Some(expected_type) = declared
+                    && !expected_type.accepts(&value)
+                {
+                    return Err(RuntimeError::TypeError(format!(
+                        "Type mismatch in struct '{}' field '{}': expected {}, found {}",
+                        struct_name, field, expected_type, value
+                    )).into());
+                }
+                let slot = fields.iter_mut().find(|(name, _)| name == field).ok_or_else(|| RuntimeError::InvalidOperation(
+                    format!("Field '{}' does not exist in struct '{}'", field, struct_name)
+                ))?;
+                slot.1 = value;
+                Ok(Value::Struct(struct_name, fields))
+            },
+            (other, PathKey::Index(_)) => Err(RuntimeError::TypeError(format!("Cannot index into {}", other)).into()),
+            (other, PathKey::Field(field)) => Err(RuntimeError::TypeError(
+                format!("Cannot set field '{}' on {}", field, other)
+            ).into()),
         }
     }
-    
-    pub fn create_thread_runtime(&self, vars_copy: HashMap<String, Value>, funcs_copy: HashMap<String, (Vec<String>, Vec<Statement>)>, structs_copy: HashMap<String, Vec<(String, Type)>>) -> MurlocRuntime {
+
+    /// A runtime for another thread that shares this scope's variables and
+    /// the program's declarations, with a call stack of its own.
+    fn thread_runtime(&self) -> MurlocRuntime {
         MurlocRuntime {
             env: Environment {
-                variables: Arc::new(Mutex::new(vars_copy)),
-                functions: Arc::new(Mutex::new(funcs_copy)),
-                structs: Arc::new(Mutex::new(structs_copy)),
+                variables: self.env.variables.child(),
+                functions: self.env.functions.clone(),
+                structs: self.env.structs.clone(),
+                enums: self.env.enums.clone(),
                 exports: Arc::new(Mutex::new(HashMap::new())),
             },
             async_manager: AsyncManager::new(),
-            recursion_depth: self.recursion_depth.clone(),
-            max_recursion_depth: 500,
+            call_stack: CallStack::new(),
+            max_recursion_depth: thread_depth(self.max_recursion_depth),
             runtime: self.runtime.clone(),
+            modules: self.modules.clone(),
+            import_chain: self.import_chain.clone(),
+            input: self.input.clone(),
         }
     }
 
+    /// Starts `body` on a thread of its own that shares this scope's
+    /// variables. The thread gives back what its body returns with `grrrtn`.
+    pub fn spawn_thread(&self, body: &[Statement]) -> JoinHandle<RuntimeResult<Value>> {
+        let thread_runtime = self.thread_runtime();
+        let runtime_for_block_on = self.runtime.clone();
+        let body_clone = body.to_vec();
+        
+        self.runtime.spawn_blocking(move || {
+            match runtime_for_block_on.block_on(async {
+                tokio::time::timeout(
+                    Duration::from_secs(30),
+                    thread_runtime.exec_block_impl(&body_clone)
+                ).await
+            }) {
+                Ok(Ok(())) => Ok(Value::Number(0)),
+                Ok(Err(ParseError::RuntimeError(RuntimeError::Return(value)))) => Ok(value),
+                Ok(Err(e)) => Err(e),
+                Err(_) => Err(RuntimeError::AsyncError("Thread timeout after 30 seconds".to_string()).into())
+            }
+        })
+    }
+
     pub fn wait_for_threads(&self, names: &[String]) -> RuntimeResult<()> {
         let mut handles = Vec::new();
         let names_cloned = names.to_vec();
@@ -781,14 +1078,16 @@ impl MurlocRuntime {
         
         let runtime_clone = self.runtime.clone();
         
-        let result = std::thread::spawn(move || {
+        
+        
+        std::thread::spawn(move || {
             runtime_clone.block_on(async move {
                 for handle in handles {
                     match handle.await {
                         Ok(result) => {
                             match result {
                                 Ok(_) => (),
-                                Err(e) => error!("Thread completed with error: {:?}", e)
+                                Err(e) => error!("Thread completed with error: {}", e)
                             }
                         },
                         Err(e) => {
@@ -800,37 +1099,124 @@ impl MurlocRuntime {
                 Ok(())
             })
         }).join()
-        .map_err(|e| RuntimeError::InvalidOperation(format!("Failed to join thread: {:?}", e)))?;
-        
-        result
+        .map_err(|_| RuntimeError::InvalidOperation("Failed to join thread: it panicked".to_string()))?
     }
+}
 
-    pub fn call_function_from_expression(&self, name: &str, args: Vec<Expression>) -> RuntimeResult<Value> {
-        let (param_names, body) = self.env.get_function(name)?;
-        
-        let evaluated_args = args.iter()
-            .map(|arg| self.env.evaluate(arg))
-            .collect::<Result<Vec<Value>, ParseError>>()?;
+/// The items a `mrrg ... blgr` loop walks, each with the value for its
+/// second variable when it has one.
+type LoopEntries = Box<dyn Iterator<Item = Result<(Value, Option<Value>), RuntimeError>> + Send>;
 
-        let mut function_env = self.env.variables.lock()
-            .map_err(|e| RuntimeError::LockError(format!("Failed to lock variables: {}", e)))?
-            .clone();
+/// A step of an assignment target once its index has been evaluated.
+enum PathKey {
+    Index(Value),
+    Field(String),
+}
+
+fn get_element(target: &Value, key: &PathKey) -> RuntimeResult<Value> {
+    let element = match key {
+        PathKey::Index(index) => target.index(index),
+        PathKey::Field(field) => target.field(field),
+    };
+    Ok(element?)
+}
+
+fn build_tokio_runtime(max_recursion_depth: usize) -> RuntimeResult<Runtime> {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .thread_stack_size(stack_size(max_recursion_depth))
+        .enable_all()
+        .build()
+        .map_err(|e| RuntimeError::AsyncError(format!("Failed to create Tokio runtime: {}", e)).into())
+}
 
-        for (param, arg) in param_names.iter().zip(evaluated_args.iter()) {
-            function_env.insert(param.clone(), arg.clone());
+/// How many calls may run at once on a thread the program starts.
+fn thread_depth(max_recursion_depth: usize) -> usize {
+    max_recursion_depth.min(DEFAULT_MAX_RECURSION_DEPTH)
+}
+
+fn stack_size(max_recursion_depth: usize) -> usize {
+    BASE_STACK_SIZE.saturating_add(max_recursion_depth.saturating_mul(STACK_PER_CALL))
+}
+
+/// Drives `future` on the current thread, parking it while the future waits.
+/// Unlike the tokio and futures executors this may nest, which expression
+/// calls need: a function body can call functions from its own expressions.
+pub(crate) fn run_to_completion<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
         }
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Checks that every variant pattern names a real variant with the right
+/// number of values and, unless a catch-all arm exists, that a `murrrgh`
+/// over an enum handles each of its variants.
+fn check_cases(enums: &EnumTable, cases: &[MatchCase], has_default: bool) -> Result<(), RuntimeError> {
+    let mut matched_enum = None;
+    let mut covered = Vec::new();
 
-        let mut result = Value::Number(0);
-        for stmt in body {
-            match stmt {
-                Statement::Return(expr) => {
-                    result = self.env.evaluate(&expr)?;
-                    break;
-                },
-                _ => continue,
+    for case in cases {
+        for pattern in &case.patterns {
+            let Pattern::Variant { enum_name, variant, fields } = pattern else {
+                continue;
+            };
+            let variants = enums.get(enum_name).ok_or_else(|| RuntimeError::InvalidOperation(
+                format!("Enum '{}' not found in the cosmic void", enum_name)
+            ))?;
+            let (_, declared) = variants.iter().find(|(name, _)| name == variant).ok_or_else(|| RuntimeError::InvalidOperation(
+                format!("Enum '{}' has no variant '{}'", enum_name, variant)
+            ))?;
+            if let Some(fields) = fields
+                && fields.len() != declared.len()
+            {
+                return Err(RuntimeError::InvalidOperation(format!(
+                    "Variant '{}.{}' carries {} values, but the pattern has {}",
+                    enum_name, variant, declared.len(), fields.len()
+                )));
+            }
+
+            matched_enum.get_or_insert(enum_name);
+            let exhaustive = fields.as_ref().is_none_or(|fields| fields.iter().all(Pattern::is_irrefutable));
+            if case.guard.is_none() && exhaustive && matched_enum == Some(enum_name) {
+                covered.push(variant);
             }
         }
-        
-        Ok(result)
     }
-} 
\ No newline at end of file
+
+    let catch_all = cases.iter()
+        .any(|case| case.guard.is_none() && case.patterns.iter().any(Pattern::is_irrefutable));
+    let Some(enum_name) = matched_enum else {
+        return Ok(());
+    };
+    if has_default || catch_all {
+        return Ok(());
+    }
+
+    let missing = enums.get(enum_name)
+        .map(|variants| variants.iter()
+            .filter(|(variant, _)| !covered.contains(&variant))
+            .map(|(variant, _)| format!("{}.{}", enum_name, variant))
+            .collect::<Vec<_>>())
+        .unwrap_or_default();
+    if !missing.is_empty() {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "This murrrgh over '{}' does not handle {}; add the missing cases or a blrrghlt",
+            enum_name, missing.join(", ")
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::ast::Value;
+use crate::interpreter::error::RuntimeError;
+
+/// A chain of variable frames. Blocks, loops and function calls each run in a
+/// frame of their own; lookups and assignments walk outward from the innermost
+/// frame until they reach the globals.
+#[derive(Clone, Default)]
+pub struct Scope {
+    frame: Arc<Mutex<HashMap<String, Value>>>,
+    parent: Option<Arc<Scope>>,
+}
+
+impl Scope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_vars(vars: HashMap<String, Value>) -> Self {
+        Self {
+            frame: Arc::new(Mutex::new(vars)),
+            parent: None,
+        }
+    }
+
+    /// A new, empty frame nested inside this one.
+    pub fn child(&self) -> Scope {
+        Scope {
+            frame: Arc::new(Mutex::new(HashMap::new())),
+            parent: Some(Arc::new(self.clone())),
+        }
+    }
+
+    /// The outermost frame of the chain.
+    pub fn global(&self) -> Scope {
+        let mut scope = self;
+        while let Some(parent) = &scope.parent {
+            scope = parent;
+        }
+        scope.clone()
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<String, Value>>, RuntimeError> {
+        self.frame.lock()
+            .map_err(|e| RuntimeError::LockError(format!("Failed to lock scope: {}", e)))
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<Value>, RuntimeError> {
+        if let Some(value) = self.lock()?.get(name) {
+            return Ok(Some(value.clone()));
+        }
+        match &self.parent {
+            Some(parent) => parent.get(name),
+            None => Ok(None),
+        }
+    }
+
+    /// Binds `name` in the innermost frame, shadowing any outer binding.
+    pub fn declare(&self, name: impl Into<String>, value: Value) -> Result<(), RuntimeError> {
+        self.lock()?.insert(name.into(), value);
+        Ok(())
+    }
+
+    /// Updates the nearest binding of `name`. Returns `false` when no frame in
+    /// the chain declares it.
+    pub fn assign(&self, name: &str, value: Value) -> Result<bool, RuntimeError> {
+        {
+            let mut frame = self.lock()?;
+            if let Some(slot) = frame.get_mut(name) {
+                *slot = value;
+                return Ok(true);
+            }
+        }
+        match &self.parent {
+            Some(parent) => parent.assign(name, value),
+            None => Ok(false),
+        }
+    }
+
+    /// Every visible binding in a single map, inner frames winning.
+    pub fn flatten(&self) -> Result<HashMap<String, Value>, RuntimeError> {
+        let mut vars = match &self.parent {
+            Some(parent) => parent.flatten()?,
+            None => HashMap::new(),
+        };
+        vars.extend(self.lock()?.iter().map(|(name, value)| (name.clone(), value.clone())));
+        Ok(vars)
+    }
+}
@@ -1,5 +1,13 @@
+use std::cell::RefCell;
+use std::fmt;
 use std::iter::Peekable;
+use std::ops::Index;
 use std::str::Chars;
+use std::sync::Arc;
+
+use crate::ast::Span;
+use crate::interpreter::RuntimeError;
+use crate::value_parser::ParseError;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
@@ -34,12 +42,148 @@ pub enum Token {
     Colon,        // :
     Semicolon,    // ;
     Dot,          // .
+    Range,        // ..
+}
+
+impl fmt::Display for Token {
+    /// Writes the token the way it appears in a scroll, keywords in their
+    /// murloc spelling.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Token::Keyword(name) => return write!(f, "{}", keyword_spelling(name)),
+            Token::Identifier(name) | Token::Number(name) => return write!(f, "{}", name),
+            Token::StringLiteral(text) => return write!(f, "\"{}\"", text),
+            Token::Assign => "=",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Multiply => "*",
+            Token::Divide => "/",
+            Token::Modulo => "%",
+            Token::LessThan => "<",
+            Token::GreaterThan => ">",
+            Token::LessEqual => "<=",
+            Token::GreaterEqual => ">=",
+            Token::Equal => "==",
+            Token::NotEqual => "!=",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::Not => "!",
+            Token::LeftParen => "(",
+            Token::RightParen => ")",
+            Token::LeftBrace => "{",
+            Token::RightBrace => "}",
+            Token::LeftBracket => "[",
+            Token::RightBracket => "]",
+            Token::Comma => ",",
+            Token::Colon => ":",
+            Token::Semicolon => ";",
+            Token::Dot => ".",
+            Token::Range => "..",
+        };
+        write!(f, "{}", symbol)
+    }
 }
 
+/// Names what the parser found where it wanted something else: the token as
+/// written, quoted, or the end of the file.
+pub fn describe_token(token: Option<&Token>) -> String {
+    match token {
+        Some(token @ Token::StringLiteral(_)) => token.to_string(),
+        Some(token) => format!("'{}'", token),
+        None => "end of file".to_string(),
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct SpannedToken {
     pub token: Token,
     pub line: usize,
     pub column: usize,
+    pub length: usize,
+}
+
+/// Tokens of a single source file, indexable like a plain `[Token]` slice but
+/// able to tell where each token came from.
+#[derive(Debug, Clone)]
+pub struct TokenStream {
+    tokens: Vec<SpannedToken>,
+    file: Arc<String>,
+    /// Syntax errors found in blocks inside expressions, like the body of an
+    /// anonymous function, until the statement holding them reports them.
+    nested_errors: RefCell<Vec<ParseError>>,
+}
+
+impl TokenStream {
+    pub fn new(tokens: Vec<SpannedToken>, file: impl Into<String>) -> Self {
+        Self {
+            tokens,
+            file: Arc::new(file.into()),
+            nested_errors: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Token> {
+        self.tokens.get(index).map(|t| &t.token)
+    }
+
+    pub fn file(&self) -> &Arc<String> {
+        &self.file
+    }
+
+    /// Records an error from a block nested in an expression, which cannot
+    /// fail the expression without losing the errors that follow it.
+    pub fn report_nested(&self, errors: Vec<ParseError>) {
+        self.nested_errors.borrow_mut().extend(errors);
+    }
+
+    /// Hands over the errors recorded by `report_nested` so far.
+    pub fn take_nested_errors(&self) -> Vec<ParseError> {
+        self.nested_errors.take()
+    }
+
+    /// Span of the token at `index`. Past the end of the stream this points just
+    /// after the last token, which is where "unexpected end" errors belong.
+    pub fn span(&self, index: usize) -> Span {
+        let (line, column, length) = match self.tokens.get(index) {
+            Some(t) => (t.line, t.column, t.length),
+            None => match self.tokens.last() {
+                Some(t) => (t.line, t.column + t.length, 1),
+                None => (1, 1, 1),
+            },
+        };
+        Span {
+            file: self.file.clone(),
+            line,
+            column,
+            length,
+        }
+    }
+
+    /// Span covering the tokens in `start..end`, cut short at the end of the
+    /// first line so it can be underlined.
+    pub fn span_from(&self, start: usize, end: usize) -> Span {
+        let first = self.span(start);
+        match end.checked_sub(1) {
+            Some(last) if last > start => first.to(&self.span(last)),
+            _ => first,
+        }
+    }
+}
+
+impl Index<usize> for TokenStream {
+    type Output = Token;
+
+    fn index(&self, index: usize) -> &Token {
+        &self.tokens[index].token
+    }
 }
 
 #[derive(Debug)]
@@ -49,6 +193,17 @@ pub struct LexerError {
     pub column: usize,
 }
 
+impl LexerError {
+    pub fn into_parse_error(self, file: &str) -> ParseError {
+        ParseError::from(RuntimeError::LexerError(self.message)).with_span(&Span {
+            file: Arc::new(file.to_string()),
+            line: self.line,
+            column: self.column,
+            length: 1,
+        })
+    }
+}
+
 const KEYWORDS: &[(&str, &str)] = &[
     ("grrr", "var"),
     ("grlbrr", "if"),
@@ -59,28 +214,48 @@ const KEYWORDS: &[(&str, &str)] = &[
     ("grl", "end"),
     ("gglrbl", "while"),
     ("murrrgh", "switch"),
+    ("wrrgl", "when"),
     ("flurg", "break"),
     ("flllurlog", "continue"),
     ("glglrr", "print"),
+    ("mrglisn", "read"),
     ("splurg", "spawn"),
     ("grrsync", "sync"),
     ("grrip", "array"),
     ("rrkgr", "struct"),
+    ("rrknum", "enum"),
     ("argl", "async"),
     ("mrgargl", "await"),
     ("mrgurl", "wait"),
     ("fshpool", "threadpool"),
     ("mrglgl", "try"),
     ("mrglurp", "catch"),
+    ("blurgh", "throw"),
+    ("mrglast", "finally"),
     ("blrrgl", "else"),
     ("grrrtn", "return"),
     ("blbtxt", "text"),
     ("numblrr", "number"),
+    ("boolgrr", "bool"),
+    ("grrmap", "map"),
+    ("grrmv", "remove"),
+    ("brrp", "step"),
+    ("mrgtru", "true"),
+    ("mrgfls", "false"),
     ("blgr", "in"),
     ("grlblgl", "case"),
     ("blrrghlt", "default"),
+    ("glurpin", "import"),
+    ("shrglr", "export"),
+    ("frmgl", "from"),
+    ("azgl", "as"),
 ];
 
+/// The murloc spelling of the keyword the lexer reads as `name`.
+pub fn keyword_spelling(name: &str) -> &str {
+    KEYWORDS.iter().find(|&&(_, keyword)| keyword == name).map_or(name, |&(spelling, _)| spelling)
+}
+
 pub struct Lexer<'a> {
     chars: Peekable<Chars<'a>>,
     line: usize,
@@ -109,12 +284,9 @@ impl<'a> Lexer<'a> {
                 }
 
                 '+' | '-' | '*' | '/' | '%' | '=' | '<' | '>' | '!' | '&' | '|' => {
-                    if let Ok(token) = self.process_operator(c) {
-                        if !(c == '/' && token.line == 0 && token.column == 0) {
-                            tokens.push(token);
-                        }
-                    } else if let Err(e) = self.process_operator(c) {
-                        return Err(e);
+                    let token = self.process_operator(c)?;
+                    if !(c == '/' && token.line == 0 && token.column == 0) {
+                        tokens.push(token);
                     }
                 }
 
@@ -127,20 +299,17 @@ impl<'a> Lexer<'a> {
                     Err(e) => return Err(e),
                 },
 
-                ch if ch.is_alphabetic() => {
+                ch if ch.is_alphabetic() || ch == '_' => {
                     tokens.push(self.process_identifier(ch));
                 }
 
-                ch if ch.is_digit(10) => {
-                    tokens.push(self.process_number(ch));
+                ch if ch.is_ascii_digit() => {
+                    tokens.push(self.process_number(ch)?);
                 }
 
                 _ => {
                     return Err(LexerError {
-                        message: format!(
-                            "Invalid character: '{}' at line {} column {}",
-                            c, self.line, self.column
-                        ),
+                        message: format!("Invalid character: '{}'", c),
                         line: self.line,
                         column: self.column,
                     });
@@ -170,23 +339,32 @@ impl<'a> Lexer<'a> {
                 token: Token::Keyword(name.to_string()),
                 line: self.line,
                 column: start_column,
+                length: self.column - start_column,
             }
         } else {
             SpannedToken {
                 token: Token::Identifier(ident),
                 line: self.line,
                 column: start_column,
+                length: self.column - start_column,
             }
         }
     }
 
-    fn process_number(&mut self, first_digit: char) -> SpannedToken {
+    fn process_number(&mut self, first_digit: char) -> Result<SpannedToken, LexerError> {
         let start_column = self.column;
         let mut num = first_digit.to_string();
         self.column += 1;
 
         while let Some(&ch) = self.chars.peek() {
-            if ch.is_digit(10) || ch == '.' && !num.contains('.') {
+            // A dot only belongs to the number when a digit follows it, so
+            // `1..10` reads as a range rather than `1.` and `.10`.
+            let fraction = ch == '.' && !num.contains('.') && {
+                let mut ahead = self.chars.clone();
+                ahead.next();
+                ahead.peek().is_some_and(|next| next.is_ascii_digit())
+            };
+            if ch.is_ascii_digit() || fraction {
                 num.push(self.chars.next().unwrap());
                 self.column += 1;
             } else {
@@ -194,11 +372,21 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        SpannedToken {
+        if let Some(&ch) = self.chars.peek()
+            && (ch.is_alphabetic() || ch == '_') {
+                return Err(LexerError {
+                    message: format!("Invalid number: '{}' is followed by '{}'", num, ch),
+                    line: self.line,
+                    column: self.column,
+                });
+            }
+
+        Ok(SpannedToken {
             token: Token::Number(num),
             line: self.line,
             column: start_column,
-        }
+            length: self.column - start_column,
+        })
     }
 
     fn process_string(&mut self) -> Result<SpannedToken, LexerError> {
@@ -206,10 +394,18 @@ impl<'a> Lexer<'a> {
         self.column += 1;
         let mut string = String::new();
         let mut escaped = false;
+        let mut closed = false;
+
+        let start_line = self.line;
 
-        while let Some(ch) = self.chars.next() {
+        for ch in self.chars.by_ref() {
             self.column += 1;
 
+            if ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            }
+
             if escaped {
                 match ch {
                     'n' => string.push('\n'),
@@ -223,6 +419,7 @@ impl<'a> Lexer<'a> {
             } else if ch == '\\' {
                 escaped = true;
             } else if ch == '"' {
+                closed = true;
                 break;
             } else {
                 string.push(ch);
@@ -231,23 +428,30 @@ impl<'a> Lexer<'a> {
 
         if escaped {
             return Err(LexerError {
-                message: format!(
-                    "Unterminated escape sequence in string at line {} column {}",
-                    self.line, start_column
-                ),
-                line: self.line,
+                message: "Unterminated escape sequence in string".to_string(),
+                line: start_line,
+                column: start_column,
+            });
+        }
+
+        if !closed {
+            return Err(LexerError {
+                message: "Unterminated string literal".to_string(),
+                line: start_line,
                 column: start_column,
             });
         }
 
         Ok(SpannedToken {
             token: Token::StringLiteral(string),
-            line: self.line,
+            line: start_line,
             column: start_column,
+            length: if self.line == start_line { self.column - start_column } else { 1 },
         })
     }
 
     fn process_operator(&mut self, operator: char) -> Result<SpannedToken, LexerError> {
+        let start_line = self.line;
         let start_column = self.column;
         self.column += 1;
 
@@ -260,8 +464,10 @@ impl<'a> Lexer<'a> {
                     self.chars.next();
                     self.column += 1;
 
-                    while let Some(ch) = self.chars.next() {
+                    for ch in self.chars.by_ref() {
                         if ch == '\n' {
+                            self.line += 1;
+                            self.column = 1;
                             break;
                         } else {
                             self.column += 1;
@@ -272,6 +478,7 @@ impl<'a> Lexer<'a> {
                         token: Token::Divide,
                         line: 0,
                         column: 0,
+                        length: 0,
                     });
                 } else if self.chars.peek() == Some(&'*') {
                     self.chars.next();
@@ -307,11 +514,8 @@ impl<'a> Lexer<'a> {
                             }
                             None => {
                                 return Err(LexerError {
-                                    message: format!(
-                                        "Block comment not closed at line {} column {}",
-                                        self.line, start_column
-                                    ),
-                                    line: self.line,
+                                    message: "Block comment not closed".to_string(),
+                                    line: start_line,
                                     column: start_column,
                                 });
                             }
@@ -322,6 +526,7 @@ impl<'a> Lexer<'a> {
                         token: Token::Divide,
                         line: 0,
                         column: 0,
+                        length: 0,
                     });
                 } else {
                     Token::Divide
@@ -371,10 +576,7 @@ impl<'a> Lexer<'a> {
                     Token::And
                 } else {
                     return Err(LexerError {
-                        message: format!(
-                            "Invalid token: expected '&&', found single '&' at line {} column {}",
-                            self.line, start_column
-                        ),
+                        message: "Invalid token: expected '&&', found single '&'".to_string(),
                         line: self.line,
                         column: start_column,
                     });
@@ -387,10 +589,7 @@ impl<'a> Lexer<'a> {
                     Token::Or
                 } else {
                     return Err(LexerError {
-                        message: format!(
-                            "Invalid token: expected '||', found single '|' at line {} column {}",
-                            self.line, start_column
-                        ),
+                        message: "Invalid token: expected '||', found single '|'".to_string(),
                         line: self.line,
                         column: start_column,
                     });
@@ -403,6 +602,7 @@ impl<'a> Lexer<'a> {
             token,
             line: self.line,
             column: start_column,
+            length: self.column - start_column,
         })
     }
 
@@ -410,6 +610,17 @@ impl<'a> Lexer<'a> {
         let start_column = self.column;
         self.column += 1;
 
+        if delimiter == '.' && self.chars.peek() == Some(&'.') {
+            self.chars.next();
+            self.column += 1;
+            return SpannedToken {
+                token: Token::Range,
+                line: self.line,
+                column: start_column,
+                length: 2,
+            };
+        }
+
         let token = match delimiter {
             '(' => Token::LeftParen,
             ')' => Token::RightParen,
@@ -428,6 +639,7 @@ impl<'a> Lexer<'a> {
             token,
             line: self.line,
             column: start_column,
+            length: 1,
         }
     }
 }
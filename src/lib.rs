@@ -4,6 +4,8 @@ pub mod ast;
 pub mod interpreter;
 pub mod value_parser;
 pub mod expression_parser;
+pub mod diagnostics;
+pub mod stdlib;
 
 pub use value_parser::ParseError;
 pub use lexer::tokenize;
@@ -1,7 +1,10 @@
-use mur_lang::lexer::{tokenize, Token as LexerToken};
+use mur_lang::lexer::{tokenize, TokenStream};
 use mur_lang::parser::parse;
-use mur_lang::interpreter::MurlocRuntime;
+use mur_lang::interpreter::{MurlocRuntime, DEFAULT_MAX_RECURSION_DEPTH, MAX_RECURSION_DEPTH};
 use mur_lang::value_parser::ParseError;
+use mur_lang::diagnostics::{Diagnostic, Emitter};
+use std::io::{BufReader, IsTerminal};
+use std::process::ExitCode;
 use std::time::Instant;
 use std::env;
 use std::fs;
@@ -28,6 +31,9 @@ fn show_help() {
     println!("  mrgl run <file.mur>    Run a Murlang program");
     println!("  mrgl help              Show this help message");
     println!("  mrgl --version         Show version information");
+    println!("\nOptions:");
+    println!("  --max-depth <n>        Allow at most <n> nested calls (default: {})", DEFAULT_MAX_RECURSION_DEPTH);
+    println!("  --input <file>         Read mrglisn input from <file> instead of the console");
     println!("\nExamples:");
     println!("  mrgl run hello.mur     Run the hello.mur program");
     println!("  mrgl help              Show this help message");
@@ -41,7 +47,11 @@ fn show_version() {
     println!("https://github.com/GabrielEstefanski/murlang/issues");
 }
 
-fn main() -> Result<(), ParseError> {
+fn use_color() -> bool {
+    std::io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none()
+}
+
+fn main() -> ExitCode {
     env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
 
     let args: Vec<String> = env::args().collect();
@@ -50,56 +60,99 @@ fn main() -> Result<(), ParseError> {
         match args[1].as_str() {
             "help" => {
                 show_help();
-                return Ok(());
+                return ExitCode::SUCCESS;
             }
             "--version" | "-V" => {
                 show_version();
-                return Ok(());
+                return ExitCode::SUCCESS;
             }
             _ => {}
         }
     }
 
+    let max_depth = match args.iter().position(|arg| arg == "--max-depth") {
+        Some(index) => match args.get(index + 1).and_then(|depth| depth.parse::<usize>().ok()) {
+            Some(depth) if (1..=MAX_RECURSION_DEPTH).contains(&depth) => depth,
+            _ => {
+                log(LogLevel::Error, &format!("--max-depth expects a number of calls from 1 to {}", MAX_RECURSION_DEPTH));
+                return ExitCode::FAILURE;
+            }
+        },
+        None => DEFAULT_MAX_RECURSION_DEPTH,
+    };
+
+    let input = match args.iter().position(|arg| arg == "--input") {
+        Some(index) => match args.get(index + 1).map(fs::File::open) {
+            Some(Ok(file)) => Some(file),
+            Some(Err(err)) => {
+                log(LogLevel::Error, &format!("Failed to open input file: {}", err));
+                return ExitCode::FAILURE;
+            }
+            None => {
+                log(LogLevel::Error, "--input expects a file to read from");
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+
     let file_path = args.iter().find(|s| s.ends_with(".mur"));
-    let source = match file_path {
+    let (path, source) = match file_path {
         Some(path) => match fs::read_to_string(path) {
             Ok(content) => {
                 log(LogLevel::Info, &format!("Executing file: {}", path));
-                content
+                (path, content)
             },
             Err(err) => {
                 log(LogLevel::Error, &format!("Failed to read file: {}", err));
-                return Err(ParseError::InvalidValue(format!("File read error: {}", err)));
+                return ExitCode::FAILURE;
             }
         },
         None => {
             log(LogLevel::Info, "No file provided.");
             show_help();
-            return Ok(());
+            return ExitCode::SUCCESS;
         }
     };
 
+    match run(path, &source, max_depth, input) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(errors) => {
+            let mut emitter = Emitter::new(use_color());
+            emitter.add_source(path.as_str(), source);
+            for err in &errors {
+                emitter.emit(&Diagnostic::from(err));
+            }
+            if errors.len() > 1 {
+                emitter.emit(&Diagnostic::error(format!("aborting the ritual due to {} previous errors", errors.len())));
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(path: &str, source: &str, max_depth: usize, input: Option<fs::File>) -> Result<(), Vec<ParseError>> {
     let total_start = Instant::now();
 
     let start = Instant::now();
     log(LogLevel::Info, "Tokenizing code...");
-    let spanned_tokens = tokenize(&source)
-        .map_err(|e| ParseError::InvalidValue(format!(
-            "Lexer error at line {}, column {}: {}", e.line, e.column, e.message)))?;
+    let spanned_tokens = tokenize(source).map_err(|e| vec![e.into_parse_error(path)])?;
     log(LogLevel::Info, &format!("Tokenizing completed in {:.2?}", start.elapsed()));
 
     let start = Instant::now();
     log(LogLevel::Info, "Parsing code...");
-    let tokens: Vec<LexerToken> = spanned_tokens.iter().map(|t| t.token.clone()).collect();
-    let statements = parse(tokens)?;
+    let statements = parse(TokenStream::new(spanned_tokens, path))?;
     log(LogLevel::Info, &format!("Parsing completed in {:.2?}", start.elapsed()));
 
     let start = Instant::now();
     log(LogLevel::Info, "Executing code...");
-    let runtime = MurlocRuntime::new();
-    runtime.run(statements)?;
+    let mut runtime = MurlocRuntime::with_max_recursion_depth(max_depth).map_err(|e| vec![e])?;
+    if let Some(file) = input {
+        runtime = runtime.with_input(BufReader::new(file));
+    }
+    runtime.run(statements).map_err(|e| vec![e])?;
     log(LogLevel::Info, &format!("Execution completed in {:.2?}", start.elapsed()));
 
     log(LogLevel::Info, &format!("Total runtime: {:.2?}", total_start.elapsed()));
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,127 +1,142 @@
-use crate::lexer::Token;
-use crate::ast::{Statement, Value, Expression, ImportSpecifier};
-use crate::expression_parser::parse_expression;
-use crate::value_parser::{parse_value, parse_type, ParseError};
+use crate::lexer::{Token, TokenStream, describe_token, keyword_spelling};
+use crate::ast::{Statement, StatementKind, Value, Expression, ExpressionKind, ImportSpecifier, CatchClause, MatchCase, PathSegment, Span};
+use crate::expression_parser::{parse_expression, parse_function_name, parse_index, parse_value_call};
+use crate::value_parser::{parse_value, parse_type, parse_pattern, ParseError};
 
-fn expect_identifier(tokens: &[Token], index: &mut usize) -> Result<String, ParseError> {
+fn expect_identifier(tokens: &TokenStream, index: &mut usize) -> Result<String, ParseError> {
     match tokens.get(*index) {
         Some(Token::Identifier(name)) => {
             *index += 1;
             Ok(name.clone())
         },
-        Some(tok) => Err(ParseError::UnexpectedToken(format!("Expected identifier, found {:?}", tok))),
-        None => Err(ParseError::UnexpectedToken("Unexpected end, expected identifier".to_string())),
+        tok => Err(ParseError::UnexpectedToken(format!("Expected a name, found {}", describe_token(tok))).with_span(&tokens.span(*index))),
     }
 }
 
-fn expect_token_type(tokens: &[Token], index: &mut usize, expected_type: &str) -> Result<(), ParseError> {
+pub(crate) fn expect_token(tokens: &TokenStream, index: &mut usize, expected: Token) -> Result<(), ParseError> {
     match tokens.get(*index) {
-        Some(token) => {
-            let matches = match (token, expected_type) {
-                (Token::LeftParen, "LeftParen") => true,
-                (Token::RightParen, "RightParen") => true,
-                (Token::LeftBracket, "LeftBracket") => true,
-                (Token::RightBracket, "RightBracket") => true,
-                (Token::LeftBrace, "LeftBrace") => true,
-                (Token::RightBrace, "RightBrace") => true,
-                (Token::Semicolon, "Semicolon") => true,
-                (Token::Colon, "Colon") => true,
-                (Token::Comma, "Comma") => true,
-                (Token::Assign, "Equals") => true,
-                _ => false,
-            };
-            
-            if matches {
-                *index += 1;
-                Ok(())
-            } else {
-                Err(ParseError::UnexpectedToken(format!("Expected {}, found {:?}", expected_type, token)))
-            }
+        Some(token) if *token == expected => {
+            *index += 1;
+            Ok(())
         },
-        None => Err(ParseError::UnexpectedToken(format!("Unexpected end, expected {}", expected_type))),
+        tok => Err(ParseError::UnexpectedToken(format!("Expected '{}', found {}", expected, describe_token(tok))).with_span(&tokens.span(*index))),
     }
 }
 
-fn expect_keyword(tokens: &[Token], index: &mut usize, keyword: &str) -> Result<(), ParseError> {
+/// Reads the `[index]` and `.field` steps that follow a name on the left of
+/// an assignment.
+fn parse_path(tokens: &TokenStream, index: &mut usize) -> Result<Vec<PathSegment>, ParseError> {
+    let mut path = Vec::new();
+    loop {
+        match tokens.get(*index) {
+            Some(Token::LeftBracket) => path.push(PathSegment::Index(parse_index(tokens, index)?)),
+            Some(Token::Dot) => {
+                *index += 1;
+                path.push(PathSegment::Field(expect_identifier(tokens, index)?));
+            },
+            _ => return Ok(path),
+        }
+    }
+}
+
+fn expect_keyword(tokens: &TokenStream, index: &mut usize, keyword: &str) -> Result<(), ParseError> {
     match tokens.get(*index) {
         Some(Token::Keyword(kw)) if kw == keyword => {
             *index += 1;
             Ok(())
         },
-        Some(tok) => Err(ParseError::UnexpectedToken(format!("Expected keyword '{}', found {:?}", keyword, tok))),
-        None => Err(ParseError::UnexpectedToken(format!("Unexpected end, expected keyword '{}'", keyword))),
+        tok => Err(ParseError::UnexpectedToken(
+            format!("Expected '{}', found {}", keyword_spelling(keyword), describe_token(tok))
+        ).with_span(&tokens.span(*index))),
     }
 }
 
-fn parse_function_args(tokens: &[Token], index: &mut usize) -> Result<Vec<Expression>, ParseError> {
+/// Parses what follows `mrglurp`: an optional `(err)` or `(err: Kind, ...)`
+/// and the handler's block.
+fn parse_catch_clause(
+    tokens: &TokenStream,
+    index: &mut usize,
+    scope_stack: &mut Vec<String>,
+    errors: &mut Vec<ParseError>,
+) -> Result<CatchClause, ParseError> {
+    let mut param = None;
+    let mut kinds = Vec::new();
+    if matches!(tokens.get(*index), Some(Token::LeftParen)) {
+        *index += 1;
+        match tokens.get(*index) {
+            Some(Token::Identifier(name)) => param = Some(name.clone()),
+            _ => return Err(ParseError::UnexpectedToken("Expected identifier as catch param".into()).with_span(&tokens.span(*index))),
+        }
+        *index += 1;
+        if matches!(tokens.get(*index), Some(Token::Colon)) {
+            *index += 1;
+            loop {
+                match tokens.get(*index) {
+                    Some(Token::Identifier(kind)) => kinds.push(kind.clone()),
+                    _ => return Err(ParseError::UnexpectedToken("Expected an error kind after ':'".into()).with_span(&tokens.span(*index))),
+                }
+                *index += 1;
+                if !matches!(tokens.get(*index), Some(Token::Comma)) {
+                    break;
+                }
+                *index += 1;
+            }
+        }
+        if matches!(tokens.get(*index), Some(Token::RightParen)) {
+            *index += 1;
+        } else {
+            return Err(ParseError::UnexpectedToken("Expected ')' after catch param".into()).with_span(&tokens.span(*index)));
+        }
+    }
+
+    expect_keyword(tokens, index, "begin")?;
+    let body = parse_block(tokens, index, scope_stack, errors)?;
+    expect_keyword(tokens, index, "end")?;
+
+    Ok(CatchClause { param, kinds, body })
+}
+
+fn parse_function_args(tokens: &TokenStream, index: &mut usize) -> Result<Vec<Expression>, ParseError> {
     let mut args = Vec::new();
     
-    expect_token_type(tokens, index, "LeftParen")?;
+    expect_token(tokens, index, Token::LeftParen)?;
     
     while *index < tokens.len() {
-        if matches!(&tokens[*index], Token::RightParen) {
-            *index += 1;
-            break;
-        }
-        
         match &tokens[*index] {
-            Token::Identifier(var_name) => {
-                args.push(Expression::Variable(var_name.clone()));
-                *index += 1;
-            },
-            Token::Number(num) => {
-                if let Ok(n) = num.parse::<i32>() {
-                    args.push(Expression::Literal(Value::Number(n)));
-                } else if let Ok(n) = num.parse::<i64>() {
-                    args.push(Expression::Literal(Value::NumberI64(n)));
-                } else if let Ok(n) = num.parse::<num_bigint::BigInt>() {
-                    args.push(Expression::Literal(Value::NumberBig(n)));
-                } else {
-                    return Err(ParseError::InvalidValue(format!("Invalid number: {}", num)));
-                }
-                *index += 1;
-            },
-            Token::StringLiteral(text) => {
-                args.push(Expression::Literal(Value::Text(text.clone())));
+            Token::RightParen => {
                 *index += 1;
+                break;
             },
             Token::Comma => {
                 *index += 1;
             },
-            Token::Minus => {
-                *index += 1;
-                if *index < tokens.len() {
-                    if let Token::Number(num) = &tokens[*index] {
-                        if let Ok(n) = num.parse::<i32>() {
-                            args.push(Expression::Literal(Value::Number(-n)));
-                        } else if let Ok(n) = num.parse::<i64>() {
-                            args.push(Expression::Literal(Value::NumberI64(-n)));
-                        } else {
-                            return Err(ParseError::InvalidValue(format!("Invalid number: -{}", num)));
-                        }
-                        *index += 1;
-                    } else {
-                        return Err(ParseError::UnexpectedToken("Expected number after minus sign".to_string()));
-                    }
-                }
-            },
-            tok => {
-                return Err(ParseError::UnexpectedToken(format!("Unexpected token in function arguments: {:?}", tok)));
-            },
+            _ => args.push(parse_expression(tokens, index)?),
         }
     }
     
     if !(*index > 0 && matches!(&tokens[*index-1], Token::RightParen)) {
-        return Err(ParseError::UnexpectedToken("Missing closing parenthesis after function arguments".to_string()));
+        return Err(ParseError::UnexpectedToken("Missing closing parenthesis after function arguments".to_string()).with_span(&tokens.span(*index)));
     }
     
     Ok(args)
 }
 
-fn parse_function_parameters(tokens: &[Token], index: &mut usize) -> Result<Vec<String>, ParseError> {
+/// Reads the call after a `grrrblbl`: a named call, or a call through an
+/// index such as `grrrblbl fs[0](21)`, which runs as an expression.
+fn parse_call(tokens: &TokenStream, index: &mut usize) -> Result<StatementKind, ParseError> {
+    let start = *index - 1;
+    let name = parse_function_name(tokens, index)?;
+    if matches!(tokens.get(*index), Some(Token::LeftBracket)) {
+        return Ok(StatementKind::Expr(parse_value_call(tokens, index, &name, start)?));
+    }
+    let args = parse_function_args(tokens, index)?;
+    Ok(StatementKind::CallFunction { name, args })
+}
+
+fn parse_function_parameters(tokens: &TokenStream, index: &mut usize) -> Result<Vec<String>, ParseError> {
     let mut params = Vec::new();
     
-    expect_token_type(tokens, index, "LeftParen")?;
+    expect_token(tokens, index, Token::LeftParen)?;
     
     while *index < tokens.len() {
         match &tokens[*index] {
@@ -137,7 +152,7 @@ fn parse_function_parameters(tokens: &[Token], index: &mut usize) -> Result<Vec<
                 *index += 1;
             }
             tok => {
-                return Err(ParseError::UnexpectedToken(format!("Unexpected token in function parameters: {:?}", tok)));
+                return Err(ParseError::UnexpectedToken(format!("Unexpected {} in function parameters", describe_token(Some(tok)))).with_span(&tokens.span(*index)));
             }
         }
     }
@@ -145,11 +160,34 @@ fn parse_function_parameters(tokens: &[Token], index: &mut usize) -> Result<Vec<
     Ok(params)
 }
 
+/// Parses an anonymous `grrrfnrrg(params) mrgl ... grl` from its parameter
+/// list on. Functions declared inside it are locals of its frame.
+pub fn parse_function_expression(tokens: &TokenStream, index: &mut usize) -> Result<(Vec<String>, Vec<Statement>), ParseError> {
+    let params = parse_function_parameters(tokens, index)?;
+    let body = parse_expression_block(tokens, index)?;
+    Ok((params, body))
+}
+
+/// Parses a `mrgl ... grl` block that appears inside an expression.
+pub fn parse_expression_block(tokens: &TokenStream, index: &mut usize) -> Result<Vec<Statement>, ParseError> {
+    expect_keyword(tokens, index, "begin")?;
+
+    let mut errors = Vec::new();
+    let body = parse_block(tokens, index, &mut vec!["<anonymous>".to_string()], &mut errors);
+    tokens.report_nested(errors);
+    let body = body?;
+
+    expect_keyword(tokens, index, "end")?;
+    Ok(body)
+}
+
 fn parse_function_or_async_function(
-    tokens: &[Token], 
+    tokens: &TokenStream, 
     index: &mut usize,
     is_async: bool,
-    scope_stack: &mut Vec<String>
+    scope_stack: &mut Vec<String>,
+    span: Span,
+    errors: &mut Vec<ParseError>,
 ) -> Result<Statement, ParseError> {
     let name = expect_identifier(tokens, index)?;
     let args = parse_function_parameters(tokens, index)?;
@@ -157,1032 +195,865 @@ fn parse_function_or_async_function(
     expect_keyword(tokens, index, "begin")?;
     
     scope_stack.push(name.clone());
-    let body = parse_block(tokens, index, Some(scope_stack))?;
+    let body = parse_block(tokens, index, scope_stack, errors);
     scope_stack.pop();
+    let body = body?;
     
     expect_keyword(tokens, index, "end")?;
     
     let parent_scope = (!scope_stack.is_empty()).then(|| scope_stack.clone());
     
     if is_async {
-        Ok(Statement::AsyncFunction { name, args, body, parent_scope })
+        Ok(Statement::new(StatementKind::AsyncFunction { name, args, body, parent_scope }, span))
     } else {
-        Ok(Statement::Function { name, args, body, parent_scope })
+        Ok(Statement::new(StatementKind::Function { name, args, body, parent_scope }, span))
     }
 }
 
-pub fn parse(tokens: Vec<Token>) -> Result<Vec<Statement>, ParseError> {
-    let mut stmts = Vec::new();
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "var", "if", "for", "while", "fn", "async", "call", "print", "return", "struct", "spawn", "wait",
+    "try", "await", "array", "import", "export", "break", "continue", "switch", "case", "default", "remove",
+    "enum", "when", "read", "throw", "finally", "threadpool",
+    "begin", "end",
+];
+
+/// Whether a statement can begin at `index`: a statement keyword or a name
+/// at the start of a line, or a `mrgl`/`grl` anywhere. Keywords in the middle
+/// of a line usually belong to an expression, like a `grrrfnrrg` in an array.
+fn starts_statement(tokens: &TokenStream, index: usize) -> bool {
+    let starts_line = index == 0 || tokens.span(index).line > tokens.span(index - 1).line;
+    match &tokens[index] {
+        Token::Keyword(kw) if kw == "begin" || kw == "end" => true,
+        Token::Keyword(kw) => starts_line && STATEMENT_KEYWORDS.contains(&kw.as_str()),
+        Token::Identifier(_) => starts_line,
+        _ => false,
+    }
+}
+
+/// The token `err` points at, when it lies in the statement that began at
+/// `start` and the parser had already stepped past it. An unclosed block is
+/// reported at its `mrgl`, which is not where parsing should resume.
+fn failing_token(tokens: &TokenStream, err: &ParseError, start: usize, stopped_at: usize) -> usize {
+    if matches!(err.innermost(), ParseError::UnclosedBlock(_)) {
+        return stopped_at;
+    }
+    err.span()
+        .and_then(|span| {
+            (start + 1..stopped_at.min(tokens.len())).find(|&index| {
+                let token = tokens.span(index);
+                token.line == span.line && token.column == span.column
+            })
+        })
+        .unwrap_or(stopped_at)
+}
+
+/// Finds where parsing can pick up again after the statement starting at
+/// `start` failed at the token `failed_at`: that token or the next one that
+/// can begin a statement, skipping past any block the broken statement had
+/// already opened.
+fn synchronize(tokens: &TokenStream, start: usize, failed_at: usize) -> usize {
+    let mut open_blocks = 0usize;
+    for index in start..failed_at.min(tokens.len()) {
+        match &tokens[index] {
+            Token::Keyword(kw) if kw == "begin" => open_blocks += 1,
+            Token::Keyword(kw) if kw == "end" => open_blocks = open_blocks.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    let mut index = failed_at.max(start + 1);
+    while index < tokens.len() {
+        if open_blocks == 0 && starts_statement(tokens, index) {
+            break;
+        }
+        match &tokens[index] {
+            Token::Keyword(kw) if kw == "begin" => open_blocks += 1,
+            Token::Keyword(kw) if kw == "end" => open_blocks = open_blocks.saturating_sub(1),
+            _ => {}
+        }
+        index += 1;
+    }
+    index
+}
+
+pub fn parse(tokens: TokenStream) -> Result<Vec<Statement>, Vec<ParseError>> {
+    let mut errors = Vec::new();
     let mut i = 0;
-    let mut scope_stack = Vec::new();
- 
-    while i < tokens.len() {
-        match &tokens[i] {
-            Token::Keyword(kw) if kw == "var" => {
-                i += 1;
-                let name = expect_identifier(&tokens, &mut i)?;
-                expect_token_type(&tokens, &mut i, "Equals")?;
-                
-                if matches!(tokens.get(i), Some(Token::Keyword(kw)) if kw == "async") {
-                    i += 1;
-                    
-                    if matches!(tokens.get(i), Some(Token::Keyword(kw)) if kw == "call") {
-                        i += 1;
-                        
-                        let func_name = expect_identifier(&tokens, &mut i)?;
-                        let args = parse_function_args(&tokens, &mut i)?;
-                        
-                        let call_stmt = Statement::CallFunction { name: func_name.clone(), args: args.clone() };
-                        let future_stmt = Statement::SpawnAsync { future: Box::new(call_stmt), thread_name: Some(name.clone()) };
-                        stmts.push(future_stmt);
-                        continue;
-                    } else {
-                        i += 1;
-                        let func_name = expect_identifier(&tokens, &mut i)?;
-                        let args = parse_function_args(&tokens, &mut i)?;
-                        
-                        stmts.push(Statement::VarDeclarationExpr(name, Expression::FunctionCall { 
-                            name: func_name.clone(), 
-                            args: args.clone() 
-                        }));
-                        continue;
-                    }
-                }
-                
-                let expr = parse_expression(&tokens, &mut i)?;
-                stmts.push(Statement::VarDeclarationExpr(name, expr));
-            }
+    let stmts = parse_statements(&tokens, &mut i, &mut Vec::new(), &mut errors, &[]);
 
-            Token::Identifier(name) => {
-                let var_name = name.clone();
-                i += 1;
-                
-                if i < tokens.len() && matches!(&tokens[i], Token::Assign) {
-                    i += 1;
-                    let expr = parse_expression(&tokens, &mut i)?;
-                    stmts.push(Statement::Assignment(var_name, expr));
-                } else {
-                    continue;
-                }
+    if errors.is_empty() {
+        Ok(stmts)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parses statements until a keyword in `terminators` (left unconsumed) or
+/// the end of the tokens. Broken statements are recorded in `errors` and
+/// skipped.
+fn parse_statements(
+    tokens: &TokenStream,
+    index: &mut usize,
+    scope_stack: &mut Vec<String>,
+    errors: &mut Vec<ParseError>,
+    terminators: &[&str],
+) -> Vec<Statement> {
+    let mut statements = Vec::new();
+
+    while *index < tokens.len() {
+        if matches!(&tokens[*index], Token::Keyword(kw) if terminators.contains(&kw.as_str())) {
+            break;
+        }
+
+        let start = *index;
+        let statement = parse_statement(tokens, index, scope_stack, errors);
+        errors.extend(tokens.take_nested_errors());
+        match statement {
+            Ok(statement) => statements.push(statement),
+            Err(err) => {
+                let failed_at = failing_token(tokens, &err, start, *index);
+                errors.push(err);
+                recover(tokens, index, start, failed_at, scope_stack, errors);
             }
+        }
+    }
 
-            Token::Keyword(kw) if kw == "if" => {
-                i += 1;
-                let condition = parse_expression(&tokens, &mut i)?;
-                
-                expect_keyword(&tokens, &mut i, "begin")?;
-                let body = parse_block(&tokens, &mut i, Some(&scope_stack))?;
-                expect_keyword(&tokens, &mut i, "end")?;
+    statements
+}
 
-                let mut else_branch = None;
+/// Skips the rest of a broken statement. Blocks that belonged to it, including
+/// `blrrgl`/`mrglurp` blocks, are still parsed so errors inside them are
+/// reported too.
+fn recover(
+    tokens: &TokenStream,
+    index: &mut usize,
+    start: usize,
+    failed_at: usize,
+    scope_stack: &mut Vec<String>,
+    errors: &mut Vec<ParseError>,
+) {
+    *index = synchronize(tokens, start, failed_at);
 
-                if let Some(Token::Keyword(kw)) = tokens.get(i) {
-                    if kw == "else" {
-                        i += 1;
-            
-                        if let Some(Token::Keyword(kw)) = tokens.get(i) {
-                            if kw == "if" {
-                                let else_if_stmt = parse_if_statement(&tokens, &mut i, &scope_stack)?;
-                                else_branch = Some(Box::new(else_if_stmt));
-                            } else {
-                                expect_keyword(&tokens, &mut i, "begin")?;
-                                let else_body = parse_block(&tokens, &mut i, Some(&scope_stack))?;
-                                expect_keyword(&tokens, &mut i, "end")?;
-            
-                                else_branch = Some(Box::new(Statement::IfStatement {
-                                    condition: Expression::Literal(Value::Number(1)),
-                                    body: else_body,
-                                    else_branch: None
-                                }));
-                            }
-                        }
-                    }
-                }
+    while matches!(tokens.get(*index), Some(Token::Keyword(kw)) if kw == "begin") {
+        *index += 1;
+        if let Err(err) = parse_block(tokens, index, scope_stack, errors) {
+            errors.push(err);
+            return;
+        }
+        *index += 1;
 
-                stmts.push(Statement::IfStatement {
-                    condition,
-                    body,
-                    else_branch
-                });
-            }
+        if *index < tokens.len() && !starts_statement(tokens, *index) {
+            *index = synchronize(tokens, *index, *index);
+        }
+    }
+}
 
-            Token::Keyword(kw) if kw == "for" => {
-                i += 1;
-                
-                if i < tokens.len() && matches!(&tokens[i], Token::Identifier(_)) {
-                    let iterator_var = expect_identifier(&tokens, &mut i)?;
-                    
-                    if i < tokens.len() && matches!(&tokens[i], Token::Keyword(kw) if kw == "in") {
-                        i += 1;
-                        let array_name = expect_identifier(&tokens, &mut i)?;
-                        
-                        expect_keyword(&tokens, &mut i, "begin")?;
-                        let body = parse_block(&tokens, &mut i, Some(&scope_stack))?;
-                        expect_keyword(&tokens, &mut i, "end")?;
-                        
-                        stmts.push(Statement::ForInLoop {
-                            iterator_var,
-                            array_name,
-                            body,
-                        });
-                    } else {
-                        expect_token_type(&tokens, &mut i, "Equals")?;
-                        let init_value = parse_expression(&tokens, &mut i)?;
-                        expect_token_type(&tokens, &mut i, "Semicolon")?;
-                        
-                        let condition = parse_expression(&tokens, &mut i)?;
-                        expect_token_type(&tokens, &mut i, "Semicolon")?;
-                        
-                        let increment_var = expect_identifier(&tokens, &mut i)?;
-                        let increment_expr = parse_expression(&tokens, &mut i)?;
-                        
-                        expect_keyword(&tokens, &mut i, "begin")?;
-                        let body = parse_block(&tokens, &mut i, Some(&scope_stack))?;
-                        expect_keyword(&tokens, &mut i, "end")?;
-                        
-                        stmts.push(Statement::ForLoop {
-                            init_var: iterator_var,
-                            init_value,
-                            condition,
-                            increment_var,
-                            increment_expr,
-                            body,
-                        });
-                    }
+/// Parses the statements of a block whose `mrgl` was just consumed, stopping
+/// at its closing `grl`.
+pub fn parse_block(
+    tokens: &TokenStream,
+    start_index: &mut usize,
+    scope_stack: &mut Vec<String>,
+    errors: &mut Vec<ParseError>,
+) -> Result<Vec<Statement>, ParseError> {
+    let mut inner_index = *start_index;
+    let statements = parse_statements(tokens, &mut inner_index, scope_stack, errors, &["end"]);
+
+    if inner_index >= tokens.len() {
+        return Err(ParseError::UnclosedBlock("this 'mrgl' is never closed by a 'grl'".to_string())
+            .with_span(&tokens.span(start_index.saturating_sub(1))));
+    }
+
+    *start_index = inner_index;
+    Ok(statements)
+}
+
+fn parse_statement(
+    tokens: &TokenStream,
+    index: &mut usize,
+    scope_stack: &mut Vec<String>,
+    errors: &mut Vec<ParseError>,
+) -> Result<Statement, ParseError> {
+    let span = tokens.span(*index);
+    let statement = match &tokens[*index] {
+        Token::Keyword(kw) if kw == "var" => {
+            *index += 1;
+            let name = expect_identifier(tokens, index)?;
+            expect_token(tokens, index, Token::Assign)?;
+
+            if matches!(tokens.get(*index), Some(Token::Keyword(kw)) if kw == "async") {
+                *index += 1;
+
+                if matches!(tokens.get(*index), Some(Token::Keyword(kw)) if kw == "call") {
+                    *index += 1;
+
+                    let call_stmt = Statement::new(parse_call(tokens, index)?, span.clone());
+                    StatementKind::SpawnAsync { future: Box::new(call_stmt), thread_name: Some(name) }
                 } else {
-                    return Err(ParseError::UnexpectedToken("Expected identifier after 'for'".to_string()));
+                    *index += 1;
+                    let func_name = parse_function_name(tokens, index)?;
+                    let args = parse_function_args(tokens, index)?;
+
+                    StatementKind::VarDeclarationExpr(name, Expression::new(ExpressionKind::FunctionCall {
+                        name: func_name,
+                        args
+                    }, span.clone()))
                 }
+            } else {
+                let expr = parse_expression(tokens, index)?;
+                StatementKind::VarDeclarationExpr(name, expr)
             }
+        }
 
-            Token::Keyword(kw) if kw == "struct" => {
-                i += 1;
-                let name = expect_identifier(&tokens, &mut i)?;
-                expect_keyword(&tokens, &mut i, "begin")?;
+        Token::Identifier(name) => {
+            let var_name = name.clone();
+            *index += 1;
 
-                let mut fields = Vec::new();
-                while let Some(token) = tokens.get(i) {
-                    if let Token::Keyword(kw) = token {
-                        if kw == "end" {
-                            break;
-                        }
-                    }
+            let path = parse_path(tokens, index)?;
 
-                    let field_name = match token {
-                        Token::Identifier(name) => name.clone(),
-                        _ => return Err(ParseError::UnexpectedToken(format!("Expected field name, found {:?}", token))),
-                    };
-                    i += 1;
-                    expect_token_type(&tokens, &mut i, "Colon")?;
-                    let field_type = parse_type(&tokens, &mut i)?;
-                    fields.push((field_name, field_type));
-
-                    if matches!(tokens.get(i), Some(Token::Comma)) {
-                        i += 1;
-                        if matches!(tokens.get(i), Some(Token::Keyword(kw)) if kw == "end") {
-                            break;
-                        }
-                    }
-                }
+            if !matches!(tokens.get(*index), Some(Token::Assign)) {
+                return Err(ParseError::UnexpectedToken(
+                    format!("Expected '=' after '{}', found {}", var_name, describe_token(tokens.get(*index)))
+                ).with_span(&tokens.span(*index)));
+            }
+            *index += 1;
+            let expr = parse_expression(tokens, index)?;
+            if path.is_empty() {
+                StatementKind::Assignment(var_name, expr)
+            } else {
+                StatementKind::PathAssignment { name: var_name, path, value: expr }
+            }
+        }
 
-                expect_keyword(&tokens, &mut i, "end")?;
-                stmts.push(Statement::StructDeclaration { name, fields });
+        Token::Keyword(kw) if kw == "remove" => {
+            *index += 1;
+            let name = expect_identifier(tokens, index)?;
+            let path = parse_path(tokens, index)?;
+            if !matches!(path.last(), Some(PathSegment::Index(_))) {
+                return Err(ParseError::UnexpectedToken(
+                    "'grrmv' needs a map key to remove, like 'grrmv scores[\"bob\"]'".to_string()
+                ).with_span(&span));
             }
+            StatementKind::Remove { name, path }
+        }
 
-            Token::Keyword(kw) if kw == "spawn" => {
-                i += 1;
-                let thread_name = if let Some(Token::Identifier(name)) = tokens.get(i) {
-                    i += 1;
-                    Some(name.clone())
-                } else {
-                    None
-                };
+        Token::Keyword(kw) if kw == "if" => {
+            return parse_if_statement(tokens, index, scope_stack, errors);
+        }
+
+        Token::Keyword(kw) if kw == "for" => {
+            *index += 1;
+            let iterator_var = match tokens.get(*index) {
+                Some(Token::Identifier(_)) => expect_identifier(tokens, index)?,
+                _ => return Err(ParseError::UnexpectedToken("Expected a name after 'mrrg'".to_string()).with_span(&tokens.span(*index))),
+            };
 
-                expect_keyword(&tokens, &mut i, "begin")?;
-                let body = parse_block(&tokens, &mut i, Some(&scope_stack))?;
-                expect_keyword(&tokens, &mut i, "end")?;
+            let value_var = if matches!(tokens.get(*index), Some(Token::Comma)) {
+                *index += 1;
+                Some(expect_identifier(tokens, index)?)
+            } else {
+                None
+            };
 
-                stmts.push(Statement::Spawn { 
-                    body,
-                    thread_name,
-                });
-            }
+            if value_var.is_some() || matches!(tokens.get(*index), Some(Token::Keyword(kw)) if kw == "in") {
+                expect_keyword(tokens, index, "in")?;
+                let iterable = parse_expression(tokens, index)?;
 
-            Token::Keyword(kw) if kw == "wait" => {
-                i += 1;
-                
-                let mut thread_names = Vec::new();
-                
-                if matches!(tokens.get(i), Some(Token::LeftBracket)) {
-                    i += 1;
-                    
-                    while i < tokens.len() {
-                        match tokens.get(i) {
-                            Some(Token::Identifier(name)) => {
-                                thread_names.push(name.clone());
-                                i += 1;
-                            }
-                            Some(Token::RightBracket) => {
-                                i += 1;
-                                break;
-                            }
-                            Some(Token::Comma) => {
-                                i += 1;
-                            }
-                            Some(tok) => {
-                                return Err(ParseError::UnexpectedToken(format!("Unexpected token in thread list: {:?}", tok)));
-                            }
-                            None => return Err(ParseError::UnexpectedToken("Missing ']' to close thread list".to_string())),
-                        }
+                if matches!(tokens.get(*index), Some(Token::Range)) {
+                    if value_var.is_some() {
+                        return Err(ParseError::UnexpectedToken(
+                            "A range hands out one number per turn; use a single loop variable".to_string()
+                        ).with_span(&span));
                     }
-                } else {
-                    let thread_name = expect_identifier(&tokens, &mut i)?;
-                    thread_names.push(thread_name);
-                }
-                
-                stmts.push(Statement::Wait { thread_names });
-            }
-            Token::Keyword(kw) if kw == "try" => {
-                i += 1;
-            
-                expect_keyword(&tokens, &mut i, "begin")?;
-                let try_block = parse_block(&tokens, &mut i, None)?;
-                expect_keyword(&tokens, &mut i, "end")?;
-            
-                expect_keyword(&tokens, &mut i, "catch")?;
-            
-                let catch_param = if matches!(tokens.get(i), Some(Token::LeftParen)) {
-                    i += 1;
-                    let param_name = match tokens.get(i) {
-                        Some(Token::Identifier(name)) => name.clone(),
-                        _ => return Err(ParseError::UnexpectedToken("Expected identifier as catch param".into())),
-                    };
-                    i += 1;
-                    if matches!(tokens.get(i), Some(Token::RightParen)) {
-                        i += 1;
+                    *index += 1;
+                    let end = parse_expression(tokens, index)?;
+                    let step = if matches!(tokens.get(*index), Some(Token::Keyword(kw)) if kw == "step") {
+                        *index += 1;
+                        Some(parse_expression(tokens, index)?)
                     } else {
-                        return Err(ParseError::UnexpectedToken("Expected ')' after catch param".into()));
+                        None
+                    };
+
+                    expect_keyword(tokens, index, "begin")?;
+                    let body = parse_block(tokens, index, scope_stack, errors)?;
+                    expect_keyword(tokens, index, "end")?;
+
+                    StatementKind::Loop {
+                        variable: iterator_var,
+                        start: iterable,
+                        end,
+                        step,
+                        body,
                     }
-                    Some(param_name)
                 } else {
-                    None
-                };
-            
-                expect_keyword(&tokens, &mut i, "begin")?;
-                let catch_body = parse_block(&tokens, &mut i, None)?;
-                expect_keyword(&tokens, &mut i, "end")?;
-            
-                stmts.push(Statement::TryBlock {
-                    try_block,
-                    catch_param,
-                    catch_body,
-                });
-            }
-            Token::Keyword(kw) if kw == "print" => {
-                i += 1;
-                let expr = parse_expression(&tokens, &mut i)?;
-                stmts.push(Statement::Print(expr));
-            }
+                    expect_keyword(tokens, index, "begin")?;
+                    let body = parse_block(tokens, index, scope_stack, errors)?;
+                    expect_keyword(tokens, index, "end")?;
 
-            Token::Keyword(kw) if kw == "await" => {
-                i += 1;
-                if i < tokens.len() && matches!(tokens.get(i), Some(Token::Keyword(kw)) if kw == "call") {
-                    i += 1;
-                    let name = expect_identifier(&tokens, &mut i)?;
-                    let args = parse_function_args(&tokens, &mut i)?;
-                    
-                    let call_stmt = Statement::CallFunction { name, args };
-                    stmts.push(Statement::Await { future: Box::new(call_stmt) });
-                } else {
-                    let future_name = expect_identifier(&tokens, &mut i)?;
-                    
-                    let var_expr = Expression::Variable(future_name);
-                    let stmt = Statement::Expr(var_expr);
-                    stmts.push(Statement::Await { future: Box::new(stmt) });
+                    StatementKind::ForInLoop {
+                        iterator_var,
+                        value_var,
+                        iterable,
+                        body,
+                    }
+                }
+            } else {
+                expect_token(tokens, index, Token::Assign)?;
+                let init_value = parse_expression(tokens, index)?;
+                expect_token(tokens, index, Token::Semicolon)?;
+
+                let condition = parse_expression(tokens, index)?;
+                expect_token(tokens, index, Token::Semicolon)?;
+
+                let increment_var = expect_identifier(tokens, index)?;
+                expect_token(tokens, index, Token::Assign)?;
+                let increment_expr = parse_expression(tokens, index)?;
+
+                expect_keyword(tokens, index, "begin")?;
+                let body = parse_block(tokens, index, scope_stack, errors)?;
+                expect_keyword(tokens, index, "end")?;
+
+                StatementKind::ForLoop {
+                    init_var: iterator_var,
+                    init_value,
+                    condition,
+                    increment_var,
+                    increment_expr,
+                    body,
                 }
             }
+        }
+
+        Token::Keyword(kw) if kw == "struct" => {
+            *index += 1;
+            let name = expect_identifier(tokens, index)?;
+            expect_keyword(tokens, index, "begin")?;
 
-            Token::Keyword(kw) if kw == "array" => {
-                i += 1;
-                let name = expect_identifier(&tokens, &mut i)?;
-                
-                expect_token_type(&tokens, &mut i, "LeftBracket")?;
-                
-                let mut elements = Vec::new();
-                while i < tokens.len() && !matches!(tokens.get(i), Some(Token::RightBracket)) {
-                    if matches!(tokens.get(i), Some(Token::Comma)) {
-                        i += 1;
-                        continue;
+            let mut fields = Vec::new();
+            while let Some(token) = tokens.get(*index) {
+                if let Token::Keyword(kw) = token
+                    && kw == "end" {
+                        break;
                     }
-                    
-                    let value = parse_value(&tokens, &mut i)?;
-                    elements.push(value);
-                    
-                    if i < tokens.len() && matches!(tokens.get(i), Some(Token::Comma)) {
-                        i += 1;
+
+                let field_name = match token {
+                    Token::Identifier(name) => name.clone(),
+                    _ => return Err(ParseError::UnexpectedToken(format!("Expected a field name, found {}", describe_token(Some(token)))).with_span(&tokens.span(*index))),
+                };
+                *index += 1;
+                expect_token(tokens, index, Token::Colon)?;
+                let field_type = parse_type(tokens, index)?;
+                fields.push((field_name, field_type));
+
+                if matches!(tokens.get(*index), Some(Token::Comma)) {
+                    *index += 1;
+                    if matches!(tokens.get(*index), Some(Token::Keyword(kw)) if kw == "end") {
+                        break;
                     }
                 }
-                
-                expect_token_type(&tokens, &mut i, "RightBracket")?;
-                
-                stmts.push(Statement::ArrayDeclaration { name, elements });
             }
 
-            Token::Keyword(kw) if kw == "fn" => {
-                i += 1;
-                let stmt = parse_function_or_async_function(&tokens, &mut i, false, &mut scope_stack)?;
-                stmts.push(stmt);
-            }
+            expect_keyword(tokens, index, "end")?;
+            StatementKind::StructDeclaration { name, fields }
+        }
 
-            Token::Keyword(kw) if kw == "async" => {
-                i += 1;
-                
-                if matches!(tokens.get(i), Some(Token::Keyword(kw)) if kw == "fn") {
-                    i += 1;
-                }
-                
-                let stmt = parse_function_or_async_function(&tokens, &mut i, true, &mut scope_stack)?;
-                stmts.push(stmt);
-            }
+        Token::Keyword(kw) if kw == "enum" => {
+            *index += 1;
+            let name = expect_identifier(tokens, index)?;
+            expect_keyword(tokens, index, "begin")?;
 
-            Token::Keyword(kw) if kw == "call" => {
-                i += 1;
-                let name = expect_identifier(&tokens, &mut i)?;
-                let args = parse_function_args(&tokens, &mut i)?;
-                
-                let next_token = tokens.get(i);
-                let is_expression = match next_token {
-                    Some(Token::Keyword(_)) | Some(Token::Identifier(_)) | None => true,
-                    _ => false
-                };
-                
-                if is_expression {
-                    stmts.push(Statement::Expr(Expression::FunctionCall { name, args }));
+            let mut variants: Vec<(String, Vec<String>)> = Vec::new();
+            while !matches!(tokens.get(*index), Some(Token::Keyword(kw)) if kw == "end") {
+                let variant_span = tokens.span(*index);
+                let variant = expect_identifier(tokens, index)?;
+                if variants.iter().any(|(existing, _)| *existing == variant) {
+                    return Err(ParseError::InvalidValue(
+                        format!("Variant '{}' appears twice in enum '{}'", variant, name)
+                    ).with_span(&variant_span));
+                }
+                let fields = if matches!(tokens.get(*index), Some(Token::LeftParen)) {
+                    parse_function_parameters(tokens, index)?
                 } else {
-                    stmts.push(Statement::CallFunction { name, args });
+                    Vec::new()
+                };
+                variants.push((variant, fields));
+
+                if matches!(tokens.get(*index), Some(Token::Comma)) {
+                    *index += 1;
                 }
             }
 
-            Token::Keyword(kw) if kw == "return" => {
-                i += 1;
-                let mut expr_index = i;
-                let expr = parse_expression(&tokens, &mut expr_index)?;
-                i = expr_index;
-                
-                stmts.push(Statement::Return(expr));
+            expect_keyword(tokens, index, "end")?;
+            StatementKind::EnumDeclaration { name, variants }
+        }
+
+        Token::Keyword(kw) if kw == "spawn" => {
+            *index += 1;
+            let thread_name = if let Some(Token::Identifier(name)) = tokens.get(*index) {
+                *index += 1;
+                Some(name.clone())
+            } else {
+                None
+            };
+
+            expect_keyword(tokens, index, "begin")?;
+            let body = parse_block(tokens, index, scope_stack, errors)?;
+            expect_keyword(tokens, index, "end")?;
+
+            StatementKind::Spawn {
+                body,
+                thread_name,
             }
+        }
 
-            Token::Keyword(kw) if kw == "import" => {
-                i += 1;
-                let mut imports = Vec::new();
-                
-                if let Some(Token::Identifier(name)) = tokens.get(i) {
-                    i += 1;
-                    expect_keyword(&tokens, &mut i, "from")?;
-                    if let Some(Token::StringLiteral(path)) = tokens.get(i) {
-                        i += 1;
-                        imports.push(ImportSpecifier::Default(name.clone()));
-                        stmts.push(Statement::Import {
-                            path: path.clone(),
-                            imports,
-                        });
-                    } else {
-                        return Err(ParseError::UnexpectedToken("Expected string literal after 'from'".to_string()));
-                    }
-                } else if let Some(Token::LeftBrace) = tokens.get(i) {
-                    i += 1;
-                    while i < tokens.len() {
-                        if let Some(Token::RightBrace) = tokens.get(i) {
-                            i += 1;
-                            break;
+        Token::Keyword(kw) if kw == "threadpool" => {
+            *index += 1;
+            let size = parse_expression(tokens, index)?;
+
+            expect_keyword(tokens, index, "begin")?;
+            let tasks = parse_block(tokens, index, scope_stack, errors)?;
+            expect_keyword(tokens, index, "end")?;
+
+            StatementKind::ThreadPool { size, tasks }
+        }
+
+        Token::Keyword(kw) if kw == "wait" => {
+            *index += 1;
+
+            let mut thread_names = Vec::new();
+
+            if matches!(tokens.get(*index), Some(Token::LeftBracket)) {
+                *index += 1;
+
+                loop {
+                    match tokens.get(*index) {
+                        Some(Token::Identifier(name)) => {
+                            thread_names.push(name.clone());
+                            *index += 1;
                         }
-                        
-                        let specifier = parse_import_specifier(&tokens, &mut i)?;
-                        imports.push(specifier);
-                        
-                        if let Some(Token::Comma) = tokens.get(i) {
-                            i += 1;
-                        } else if let Some(Token::RightBrace) = tokens.get(i) {
-                            i += 1;
+                        Some(Token::RightBracket) => {
+                            *index += 1;
                             break;
-                        } else {
-                            return Err(ParseError::UnexpectedToken("Expected ',' or '}' in import specifiers".to_string()));
                         }
+                        Some(Token::Comma) => {
+                            *index += 1;
+                        }
+                        Some(tok) => {
+                            return Err(ParseError::UnexpectedToken(format!("Unexpected {} in thread list", describe_token(Some(tok)))).with_span(&tokens.span(*index)));
+                        }
+                        None => return Err(ParseError::UnexpectedToken("Missing ']' to close thread list".to_string()).with_span(&tokens.span(*index))),
                     }
-                    
-                    expect_keyword(&tokens, &mut i, "from")?;
-                    if let Some(Token::StringLiteral(path)) = tokens.get(i) {
-                        i += 1;
-                        stmts.push(Statement::Import {
-                            path: path.clone(),
-                            imports,
-                        });
-                    } else {
-                        return Err(ParseError::UnexpectedToken("Expected string literal after 'from'".to_string()));
-                    }
-                } else {
-                    return Err(ParseError::UnexpectedToken("Invalid import statement".to_string()));
                 }
+            } else {
+                let thread_name = expect_identifier(tokens, index)?;
+                thread_names.push(thread_name);
             }
 
-            Token::Keyword(kw) if kw == "export" => {
-                i += 1;
-                let is_default = if let Some(Token::Keyword(kw)) = tokens.get(i) {
-                    if kw == "default" {
-                        i += 1;
-                        true
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                };
+            StatementKind::Wait { thread_names }
+        }
 
-                if let Some(Token::Identifier(name)) = tokens.get(i) {
-                    i += 1;
-                    stmts.push(Statement::Export {
-                        name: name.clone(),
-                        is_default,
-                    });
-                } else {
-                    return Err(ParseError::UnexpectedToken("Expected identifier after 'export'".to_string()));
-                }
-            }
+        Token::Keyword(kw) if kw == "try" => {
+            *index += 1;
 
-            Token::Keyword(kw) if kw == "while" => {
-                i += 1;
-                let condition = parse_expression(&tokens, &mut i)?;
-                
-                expect_keyword(&tokens, &mut i, "begin")?;
-                let body = parse_block(&tokens, &mut i, Some(&scope_stack))?;
-                expect_keyword(&tokens, &mut i, "end")?;
+            expect_keyword(tokens, index, "begin")?;
+            let try_block = parse_block(tokens, index, scope_stack, errors)?;
+            expect_keyword(tokens, index, "end")?;
 
-                stmts.push(Statement::WhileLoop { condition, body });
+            let mut catches = Vec::new();
+            while matches!(tokens.get(*index), Some(Token::Keyword(kw)) if kw == "catch") {
+                *index += 1;
+                catches.push(parse_catch_clause(tokens, index, scope_stack, errors)?);
             }
 
-            Token::Keyword(kw) if kw == "break" => {
-                i += 1;
-                stmts.push(Statement::Break);
-            }
+            let finally = if matches!(tokens.get(*index), Some(Token::Keyword(kw)) if kw == "finally") {
+                *index += 1;
+                expect_keyword(tokens, index, "begin")?;
+                let body = parse_block(tokens, index, scope_stack, errors)?;
+                expect_keyword(tokens, index, "end")?;
+                Some(body)
+            } else {
+                None
+            };
 
-            Token::Keyword(kw) if kw == "continue" => {
-                i += 1;
-                stmts.push(Statement::Continue);
+            if catches.is_empty() && finally.is_none() {
+                return Err(ParseError::MissingToken("'mrglgl' needs a 'mrglurp' or 'mrglast' after its block".to_string())
+                    .with_span(&tokens.span(*index)));
             }
 
-            Token::Keyword(kw) if kw == "switch" => {
-                i += 1;
-                let value = parse_expression(&tokens, &mut i)?;
-                
-                expect_keyword(&tokens, &mut i, "begin")?;
-                
-                let mut cases = Vec::new();
-                let mut default = None;
-                
-                while i < tokens.len() {
-                    if let Token::Keyword(kw) = &tokens[i] {
-                        if kw == "end" {
-                            i += 1;
-                            break;
-                        } else if kw == "case" {
-                            i += 1;
-                            let case_value = parse_value(&tokens, &mut i)?;
-                            expect_token_type(&tokens, &mut i, "Colon")?;
-                            
-                            let case_body = parse_case_block(&tokens, &mut i)?;
-                            cases.push((case_value, case_body));
-                        } else if kw == "default" {
-                            i += 1;
-                            expect_token_type(&tokens, &mut i, "Colon")?;
-                            
-                            let default_body = parse_case_block(&tokens, &mut i)?;
-                            default = Some(default_body);
-                        } else {
-                            i += 1;
-                        }
-                    } else {
-                        i += 1;
-                    }
-                }
-                
-                stmts.push(Statement::SwitchStatement {
-                    value,
-                    cases,
-                    default,
-                });
+            StatementKind::TryBlock {
+                try_block,
+                catches,
+                finally,
             }
+        }
+
+        Token::Keyword(kw) if kw == "print" => {
+            *index += 1;
+            let expr = parse_expression(tokens, index)?;
+            StatementKind::Print(expr)
+        }
+
+        Token::Keyword(kw) if kw == "await" => {
+            *index += 1;
+            if matches!(tokens.get(*index), Some(Token::Keyword(kw)) if kw == "call") {
+                *index += 1;
+                let call_stmt = Statement::new(parse_call(tokens, index)?, span.clone());
+                StatementKind::Await { future: Box::new(call_stmt) }
+            } else {
+                let future_name = expect_identifier(tokens, index)?;
 
-            _ => {
-                i += 1;
-                continue;
+                let var_expr = Expression::new(ExpressionKind::Variable(future_name), span.clone());
+                let stmt = Statement::new(StatementKind::Expr(var_expr), span.clone());
+                StatementKind::Await { future: Box::new(stmt) }
             }
         }
-    }
 
-    Ok(stmts)
-}
+        Token::Keyword(kw) if kw == "array" => {
+            *index += 1;
+            let name = expect_identifier(tokens, index)?;
 
-pub fn parse_block(tokens: &[Token], start_index: &mut usize, current_scope: Option<&Vec<String>>) -> Result<Vec<Statement>, ParseError> {
-    let mut statements = Vec::new();
-    let mut block_depth = 1;
-    let mut inner_index = *start_index;
-    let mut scope_stack = match current_scope {
-        Some(scope) => scope.clone(),
-        None => Vec::new(),
-    };
+            expect_token(tokens, index, Token::LeftBracket)?;
 
-    while inner_index < tokens.len() {
-        match &tokens[inner_index] {
-            Token::Keyword(kw) if kw == "end" => {
-                block_depth -= 1;
-                if block_depth == 0 {
-                    break;
+            let mut elements = Vec::new();
+            while *index < tokens.len() && !matches!(tokens.get(*index), Some(Token::RightBracket)) {
+                if matches!(tokens.get(*index), Some(Token::Comma)) {
+                    *index += 1;
+                    continue;
+                }
+
+                let value = parse_value(tokens, index)?;
+                elements.push(value);
+
+                if matches!(tokens.get(*index), Some(Token::Comma)) {
+                    *index += 1;
                 }
-                inner_index += 1;
             }
-            Token::Keyword(kw) if kw == "begin" => {
-                block_depth += 1;
-                inner_index += 1;
+
+            expect_token(tokens, index, Token::RightBracket)?;
+
+            StatementKind::ArrayDeclaration { name, elements }
+        }
+
+        Token::Keyword(kw) if kw == "fn" => {
+            *index += 1;
+            return parse_function_or_async_function(tokens, index, false, scope_stack, span, errors);
+        }
+
+        Token::Keyword(kw) if kw == "async" => {
+            *index += 1;
+
+            if matches!(tokens.get(*index), Some(Token::Keyword(kw)) if kw == "fn") {
+                *index += 1;
             }
-            Token::Keyword(kw) if kw == "var" => {
-                inner_index += 1;
-                let name = expect_identifier(&tokens, &mut inner_index)?;
-                expect_token_type(&tokens, &mut inner_index, "Equals")?;
-                
-                if inner_index < tokens.len() && matches!(&tokens[inner_index], Token::Keyword(kw) if kw == "async") {
-                    inner_index += 1;
-                    
-                    if inner_index < tokens.len() && matches!(&tokens[inner_index], Token::Keyword(kw) if kw == "call") {
-                        inner_index += 1;
-                        let func_name = expect_identifier(&tokens, &mut inner_index)?;
-                        let args = parse_function_args(&tokens, &mut inner_index)?;
-                        
-                        let call_stmt = Statement::CallFunction { name: func_name.clone(), args: args.clone() };
-                        let future_stmt = Statement::SpawnAsync { future: Box::new(call_stmt), thread_name: Some(name.clone()) };
-                        statements.push(future_stmt);
-                        continue;
+
+            return parse_function_or_async_function(tokens, index, true, scope_stack, span, errors);
+        }
+
+        Token::Keyword(kw) if kw == "call" => {
+            *index += 1;
+            parse_call(tokens, index)?
+        }
+
+        Token::Keyword(kw) if kw == "return" => {
+            *index += 1;
+            let expr = parse_expression(tokens, index)?;
+            StatementKind::Return(expr)
+        }
+
+        Token::Keyword(kw) if kw == "import" => {
+            *index += 1;
+            let mut imports = Vec::new();
+
+            if let Some(Token::Identifier(name)) = tokens.get(*index) {
+                *index += 1;
+                imports.push(ImportSpecifier::Default(name.clone()));
+            } else if let Some(Token::Multiply) = tokens.get(*index) {
+                imports.push(parse_import_specifier(tokens, index)?);
+            } else if let Some(Token::LeftBrace) = tokens.get(*index) {
+                *index += 1;
+                while *index < tokens.len() {
+                    if let Some(Token::RightBrace) = tokens.get(*index) {
+                        *index += 1;
+                        break;
+                    }
+
+                    let specifier = parse_import_specifier(tokens, index)?;
+                    imports.push(specifier);
+
+                    if let Some(Token::Comma) = tokens.get(*index) {
+                        *index += 1;
+                    } else if let Some(Token::RightBrace) = tokens.get(*index) {
+                        *index += 1;
+                        break;
+                    } else {
+                        return Err(ParseError::UnexpectedToken("Expected ',' or '}' in import specifiers".to_string()).with_span(&tokens.span(*index)));
                     }
                 }
-                
-                let mut expr_index = inner_index;
-                let expr = parse_expression(tokens, &mut expr_index)?;
-                inner_index = expr_index;
-                
-                statements.push(Statement::VarDeclarationExpr(name, expr));
+            } else {
+                return Err(ParseError::UnexpectedToken("Invalid import statement".to_string()).with_span(&tokens.span(*index)));
             }
-            Token::Identifier(var_name) => {
-                inner_index += 1;
-                
-                if inner_index < tokens.len() && matches!(&tokens[inner_index], Token::Assign) {
-                    inner_index += 1;
-                    
-                    let mut expr_index = inner_index;
-                    let expr = parse_expression(tokens, &mut expr_index)?;
-                    inner_index = expr_index;
-                    
-                    statements.push(Statement::Assignment(var_name.clone(), expr));
+
+            expect_keyword(tokens, index, "from")?;
+            match tokens.get(*index) {
+                Some(Token::StringLiteral(path)) => {
+                    *index += 1;
+                    StatementKind::Import {
+                        path: path.clone(),
+                        imports,
+                    }
                 }
+                _ => return Err(ParseError::UnexpectedToken("Expected a module path in quotes after 'frmgl'".to_string()).with_span(&tokens.span(*index))),
             }
-            Token::Keyword(kw) if kw == "print" => {
-                inner_index += 1;
-                
-                let mut expr_index = inner_index;
-                let expr = parse_expression(tokens, &mut expr_index)?;
-                inner_index = expr_index;
-                
-                statements.push(Statement::Print(expr));
-            }
-            Token::Keyword(kw) if kw == "await" => {
-                inner_index += 1;
-                
-                if inner_index < tokens.len() && matches!(&tokens[inner_index], Token::Keyword(kw) if kw == "call") {
-                    inner_index += 1;
-                    let name = expect_identifier(&tokens, &mut inner_index)?;
-                    let args = parse_function_args(&tokens, &mut inner_index)?;
-                    
-                    let call_stmt = Statement::CallFunction { name, args };
-                    statements.push(Statement::Await { future: Box::new(call_stmt) });
-                } else {
-                    let future_name = expect_identifier(&tokens, &mut inner_index)?;
-                    
-                    let var_expr = Expression::Variable(future_name);
-                    let stmt = Statement::Expr(var_expr);
-                    statements.push(Statement::Await { future: Box::new(stmt) });
-                }
+        }
+
+        Token::Keyword(kw) if kw == "export" => {
+            *index += 1;
+            let is_default = matches!(tokens.get(*index), Some(Token::Keyword(kw)) if kw == "default");
+            if is_default {
+                *index += 1;
             }
-            Token::Keyword(kw) if kw == "if" => {
-                inner_index += 1;
-                
-                let mut expr_index = inner_index;
-                let condition = parse_expression(tokens, &mut expr_index)?;
-                inner_index = expr_index;
-
-                expect_keyword(tokens, &mut inner_index, "begin")?;
-                let if_body = parse_block(tokens, &mut inner_index, None)?;
-                expect_keyword(tokens, &mut inner_index, "end")?;
-
-                let mut else_branch = None;
-
-                if let Some(Token::Keyword(kw)) = tokens.get(inner_index) {
-                    if kw == "else" {
-                        inner_index += 1;
-                
-                        if let Some(Token::Keyword(next_kw)) = tokens.get(inner_index) {
-                            if next_kw == "if" {
-                                let else_if_stmt = parse_if_statement(tokens, &mut inner_index, &vec![])?;
-                                else_branch = Some(Box::new(else_if_stmt));
-                            } else if next_kw == "begin" {
-                                inner_index += 1;
-                                let else_body = parse_block(tokens, &mut inner_index, None)?;
-                                expect_keyword(tokens, &mut inner_index, "end")?;
-                
-                                else_branch = Some(Box::new(Statement::IfStatement {
-                                    condition: Expression::Literal(Value::Number(1)),
-                                    body: else_body,
-                                    else_branch: None
-                                }));
-                            } else {
-                                return Err(ParseError::UnexpectedToken(
-                                    format!("Esperado 'if' ou 'begin' após 'else', encontrado {:?}", tokens.get(inner_index))
-                                ));
-                            }
+
+            match tokens.get(*index) {
+                Some(Token::Identifier(name)) => {
+                    *index += 1;
+                    if let Some(Token::Identifier(extra)) = tokens.get(*index)
+                        && tokens.span(*index).line == tokens.span(*index - 1).line {
+                            return Err(ParseError::UnexpectedToken(
+                                format!("'shrglr' exports a single name, but '{}' follows '{}'", extra, name)
+                            ).with_span(&tokens.span(*index)));
                         }
+                    StatementKind::Export {
+                        name: name.clone(),
+                        is_default,
                     }
                 }
-
-                statements.push(Statement::IfStatement {
-                    condition,
-                    body: if_body,
-                    else_branch,
-                });
+                _ => return Err(ParseError::UnexpectedToken("Expected a name after 'shrglr'".to_string()).with_span(&tokens.span(*index))),
             }
-            Token::Keyword(kw) if kw == "for" => {
-                inner_index += 1;
-                
-                let mut has_equals = false;
-                let mut lookahead = inner_index;
-                
-                while lookahead < tokens.len() && !matches!(&tokens[lookahead], Token::Semicolon) {
-                    if matches!(&tokens[lookahead], Token::Assign) {
-                        has_equals = true;
+        }
+
+        Token::Keyword(kw) if kw == "while" => {
+            *index += 1;
+            let condition = parse_expression(tokens, index)?;
+
+            expect_keyword(tokens, index, "begin")?;
+            let body = parse_block(tokens, index, scope_stack, errors)?;
+            expect_keyword(tokens, index, "end")?;
+
+            StatementKind::WhileLoop { condition, body }
+        }
+
+        Token::Keyword(kw) if kw == "break" => {
+            *index += 1;
+            StatementKind::Break
+        }
+
+        Token::Keyword(kw) if kw == "continue" => {
+            *index += 1;
+            StatementKind::Continue
+        }
+
+        Token::Keyword(kw) if kw == "switch" => {
+            *index += 1;
+            let value = parse_expression(tokens, index)?;
+
+            expect_keyword(tokens, index, "begin")?;
+
+            let mut cases = Vec::new();
+            let mut default = None;
+
+            loop {
+                match tokens.get(*index) {
+                    Some(Token::Keyword(kw)) if kw == "end" => {
+                        *index += 1;
                         break;
                     }
-                    lookahead += 1;
-                }
-                
-                if has_equals {
-                    let init_var = expect_identifier(&tokens, &mut inner_index)?;
-                    expect_token_type(&tokens, &mut inner_index, "Equals")?;
-                    
-                    let mut expr_index = inner_index;
-                    let init_value = parse_expression(&tokens, &mut expr_index)?;
-                    inner_index = expr_index;
-                    
-                    expect_token_type(&tokens, &mut inner_index, "Semicolon")?;
-                    
-                    expr_index = inner_index;
-                    let condition = parse_expression(&tokens, &mut expr_index)?;
-                    inner_index = expr_index;
-                    
-                    expect_token_type(&tokens, &mut inner_index, "Semicolon")?;
-            
-                    let increment_var = expect_identifier(&tokens, &mut inner_index)?;
-                    expect_token_type(&tokens, &mut inner_index, "Equals")?;
-                    
-                    expr_index = inner_index;
-                    let increment_expr = parse_expression(&tokens, &mut expr_index)?;
-                    inner_index = expr_index;
-                    
-                    expect_keyword(&tokens, &mut inner_index, "begin")?;
-                    let for_body = parse_block(&tokens, &mut inner_index, None)?;
-                    expect_keyword(&tokens, &mut inner_index, "end")?;
-            
-                    statements.push(Statement::ForLoop {
-                        init_var,
-                        init_value,
-                        condition,
-                        increment_var,
-                        increment_expr,
-                        body: for_body
-                    });
-                } else {
-                    let iterator_var = expect_identifier(&tokens, &mut inner_index)?;
-                    expect_keyword(&tokens, &mut inner_index, "in")?;
-                    let array_name = expect_identifier(&tokens, &mut inner_index)?;
-                    
-                    expect_keyword(&tokens, &mut inner_index, "begin")?;
-                    let body = parse_block(&tokens, &mut inner_index, Some(&scope_stack))?;
-                    expect_keyword(&tokens, &mut inner_index, "end")?;
-                    
-                    statements.push(Statement::ForInLoop {
-                        iterator_var,
-                        array_name,
-                        body,
-                    });
-                }
-            }
-            Token::Keyword(kw) if kw == "call" => {
-                    inner_index += 1;
-                let name = expect_identifier(&tokens, &mut inner_index)?;
-                let args = parse_function_args(&tokens, &mut inner_index)?;
-                statements.push(Statement::CallFunction { name, args });
-            }
-            Token::Keyword(kw) if kw == "return" => {
-                inner_index += 1;
-                let mut expr_index = inner_index;
-                let expr = parse_expression(tokens, &mut expr_index)?;
-                inner_index = expr_index;
-                
-                statements.push(Statement::Return(expr));
-            }
-            Token::Keyword(kw) if kw == "async" => {
-                inner_index += 1;
-                
-                if inner_index < tokens.len() && matches!(&tokens[inner_index], Token::Keyword(kw) if kw == "fn") {
-                    inner_index += 1;
+                    Some(Token::Keyword(kw)) if kw == "case" => {
+                        *index += 1;
+                        let mut patterns = vec![parse_pattern(tokens, index)?];
+                        while matches!(tokens.get(*index), Some(Token::Comma)) {
+                            *index += 1;
+                            patterns.push(parse_pattern(tokens, index)?);
+                        }
+                        let guard = if matches!(tokens.get(*index), Some(Token::Keyword(kw)) if kw == "if") {
+                            *index += 1;
+                            Some(parse_expression(tokens, index)?)
+                        } else {
+                            None
+                        };
+                        expect_token(tokens, index, Token::Colon)?;
+
+                        let body = parse_statements(tokens, index, scope_stack, errors, &["case", "default", "end"]);
+                        cases.push(MatchCase { patterns, guard, body });
+                    }
+                    Some(Token::Keyword(kw)) if kw == "default" => {
+                        *index += 1;
+                        expect_token(tokens, index, Token::Colon)?;
+
+                        let default_body = parse_statements(tokens, index, scope_stack, errors, &["case", "default", "end"]);
+                        default = Some(default_body);
+                    }
+                    token => {
+                        return Err(ParseError::UnexpectedToken(
+                            format!("Expected 'grlblgl', 'blrrghlt' or 'grl' in 'murrrgh', found {}", describe_token(token))
+                        ).with_span(&tokens.span(*index)));
+                    }
                 }
-                
-                let stmt = parse_function_or_async_function(&tokens, &mut inner_index, true, &mut scope_stack)?;
-                statements.push(stmt);
             }
-            Token::Keyword(kw) if kw == "spawn" => {
-                inner_index += 1;
-                
-                let thread_name = if let Some(Token::Identifier(name)) = tokens.get(inner_index) {
-                    inner_index += 1;
-                    Some(name.clone())
-                } else {
-                    None
-                };
-                
-                expect_keyword(&tokens, &mut inner_index, "begin")?;
-                let spawn_body = parse_block(tokens, &mut inner_index, Some(&scope_stack))?;
-                expect_keyword(&tokens, &mut inner_index, "end")?;
-                
-                statements.push(Statement::Spawn { 
-                    body: spawn_body,
-                    thread_name,
-                });
+
+            StatementKind::SwitchStatement {
+                value,
+                cases,
+                default,
             }
-            Token::Keyword(kw) if kw == "wait" => {
-                inner_index += 1;
-                
-                let mut thread_names = Vec::new();
-                
-                if inner_index < tokens.len() && matches!(&tokens[inner_index], Token::LeftBracket) {
-                    inner_index += 1;
-                    
-                    while inner_index < tokens.len() {
-                        match &tokens[inner_index] {
-                            Token::Identifier(name) => {
-                                thread_names.push(name.clone());
-                                inner_index += 1;
-                            }
-                            Token::RightBracket => {
-                                inner_index += 1;
-                                break;
-                            }
-                            Token::Comma => {
-                                inner_index += 1;
-                            }
-                            tok => {
-                                return Err(ParseError::UnexpectedToken(format!("Unexpected token in thread list: {:?}", tok)));
-                            }
-                        }
+        }
+
+        Token::Keyword(kw) if kw == "throw" => {
+            *index += 1;
+            StatementKind::Throw(parse_expression(tokens, index)?)
+        }
+
+        Token::Keyword(kw) if kw == "read" => {
+            *index += 1;
+            StatementKind::Read(expect_identifier(tokens, index)?)
+        }
+
+        Token::Keyword(kw) if kw == "when" => {
+            *index += 1;
+            expect_keyword(tokens, index, "begin")?;
+
+            let mut branches = Vec::new();
+            let mut default = None;
+            loop {
+                match tokens.get(*index) {
+                    Some(Token::Keyword(kw)) if kw == "end" => {
+                        *index += 1;
+                        break;
                     }
-                } else if inner_index < tokens.len() {
-                    match &tokens[inner_index] {
-                        Token::Identifier(name) => {
-                            thread_names.push(name.clone());
-                            inner_index += 1;
-                        }
-                        tok => {
-                            return Err(ParseError::UnexpectedToken(format!("Expected identifier after 'wait', found {:?}", tok)));
-                        }
+                    Some(Token::Keyword(kw)) if kw == "case" => {
+                        *index += 1;
+                        let condition = parse_expression(tokens, index)?;
+                        expect_token(tokens, index, Token::Colon)?;
+                        let body = parse_statements(tokens, index, scope_stack, errors, &["case", "default", "end"]);
+                        branches.push((condition, body));
                     }
-                } else {
-                    return Err(ParseError::UnexpectedToken("Missing identifier after 'wait'".to_string()));
-                }
-                
-                statements.push(Statement::Wait { thread_names });
-            },
-            Token::Keyword(kw) if kw == "while" => {
-                inner_index += 1;
-                
-                let mut expr_index = inner_index;
-                let condition = parse_expression(tokens, &mut expr_index)?;
-                inner_index = expr_index;
-                
-                expect_keyword(&tokens, &mut inner_index, "begin")?;
-                let while_body = parse_block(tokens, &mut inner_index, None)?;
-                expect_keyword(&tokens, &mut inner_index, "end")?;
-                
-                statements.push(Statement::WhileLoop { condition, body: while_body });
-            },
-            Token::Keyword(kw) if kw == "fn" => {
-                inner_index += 1;
-                let stmt = parse_function_or_async_function(&tokens, &mut inner_index, false, &mut scope_stack)?;
-                statements.push(stmt);
-            },
-            Token::Keyword(kw) if kw == "break" => {
-                inner_index += 1;
-                statements.push(Statement::Break);
-            },
-            Token::Keyword(kw) if kw == "continue" => {
-                inner_index += 1;
-                statements.push(Statement::Continue);
-            },
-            Token::Keyword(kw) if kw == "switch" => {
-                inner_index += 1;
-                
-                let mut expr_index = inner_index;
-                let value = parse_expression(tokens, &mut expr_index)?;
-                inner_index = expr_index;
-                
-                expect_keyword(&tokens, &mut inner_index, "begin")?;
-                
-                let mut cases = Vec::new();
-                let mut default = None;
-                
-                while inner_index < tokens.len() {
-                    if let Token::Keyword(kw) = &tokens[inner_index] {
-                        if kw == "end" {
-                            inner_index += 1;
-                            break;
-                        } else if kw == "case" {
-                            inner_index += 1;
-                            let case_value = parse_value(&tokens, &mut inner_index)?;
-                            expect_token_type(&tokens, &mut inner_index, "Colon")?;
-                            
-                            let case_body = parse_case_block(&tokens, &mut inner_index)?;
-                            cases.push((case_value, case_body));
-                        } else if kw == "default" {
-                            inner_index += 1;
-                            expect_token_type(&tokens, &mut inner_index, "Colon")?;
-                            
-                            let default_body = parse_case_block(&tokens, &mut inner_index)?;
-                            default = Some(default_body);
-                        } else {
-                            inner_index += 1;
-                        }
-                    } else {
-                        inner_index += 1;
+                    Some(Token::Keyword(kw)) if kw == "default" => {
+                        *index += 1;
+                        expect_token(tokens, index, Token::Colon)?;
+                        default = Some(parse_statements(tokens, index, scope_stack, errors, &["case", "default", "end"]));
+                    }
+                    token => {
+                        return Err(ParseError::UnexpectedToken(
+                            format!("Expected 'grlblgl', 'blrrghlt' or 'grl' in 'wrrgl', found {}", describe_token(token))
+                        ).with_span(&tokens.span(*index)));
                     }
                 }
-                
-                statements.push(Statement::SwitchStatement {
-                    value,
-                    cases,
-                    default,
-                });
-            },
-            _ => inner_index += 1,
+            }
+
+            let mut branches = branches.into_iter();
+            let Some((condition, body)) = branches.next() else {
+                return Err(ParseError::UnexpectedToken("'wrrgl' needs at least one 'grlblgl' branch".to_string()).with_span(&span));
+            };
+            StatementKind::WhenStatement {
+                condition,
+                body,
+                alternatives: branches.collect(),
+                default,
+            }
         }
-    }
-    
-    *start_index = inner_index;
-    Ok(statements)
+
+        Token::Keyword(kw) if kw == "begin" => {
+            return Err(ParseError::UnexpectedToken("This 'mrgl' does not open the block of any statement".to_string()).with_span(&span));
+        }
+
+        Token::Keyword(kw) if kw == "end" => {
+            return Err(ParseError::UnexpectedToken("This 'grl' has no open 'mrgl' to close".to_string()).with_span(&span));
+        }
+
+        token => {
+            return Err(ParseError::UnexpectedToken(format!("Expected a statement, found {}", describe_token(Some(token)))).with_span(&span));
+        }
+    };
+
+    Ok(Statement::new(statement, span))
 }
 
-fn parse_import_specifier(tokens: &[Token], index: &mut usize) -> Result<ImportSpecifier, ParseError> {
+fn parse_import_specifier(tokens: &TokenStream, index: &mut usize) -> Result<ImportSpecifier, ParseError> {
     match tokens.get(*index) {
         Some(Token::Identifier(name)) => {
             *index += 1;
             
-            if let Some(Token::Keyword(kw)) = tokens.get(*index) {
-                if kw == "as" {
+            if let Some(Token::Keyword(kw)) = tokens.get(*index)
+                && kw == "as" {
                     *index += 1;
                     if let Some(Token::Identifier(alias)) = tokens.get(*index) {
                         *index += 1;
                         return Ok(ImportSpecifier::Named(name.clone(), alias.clone()));
                     }
                 }
-            }
             
             Ok(ImportSpecifier::Specific(name.clone()))
         },
-        Some(Token::Keyword(kw)) if kw == "*" => {
+        Some(Token::Multiply) => {
             *index += 1;
             expect_keyword(tokens, index, "as")?;
             if let Some(Token::Identifier(name)) = tokens.get(*index) {
                 *index += 1;
                 Ok(ImportSpecifier::Namespace(name.clone()))
             } else {
-                Err(ParseError::UnexpectedToken("Expected identifier after 'as'".to_string()))
+                Err(ParseError::UnexpectedToken("Expected a name after 'azgl'".to_string()).with_span(&tokens.span(*index)))
             }
         },
-        _ => Err(ParseError::UnexpectedToken("Invalid import specifier".to_string()))
+        _ => Err(ParseError::UnexpectedToken("Invalid import specifier".to_string()).with_span(&tokens.span(*index)))
     }
 }
 
 fn parse_if_statement(
-    tokens: &[Token],
+    tokens: &TokenStream,
     i: &mut usize,
-    scope_stack: &Vec<String>,
+    scope_stack: &mut Vec<String>,
+    errors: &mut Vec<ParseError>,
 ) -> Result<Statement, ParseError> {
+    let span = tokens.span(*i);
     *i += 1;
 
     let condition = parse_expression(tokens, i)?;
     expect_keyword(tokens, i, "begin")?;
-    let body = parse_block(tokens, i, Some(scope_stack))?;
+    let body = parse_block(tokens, i, scope_stack, errors)?;
     expect_keyword(tokens, i, "end")?;
 
     let mut else_branch = None;
 
-    if let Some(Token::Keyword(kw)) = tokens.get(*i) {
-        if kw == "else" {
+    if let Some(Token::Keyword(kw)) = tokens.get(*i)
+        && kw == "else" {
             *i += 1;
     
             match tokens.get(*i) {
                 Some(Token::Keyword(next_kw)) if next_kw == "if" => {
-                    let else_if_stmt = parse_if_statement(tokens, i, scope_stack)?;
+                    let else_if_stmt = parse_if_statement(tokens, i, scope_stack, errors)?;
                     else_branch = Some(Box::new(else_if_stmt));
                 }
                 Some(Token::Keyword(next_kw)) if next_kw == "begin" => {
                     *i += 1;
-                    let else_body = parse_block(tokens, i, Some(scope_stack))?;
+                    let else_body = parse_block(tokens, i, scope_stack, errors)?;
                     expect_keyword(tokens, i, "end")?;
-                                else_branch = Some(Box::new(Statement::IfStatement {
-                                    condition: Expression::Literal(Value::Number(1)),
-                                    body: else_body,
-                                    else_branch: None
-                                }));
+                    else_branch = Some(Box::new(Statement::new(StatementKind::IfStatement {
+                        condition: Expression::new(ExpressionKind::Literal(Value::Number(1)), span.clone()),
+                        body: else_body,
+                        else_branch: None
+                    }, span.clone())));
                 }
                 _ => {
                     return Err(ParseError::UnexpectedToken(
-                        format!("Esperado 'if' ou 'begin' após 'else', encontrado {:?}", tokens.get(*i))
-                    ));
+                        format!("Expected 'grlbrr' or 'mrgl' after 'blrrgl', found {}", describe_token(tokens.get(*i)))
+                    ).with_span(&tokens.span(*i)));
                 }
             }
         }
-    }
-    
 
-    Ok(Statement::IfStatement {
+    Ok(Statement::new(StatementKind::IfStatement {
         condition,
         body,
         else_branch,
-    })
+    }, span))
 }
 
-fn parse_case_block(tokens: &[Token], index: &mut usize) -> Result<Vec<Statement>, ParseError> {
-    let mut statements = Vec::new();
-    
-    while *index < tokens.len() {
-        if let Token::Keyword(kw) = &tokens[*index] {
-            if kw == "case" || kw == "default" || kw == "end" {
-                break;
-            }
-        }
-        
-        match &tokens[*index] {
-            Token::Keyword(kw) if kw == "var" => {
-                *index += 1;
-                let name = expect_identifier(&tokens, index)?;
-                expect_token_type(&tokens, index, "Equals")?;
-                let expr = parse_expression(&tokens, index)?;
-                statements.push(Statement::VarDeclarationExpr(name, expr));
-            },
-            Token::Identifier(name) => {
-                let var_name = name.clone();
-                *index += 1;
-                
-                if *index < tokens.len() && matches!(&tokens[*index], Token::Assign) {
-                    *index += 1;
-                    let expr = parse_expression(&tokens, index)?;
-                    statements.push(Statement::Assignment(var_name, expr));
-                }
-            },
-            Token::Keyword(kw) if kw == "break" => {
-                *index += 1;
-                statements.push(Statement::Break);
-            },
-            Token::Keyword(kw) if kw == "continue" => {
-                *index += 1;
-                statements.push(Statement::Continue);
-            },
-            Token::Keyword(kw) if kw == "return" => {
-                *index += 1;
-                let expr = parse_expression(&tokens, index)?;
-                statements.push(Statement::Return(expr));
-            },
-            Token::Keyword(kw) if kw == "if" => {
-                let statement = parse_if_statement(&tokens, index, &vec![])?;
-                statements.push(statement);
-            },
-            Token::Keyword(kw) if kw == "call" => {
-                *index += 1;
-                let name = expect_identifier(&tokens, index)?;
-                let args = parse_function_args(&tokens, index)?;
-                statements.push(Statement::CallFunction { name, args });
-            },
-            Token::Keyword(kw) if kw == "print" => {
-                *index += 1;
-                let expr = parse_expression(&tokens, index)?;
-                statements.push(Statement::Print(expr));
-            },
-            _ => *index += 1,
-        }
-    }
-    
-    Ok(statements)
-}
@@ -0,0 +1,27 @@
+//! Modules bundled into the binary, importable from anywhere as `std/<name>`.
+
+const PREFIX: &str = "<std>/";
+
+const MODULES: &[(&str, &str)] = &[
+    ("collections", include_str!("../std/collections.mur")),
+    ("math", include_str!("../std/math.mur")),
+    ("strings", include_str!("../std/strings.mur")),
+];
+
+/// Maps an import path such as `std/math` to the file name the bundled module
+/// is known by, or `None` when no such module ships with the binary.
+pub fn find(import_path: &str) -> Option<String> {
+    let name = import_path.strip_prefix("std/")?;
+    let name = name.strip_suffix(".mur").unwrap_or(name);
+    lookup(name).map(|_| format!("{}{}.mur", PREFIX, name))
+}
+
+/// Source of a bundled module given the file name returned by [`find`].
+pub fn source(file: &str) -> Option<&'static str> {
+    let name = file.strip_prefix(PREFIX)?.strip_suffix(".mur")?;
+    lookup(name)
+}
+
+fn lookup(name: &str) -> Option<&'static str> {
+    MODULES.iter().find(|(module, _)| *module == name).map(|(_, source)| *source)
+}
@@ -1,15 +1,84 @@
-use crate::ast::{Value, Type};
-use crate::lexer::Token;
-use num_bigint::BigInt;
+use crate::ast::{Value, Type, Pattern, Span};
+use crate::interpreter::{CallFrame, RuntimeError, describe_trace};
+use crate::lexer::{Token, TokenStream, describe_token, keyword_spelling};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ParseError {
     InvalidValue(String),
     InvalidType(String),
     InvalidArrayType(String),
     UnexpectedToken(String),
     MissingToken(String),
-    RuntimeError(crate::interpreter::RuntimeError),
+    UnclosedBlock(String),
+    RuntimeError(RuntimeError),
+    Spanned(Box<ParseError>, Span),
+    Traced(Box<ParseError>, Vec<CallFrame>),
+}
+
+impl ParseError {
+    /// Pins the error to `span` unless it already knows where it happened.
+    /// Loop and return signals are not errors and never get a location.
+    pub fn with_span(self, span: &Span) -> Self {
+        match self {
+            ParseError::Spanned(..)
+            | ParseError::RuntimeError(RuntimeError::Break)
+            | ParseError::RuntimeError(RuntimeError::Continue)
+            | ParseError::RuntimeError(RuntimeError::Return(_)) => self,
+            ParseError::Traced(err, trace) => ParseError::Traced(Box::new(err.with_span(span)), trace),
+            _ => ParseError::Spanned(Box::new(self), span.clone()),
+        }
+    }
+
+    /// Records the calls that were running when the error was raised, unless
+    /// it already carries a trace or is only a loop or return signal.
+    pub fn with_trace(self, trace: impl FnOnce() -> Vec<CallFrame>) -> Self {
+        match self {
+            ParseError::Traced(..)
+            | ParseError::RuntimeError(RuntimeError::Break)
+            | ParseError::RuntimeError(RuntimeError::Continue)
+            | ParseError::RuntimeError(RuntimeError::Return(_)) => self,
+            _ => {
+                let trace = trace();
+                if trace.is_empty() {
+                    self
+                } else {
+                    ParseError::Traced(Box::new(self), trace)
+                }
+            },
+        }
+    }
+
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            ParseError::Spanned(_, span) => Some(span),
+            ParseError::Traced(err, _) => err.span(),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a loop or return signal rather than an error.
+    pub fn is_signal(&self) -> bool {
+        matches!(
+            self,
+            ParseError::RuntimeError(RuntimeError::Break | RuntimeError::Continue | RuntimeError::Return(_))
+        )
+    }
+
+    /// The error itself, without the location and trace wrapped around it.
+    pub fn innermost(&self) -> &ParseError {
+        match self {
+            ParseError::Spanned(err, _) | ParseError::Traced(err, _) => err.innermost(),
+            _ => self,
+        }
+    }
+
+    /// The calls that were running when the error was raised, innermost first.
+    pub fn trace(&self) -> &[CallFrame] {
+        match self {
+            ParseError::Traced(_, trace) => trace,
+            _ => &[],
+        }
+    }
 }
 
 impl std::fmt::Display for ParseError {
@@ -20,81 +89,246 @@ impl std::fmt::Display for ParseError {
             ParseError::InvalidArrayType(msg) => write!(f, "Array type violation in the matrix: {}", msg),
             ParseError::UnexpectedToken(msg) => write!(f, "Unexpected token in the codex: {}", msg),
             ParseError::MissingToken(msg) => write!(f, "Missing token in the ritual: {}", msg),
-            ParseError::RuntimeError(err) => write!(f, "Runtime anomaly detected: {:?}", err),
+            ParseError::UnclosedBlock(msg) => write!(f, "Ritual left unsealed: {}", msg),
+            ParseError::RuntimeError(err) => write!(f, "{}", err),
+            ParseError::Spanned(err, span) => write!(f, "{}: {}", span, err),
+            ParseError::Traced(err, trace) => {
+                write!(f, "{}", err)?;
+                for line in describe_trace(trace) {
+                    write!(f, "\n    {}", line)?;
+                }
+                Ok(())
+            },
         }
     }
 }
 
 impl std::error::Error for ParseError {}
 
-pub fn parse_value(tokens: &[Token], i: &mut usize) -> Result<Value, ParseError> {
+pub fn parse_value(tokens: &TokenStream, i: &mut usize) -> Result<Value, ParseError> {
     if *i >= tokens.len() {
-        return Err(ParseError::UnexpectedToken("Unexpected end of token stream".to_string()));
+        return Err(ParseError::UnexpectedToken("Expected a value, found end of file".to_string()).with_span(&tokens.span(*i)));
     }
 
     match &tokens[*i] {
         Token::Number(n) => {
+            let span = tokens.span(*i);
             *i += 1;
-            if let Ok(n) = n.to_string().parse::<i32>() {
-                Ok(Value::Number(n))
-            } else if let Ok(n) = n.to_string().parse::<i64>() {
-                Ok(Value::NumberI64(n))
-            } else if let Ok(n) = n.to_string().parse::<BigInt>() {
-                Ok(Value::NumberBig(n))
-            } else {
-                Err(ParseError::InvalidValue(format!("Invalid number format: {}", n)))
-            }
+            Value::parse_number(n)
+                .ok_or_else(|| ParseError::InvalidValue(format!("Invalid number format: {}", n)).with_span(&span))
         },
         Token::StringLiteral(s) => {
             *i += 1;
             Ok(Value::Text(s.clone()))
         },
+        Token::Keyword(kw) if kw == "true" || kw == "false" => {
+            *i += 1;
+            Ok(Value::Bool(kw == "true"))
+        },
         Token::LeftBracket => {
             *i += 1;
             let mut elements = Vec::new();
             while *i < tokens.len() && !matches!(&tokens[*i], Token::RightBracket) {
                 elements.push(parse_value(tokens, i)?);
-                if let Token::Comma = &tokens[*i] {
-                    *i += 1;
-                } else if !matches!(&tokens[*i], Token::RightBracket) {
-                    return Err(ParseError::UnexpectedToken(format!("Expected ',' or ']', found {:?}", tokens[*i])));
+                match tokens.get(*i) {
+                    Some(Token::Comma) => *i += 1,
+                    Some(Token::RightBracket) | None => {},
+                    Some(tok) => return Err(ParseError::UnexpectedToken(format!("Expected ',' or ']', found {}", describe_token(Some(tok)))).with_span(&tokens.span(*i))),
                 }
             }
             if *i >= tokens.len() {
-                return Err(ParseError::MissingToken("Missing ']' to close the array".to_string()));
+                return Err(ParseError::MissingToken("Missing ']' to close the array".to_string()).with_span(&tokens.span(*i)));
             }
             *i += 1;
             Ok(Value::Array(elements))
         },
         Token::Identifier(_) => {
-            Err(ParseError::InvalidValue("Identifier must be processed by expression_parser".to_string()))
+            Err(ParseError::InvalidValue("Identifier must be processed by expression_parser".to_string()).with_span(&tokens.span(*i)))
+        },
+        tok => Err(ParseError::InvalidValue(format!("Expected a value, found {}", describe_token(Some(tok)))).with_span(&tokens.span(*i))),
+    }
+}
+
+/// Reads one `grlblgl` pattern: `_`, a name to bind, a literal or literal
+/// range, `Enum.Variant(...)`, `Struct { field: pattern, ... }` or an array
+/// shape ending in an optional `..rest`.
+pub fn parse_pattern(tokens: &TokenStream, i: &mut usize) -> Result<Pattern, ParseError> {
+    let span = tokens.span(*i);
+    match tokens.get(*i) {
+        Some(Token::Identifier(name)) if name == "_" => {
+            *i += 1;
+            Ok(Pattern::Wildcard)
+        },
+        Some(Token::Identifier(name)) => {
+            *i += 1;
+            match tokens.get(*i) {
+                Some(Token::Dot) => {
+                    *i += 1;
+                    let variant = match tokens.get(*i) {
+                        Some(Token::Identifier(variant)) => variant.clone(),
+                        tok => return Err(ParseError::UnexpectedToken(format!("Expected a variant name after '{}.', found {}", name, describe_token(tok))).with_span(&tokens.span(*i))),
+                    };
+                    *i += 1;
+                    let fields = if matches!(tokens.get(*i), Some(Token::LeftParen)) {
+                        *i += 1;
+                        Some(parse_pattern_list(tokens, i, Token::RightParen)?.0)
+                    } else {
+                        None
+                    };
+                    Ok(Pattern::Variant { enum_name: name.clone(), variant, fields })
+                },
+                Some(Token::LeftBrace) => {
+                    *i += 1;
+                    let mut fields = Vec::new();
+                    while !matches!(tokens.get(*i), Some(Token::RightBrace)) {
+                        let field = match tokens.get(*i) {
+                            Some(Token::Identifier(field)) => field.clone(),
+                            tok => return Err(ParseError::UnexpectedToken(format!("Expected a field name in struct pattern, found {}", describe_token(tok))).with_span(&tokens.span(*i))),
+                        };
+                        *i += 1;
+                        let pattern = if matches!(tokens.get(*i), Some(Token::Colon)) {
+                            *i += 1;
+                            parse_pattern(tokens, i)?
+                        } else {
+                            Pattern::Binding(field.clone())
+                        };
+                        fields.push((field, pattern));
+                        match tokens.get(*i) {
+                            Some(Token::Comma) => *i += 1,
+                            Some(Token::RightBrace) => {},
+                            tok => return Err(ParseError::UnexpectedToken(format!("Expected ',' or '}}', found {}", describe_token(tok))).with_span(&tokens.span(*i))),
+                        }
+                    }
+                    *i += 1;
+                    Ok(Pattern::Struct { name: name.clone(), fields })
+                },
+                _ => Ok(Pattern::Binding(name.clone())),
+            }
+        },
+        Some(Token::LeftBracket) => {
+            *i += 1;
+            let (elements, rest) = parse_pattern_list(tokens, i, Token::RightBracket)?;
+            Ok(Pattern::Array { elements, rest: rest.map(Box::new) })
+        },
+        Some(_) => {
+            let low = parse_literal_pattern(tokens, i)?;
+            if matches!(tokens.get(*i), Some(Token::Range)) {
+                *i += 1;
+                let high = parse_literal_pattern(tokens, i)?;
+                Ok(Pattern::Range(low, high))
+            } else {
+                Ok(Pattern::Literal(low))
+            }
         },
-        _ => Err(ParseError::InvalidValue(format!("Unexpected token in value context: {:?}", tokens[*i]))),
+        None => Err(ParseError::UnexpectedToken("Expected a pattern, found end of file".to_string()).with_span(&span)),
     }
 }
 
-pub fn parse_type(tokens: &[Token], i: &mut usize) -> Result<Type, ParseError> {
+/// Reads comma-separated patterns up to `close`, allowing a final `..` or
+/// `..name` that collects the remaining elements.
+fn parse_pattern_list(tokens: &TokenStream, i: &mut usize, close: Token) -> Result<(Vec<Pattern>, Option<Pattern>), ParseError> {
+    let mut patterns = Vec::new();
+    let mut rest = None;
+    loop {
+        match tokens.get(*i) {
+            Some(token) if *token == close => {
+                *i += 1;
+                return Ok((patterns, rest));
+            },
+            Some(Token::Range) if rest.is_none() && close == Token::RightBracket => {
+                *i += 1;
+                rest = Some(match tokens.get(*i) {
+                    Some(Token::Identifier(name)) if name != "_" => {
+                        *i += 1;
+                        Pattern::Binding(name.clone())
+                    },
+                    Some(Token::Identifier(_)) => {
+                        *i += 1;
+                        Pattern::Wildcard
+                    },
+                    _ => Pattern::Wildcard,
+                });
+            },
+            Some(_) if rest.is_none() => patterns.push(parse_pattern(tokens, i)?),
+            tok => return Err(ParseError::UnexpectedToken(format!("Expected '{}' to close the pattern, found {}", close, describe_token(tok))).with_span(&tokens.span(*i))),
+        }
+        match tokens.get(*i) {
+            Some(Token::Comma) => *i += 1,
+            Some(token) if *token == close => {},
+            tok => return Err(ParseError::UnexpectedToken(format!("Expected ',' or '{}', found {}", close, describe_token(tok))).with_span(&tokens.span(*i))),
+        }
+    }
+}
+
+fn parse_literal_pattern(tokens: &TokenStream, i: &mut usize) -> Result<Value, ParseError> {
+    if matches!(tokens.get(*i), Some(Token::Minus)) {
+        *i += 1;
+        let span = tokens.span(*i);
+        return match tokens.get(*i) {
+            Some(Token::Number(n)) => {
+                *i += 1;
+                Value::parse_number(&format!("-{}", n))
+                    .ok_or_else(|| ParseError::InvalidValue(format!("Invalid number format: -{}", n)).with_span(&span))
+            },
+            tok => Err(ParseError::UnexpectedToken(format!("Expected a number after '-', found {}", describe_token(tok))).with_span(&span)),
+        };
+    }
+    match tokens.get(*i) {
+        Some(Token::Number(_) | Token::StringLiteral(_)) => parse_value(tokens, i),
+        Some(Token::Keyword(kw)) if kw == "true" || kw == "false" => parse_value(tokens, i),
+        tok => Err(ParseError::UnexpectedToken(format!("Expected a pattern, found {}", describe_token(tok))).with_span(&tokens.span(*i))),
+    }
+}
+
+pub fn parse_type(tokens: &TokenStream, i: &mut usize) -> Result<Type, ParseError> {
     if *i >= tokens.len() {
-        return Err(ParseError::UnexpectedToken("Unexpected end of token stream".to_string()));
+        return Err(ParseError::UnexpectedToken("Expected a type, found end of file".to_string()).with_span(&tokens.span(*i)));
     }
 
     match &tokens[*i] {
         Token::Keyword(kw) => {
+            let field_type = match kw.to_lowercase().as_str() {
+                "number" => Type::Number,
+                "text" => Type::Text,
+                "fn" => Type::Function,
+                "bool" => Type::Bool,
+                "map" => Type::Map,
+                "array" => {
+                    *i += 1;
+                    if !matches!(tokens.get(*i), Some(Token::LessThan)) {
+                        return Err(ParseError::InvalidArrayType(
+                            "'grrip' needs its element type, like 'grrip<numblrr>'".to_string()
+                        ).with_span(&tokens.span(*i)));
+                    }
+                    *i += 1;
+                    let element = parse_type(tokens, i)?;
+                    if !matches!(tokens.get(*i), Some(Token::GreaterThan)) {
+                        return Err(ParseError::InvalidArrayType(
+                            format!("Expected '>' to close 'grrip<{}', found {}", element, describe_token(tokens.get(*i)))
+                        ).with_span(&tokens.span(*i)));
+                    }
+                    *i += 1;
+                    return Ok(Type::Array(Box::new(element)));
+                },
+                _ => return Err(ParseError::InvalidType(format!("'{}' is not a type", keyword_spelling(kw))).with_span(&tokens.span(*i))),
+            };
             *i += 1;
-            match kw.to_lowercase().as_str() {
-                "number" => Ok(Type::Number),
-                "text" => Ok(Type::Text),
-                _ => Err(ParseError::InvalidType(format!("Invalid type keyword: {}", kw))),
-            }
+            Ok(field_type)
         },
         Token::Identifier(name) => {
             *i += 1;
             match name.to_lowercase().as_str() {
                 "number" => Ok(Type::Number),
                 "text" => Ok(Type::Text),
-                _ => Ok(Type::Struct(name.clone())),
+                _ => match (tokens.get(*i), tokens.get(*i + 1)) {
+                    (Some(Token::Dot), Some(Token::Identifier(member))) => {
+                        *i += 2;
+                        Ok(Type::Struct(format!("{}.{}", name, member)))
+                    },
+                    _ => Ok(Type::Struct(name.clone())),
+                },
             }
         },
-        _ => Err(ParseError::InvalidType(format!("Unexpected token in type context: {:?}", tokens[*i]))),
+        tok => Err(ParseError::InvalidType(format!("Expected a type, found {}", describe_token(Some(tok)))).with_span(&tokens.span(*i))),
     }
 }
@@ -0,0 +1,39 @@
+mod common;
+
+use common::{global, text};
+use mur_lang::Value;
+
+#[test]
+fn access_chains_from_a_call_result() {
+    let source = r#"
+rrkgr Murloc
+mrgl
+    name: blbtxt,
+grl
+grrrfnrrg clan(leader)
+mrgl
+    grrrtn [Murloc { name: leader }, Murloc { name: "Blrgl" }]
+grl
+grrrfnrrg pair(n)
+mrgl
+    grrrtn [n, n * 2]
+grl
+grrr leader = grrrblbl clan("Grrmrgl")[0].name
+grrr second = grrrblbl pair(5)[1]
+"#;
+    assert_eq!(global(source, "leader"), text("Grrmrgl"));
+    assert_eq!(global(source, "second"), Value::Number(10));
+}
+
+#[test]
+fn indexes_chain_into_nested_values() {
+    let source = r#"
+grrr m = { "k": [1, [2, 3]] }
+grrr inner = m["k"][1]
+grrr deepest = m["k"][1][0]
+grrr literal = [[4, 5], [6]][0][1]
+"#;
+    assert_eq!(global(source, "inner"), Value::Array(vec![Value::Number(2), Value::Number(3)]));
+    assert_eq!(global(source, "deepest"), Value::Number(2));
+    assert_eq!(global(source, "literal"), Value::Number(5));
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{error_kind, global};
+use mur_lang::interpreter::ErrorValue;
+use mur_lang::lexer::TokenStream;
+use mur_lang::{parse_expression, tokenize, Value};
+
+#[test]
+fn ordering_values_without_an_order_is_a_type_error() {
+    assert_eq!(error_kind("grrr x = \"a\" < 1"), "TypeError");
+    assert_eq!(error_kind("grrr x = mrgtru < mrgfls"), "TypeError");
+    assert_eq!(error_kind("grrr x = [1] >= [1]"), "TypeError");
+}
+
+#[test]
+fn the_type_error_can_be_caught() {
+    let source = r#"
+grrr kind = ""
+mrglgl mrgl
+    grrr x = "a" < 1
+grl mrglurp (err) mrgl
+    kind = err.kind
+grl
+"#;
+    assert_eq!(global(source, "kind"), Value::Text("TypeError".to_string()));
+}
+
+#[test]
+fn numbers_and_text_still_order_and_anything_compares_equal() {
+    assert_eq!(global("grrr x = 1 < 2.5", "x"), Value::Bool(true));
+    assert_eq!(global("grrr x = \"cod\" < \"eel\"", "x"), Value::Bool(true));
+    assert_eq!(global("grrr x = mrgtru == 1", "x"), Value::Bool(false));
+    assert_eq!(global("grrr x = [1] == [1]", "x"), Value::Bool(true));
+}
+
+/// Evaluates `source` as a constant expression, outside the runtime.
+fn constant(source: &str) -> Result<Value, String> {
+    let tokens = TokenStream::new(tokenize(source).unwrap(), "test.mur".to_string());
+    let expression = parse_expression(&tokens, &mut 0).unwrap();
+    expression.eval(&HashMap::new()).map_err(|e| ErrorValue::from_error(&e).kind)
+}
+
+#[test]
+fn constant_expressions_compare_the_same_way() {
+    assert_eq!(constant("\"cod\" < \"eel\""), Ok(Value::Bool(true)));
+    assert_eq!(constant("1 < 2"), Ok(Value::Bool(true)));
+    assert_eq!(constant("\"a\" < 1"), Err("TypeError".to_string()));
+    assert_eq!(constant("mrgtru < mrgfls"), Err("TypeError".to_string()));
+    assert_eq!(constant("[1] >= [1]"), Err("TypeError".to_string()));
+}
+
+#[test]
+fn booleans_can_be_looked_for_in_arrays() {
+    assert_eq!(constant("mrgtru blgr [mrgfls, mrgtru]"), Ok(Value::Bool(true)));
+    assert_eq!(global("grrr x = mrgfls blgr [mrgtru]", "x"), Value::Bool(false));
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use common::global;
+use mur_lang::Value;
+
+/// A function whose body does several things before returning, so a call
+/// that stopped early would leave `steps` short.
+const TALLY: &str = r#"
+grrr steps = 0
+grrip rounds [1, 2]
+grrrfnrrg tally(x)
+mrgl
+    steps = steps + 1
+    grrr doubled = x * 2
+    grlbrr (doubled > 0)
+    mrgl
+        steps = steps + 1
+    grl
+    mrrg i blgr rounds
+    mrgl
+        steps = steps + 1
+    grl
+    grrrtn doubled
+grl
+"#;
+
+fn run_tally(calls: &str) -> (Value, Value) {
+    let source = format!("{}{}", TALLY, calls);
+    (global(&source, "steps"), global(&source, "result"))
+}
+
+#[test]
+fn a_call_statement_runs_the_whole_body() {
+    assert_eq!(run_tally("grrrblbl tally(1)\ngrrr result = 0"), (Value::Number(4), Value::Number(0)));
+}
+
+#[test]
+fn a_call_in_an_expression_runs_the_whole_body() {
+    assert_eq!(run_tally("grrr result = grrrblbl tally(2) + 1"), (Value::Number(4), Value::Number(5)));
+}
+
+#[test]
+fn nested_calls_run_the_whole_body() {
+    assert_eq!(run_tally("grrr result = grrrblbl tally(grrrblbl tally(1))"), (Value::Number(8), Value::Number(4)));
+}
+
+#[test]
+fn calls_through_a_value_run_the_whole_body() {
+    assert_eq!(
+        run_tally("grrr fs = [tally]\ngrrrblbl fs[0](1)\ngrrr result = grrrblbl fs[0](3)"),
+        (Value::Number(8), Value::Number(6))
+    );
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::{error_kind, global, run, text};
+use mur_lang::Value;
+
+#[test]
+fn a_worker_sums_what_it_receives() {
+    let source = r#"
+grrr jobs = grrrblbl channel(2)
+grrr worker = splurg mrgl
+    grrr total = 0
+    mrrg job blgr jobs mrgl
+        total = total + job
+    grl
+    grrrtn total
+grl
+mrrg n blgr 1..5 mrgl
+    grrrblbl send(jobs, n)
+grl
+grrrblbl close(jobs)
+grrr total = mrgurl worker
+"#;
+    assert_eq!(global(source, "total"), Value::Number(15));
+}
+
+#[test]
+fn values_arrive_in_the_order_they_were_sent() {
+    let source = r#"
+grrr ch = grrrblbl channel()
+grrrblbl send(ch, "cod")
+grrrblbl send(ch, "eel")
+grrr first = grrrblbl recv(ch)
+grrr second = grrrblbl recv(ch)
+"#;
+    let runtime = run(source).unwrap();
+    assert_eq!(runtime.env.get_var("first").unwrap(), text("cod"));
+    assert_eq!(runtime.env.get_var("second").unwrap(), text("eel"));
+}
+
+#[test]
+fn try_recv_and_recv_timeout_do_not_wait_forever() {
+    let source = r#"
+grrr ch = grrrblbl channel()
+grrr nothing = grrrblbl try_recv(ch)
+grrr late = grrrblbl recv_timeout(ch, 10)
+grrrblbl send(ch, 7)
+grrr something = grrrblbl try_recv(ch)
+"#;
+    let runtime = run(source).unwrap();
+    assert_eq!(runtime.env.get_var("nothing").unwrap(), Value::Array(vec![]));
+    assert_eq!(runtime.env.get_var("late").unwrap(), Value::Array(vec![]));
+    assert_eq!(runtime.env.get_var("something").unwrap(), Value::Array(vec![Value::Number(7)]));
+}
+
+#[test]
+fn closed_channels_still_give_back_sent_values() {
+    let source = r#"
+grrr ch = grrrblbl channel()
+grrrblbl send(ch, 1)
+grrrblbl close(ch)
+grrr last = grrrblbl recv(ch)
+"#;
+    assert_eq!(global(source, "last"), Value::Number(1));
+}
+
+#[test]
+fn sending_on_a_closed_channel_fails() {
+    let source = r#"
+grrr ch = grrrblbl channel()
+grrrblbl close(ch)
+grrrblbl send(ch, 1)
+"#;
+    assert_eq!(error_kind(source), "InvalidOperation");
+}
+
+#[test]
+fn receiving_from_a_closed_empty_channel_fails() {
+    let source = r#"
+grrr ch = grrrblbl channel()
+grrrblbl close(ch)
+grrr value = grrrblbl recv(ch)
+"#;
+    assert_eq!(error_kind(source), "InvalidOperation");
+}
@@ -0,0 +1,87 @@
+#![allow(dead_code)]
+
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use mur_lang::interpreter::ErrorValue;
+use mur_lang::lexer::TokenStream;
+use mur_lang::{parse, tokenize, MurlocRuntime, ParseError, Value};
+
+/// Runs `source` as if it were the file at `path`, with `input` as the lines
+/// `mrglisn` reads. Hands back the runtime so tests can look at the globals
+/// the program left behind.
+pub fn run_file(path: &Path, source: &str, input: &str) -> Result<MurlocRuntime, Vec<ParseError>> {
+    let file = path.display().to_string();
+    let tokens = tokenize(source).map_err(|e| vec![e.into_parse_error(&file)])?;
+    let statements = parse(TokenStream::new(tokens, file))?;
+    let runtime = MurlocRuntime::new().with_input(Cursor::new(input.to_string()));
+    runtime.run(statements).map_err(|e| vec![e])?;
+    Ok(runtime)
+}
+
+/// A fresh directory holding `files`, each given as a name and its source.
+pub fn reef(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("murlang-test-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (file, source) in files {
+        fs::write(dir.join(file), source).unwrap();
+    }
+    dir
+}
+
+pub fn run_with_input(source: &str, input: &str) -> Result<MurlocRuntime, Vec<ParseError>> {
+    run_file(Path::new("test.mur"), source, input)
+}
+
+pub fn run(source: &str) -> Result<MurlocRuntime, Vec<ParseError>> {
+    run_with_input(source, "")
+}
+
+/// The syntax errors `source` is rejected with.
+pub fn syntax_errors(source: &str) -> Vec<ParseError> {
+    let tokens = tokenize(source).unwrap_or_else(|e| panic!("tokenizing failed: {:?}", e));
+    match parse(TokenStream::new(tokens, "test.mur")) {
+        Ok(_) => panic!("program was expected to be rejected"),
+        Err(errors) => errors,
+    }
+}
+
+/// The line each syntax error in `source` points at.
+pub fn syntax_error_lines(source: &str) -> Vec<usize> {
+    syntax_errors(source).iter().map(|e| e.span().expect("syntax errors have a location").line).collect()
+}
+
+/// The value of global `name` after running `source`.
+pub fn global(source: &str, name: &str) -> Value {
+    let runtime = run(source).unwrap_or_else(|errors| panic!("program failed: {:?}", errors));
+    runtime.env.get_var(name).unwrap_or_else(|e| panic!("no global '{}': {:?}", name, e))
+}
+
+/// A text value, as the tests spell it.
+pub fn text(value: &str) -> Value {
+    Value::Text(value.to_string())
+}
+
+/// The message of the single error `source` fails with.
+pub fn error_message(source: &str) -> String {
+    match run(source) {
+        Ok(_) => panic!("program was expected to fail"),
+        Err(errors) => {
+            assert_eq!(errors.len(), 1, "expected one error, got {:?}", errors);
+            errors[0].to_string()
+        }
+    }
+}
+
+/// The kind of the single error `source` fails with.
+pub fn error_kind(source: &str) -> String {
+    match run(source) {
+        Ok(_) => panic!("program was expected to fail"),
+        Err(errors) => {
+            assert_eq!(errors.len(), 1, "expected one error, got {:?}", errors);
+            ErrorValue::from_error(&errors[0]).kind
+        }
+    }
+}
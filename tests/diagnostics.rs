@@ -0,0 +1,49 @@
+mod common;
+
+use common::{run, syntax_errors};
+use mur_lang::diagnostics::Diagnostic;
+
+/// The headline of the single syntax error `source` is rejected with.
+fn syntax_message(source: &str) -> String {
+    let errors = syntax_errors(source);
+    assert_eq!(errors.len(), 1, "expected one error, got {:?}", errors);
+    Diagnostic::from(&errors[0]).message
+}
+
+#[test]
+fn tokens_are_shown_as_written() {
+    let message = syntax_message("grrr a = P { x: 1 y: 2 }");
+    assert!(message.ends_with("Expected ',' or '}', found 'y'"), "{}", message);
+    let message = syntax_message("grrrtn = 1");
+    assert!(message.ends_with("Expected a value, found '='"), "{}", message);
+}
+
+#[test]
+fn keywords_use_their_murloc_spelling() {
+    let message = syntax_message("grlbrr 1 > 0 glglrr 1");
+    assert!(message.ends_with("Expected 'mrgl', found 'glglrr'"), "{}", message);
+    let message = syntax_message("grrr a = grrr");
+    assert!(message.ends_with("'grrr' cannot start an expression"), "{}", message);
+}
+
+#[test]
+fn running_out_of_tokens_is_the_end_of_the_file() {
+    let message = syntax_message("grrr a =");
+    assert!(message.ends_with("Expected a value, found end of file"), "{}", message);
+    let message = syntax_message("glurpin { a } frmgl");
+    assert!(!message.contains("None"), "{}", message);
+}
+
+#[test]
+fn values_are_shown_as_written() {
+    let errors = run("grrrtn 5").err().unwrap();
+    let message = Diagnostic::from(&errors[0]).message;
+    assert!(message.ends_with("Offering returned: 5"), "{}", message);
+}
+
+#[test]
+fn runtime_errors_keep_only_their_own_text() {
+    let errors = run("grrr a = missing").err().unwrap();
+    let message = Diagnostic::from(&errors[0]).message;
+    assert_eq!(message, "'missing' floats undefined in the tide. Summon it, fool!");
+}
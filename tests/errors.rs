@@ -0,0 +1,50 @@
+mod common;
+
+use common::{global, run, text};
+use mur_lang::interpreter::ErrorValue;
+use mur_lang::Value;
+
+#[test]
+fn caught_errors_carry_their_kind_and_message() {
+    let source = r#"
+grrr kind = ""
+grrr message = ""
+mrglgl mrgl
+    grrr ratio = 10 / 0
+grl mrglurp (err) mrgl
+    kind = err.kind
+    message = err.message
+grl
+"#;
+    let runtime = run(source).unwrap();
+    assert_eq!(runtime.env.get_var("kind").unwrap(), text("DivisionByZero"));
+    assert_ne!(runtime.env.get_var("message").unwrap(), text(""));
+}
+
+#[test]
+fn thrown_values_keep_their_value() {
+    let source = r#"
+grrr caught = 0
+mrglgl mrgl
+    blurgh 42
+grl mrglurp (err: Thrown) mrgl
+    caught = err.value
+grl
+"#;
+    assert_eq!(global(source, "caught"), Value::Number(42));
+}
+
+#[test]
+fn rethrowing_keeps_the_original_error() {
+    let source = r#"
+mrglgl mrgl
+    grrr ratio = 10 / 0
+grl mrglurp (err) mrgl
+    blurgh err
+grl
+"#;
+    let errors = run(source).err().unwrap();
+    let error = ErrorValue::from_error(&errors[0]);
+    assert_eq!(error.kind, "DivisionByZero");
+    assert_eq!(error.span.map(|span| span.line), Some(3));
+}
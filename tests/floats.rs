@@ -0,0 +1,16 @@
+mod common;
+
+use common::{error_kind, global};
+use mur_lang::Value;
+
+#[test]
+fn integers_and_floats_mix() {
+    assert_eq!(global("grrr x = 1 + 0.5", "x"), Value::Float(1.5));
+    assert_eq!(global("grrr x = 7 / 2", "x"), Value::Number(3));
+    assert_eq!(global("grrr x = 2.0 == 2", "x"), Value::Bool(true));
+}
+
+#[test]
+fn float_division_by_zero_is_an_error() {
+    assert_eq!(error_kind("grrr x = 1.5 / 0"), "DivisionByZero");
+}
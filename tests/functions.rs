@@ -0,0 +1,71 @@
+mod common;
+
+use common::{error_kind, global, text};
+use mur_lang::Value;
+
+#[test]
+fn a_closure_keeps_its_captured_state_between_calls() {
+    let source = r#"
+grrrfnrrg make_counter()
+mrgl
+    grrr count = 0
+    grrrtn grrrfnrrg()
+    mrgl
+        count = count + 1
+        grrrtn count
+    grl
+grl
+grrr next = grrrblbl make_counter()
+grrrblbl next()
+grrrblbl next()
+grrr third = grrrblbl next()
+"#;
+    assert_eq!(global(source, "third"), Value::Number(3));
+}
+
+#[test]
+fn functions_can_be_passed_as_callbacks() {
+    let source = r#"
+grrrfnrrg twice(f, x)
+mrgl
+    grrrtn grrrblbl f(grrrblbl f(x))
+grl
+grrrfnrrg inc(x)
+mrgl
+    grrrtn x + 1
+grl
+grrr named = grrrblbl twice(inc, 1)
+grrr anonymous = grrrblbl twice(grrrfnrrg(x) mrgl grrrtn x * 3 grl, 1)
+"#;
+    assert_eq!(global(source, "named"), Value::Number(3));
+    assert_eq!(global(source, "anonymous"), Value::Number(9));
+}
+
+#[test]
+fn functions_in_arrays_and_fields_can_be_called() {
+    let source = r#"
+rrkgr Murloc
+mrgl
+    name: blbtxt,
+    greet: grrrfnrrg,
+grl
+grrr fs = [grrrfnrrg(x) mrgl grrrtn x * 2 grl]
+grrr clan = [
+    Murloc { name: "Grrmrgl", greet: grrrfnrrg(x) mrgl grrrtn "mrgl " + x grl },
+    Murloc { name: "Blrgl", greet: grrrfnrrg(x) mrgl grrrtn "aaugh " + x grl }
+]
+grrr doubled = grrrblbl fs[0](21)
+grrr greeting = grrrblbl clan[1].greet("hi")
+"#;
+    assert_eq!(global(source, "doubled"), Value::Number(42));
+    assert_eq!(global(source, "greeting"), text("aaugh hi"));
+}
+
+#[test]
+fn calling_something_that_is_not_a_function_is_a_type_error() {
+    let source = r#"
+grrr fs = [1]
+grrr x = grrrblbl fs[0](2)
+"#;
+    assert_eq!(error_kind(source), "TypeError");
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use common::global;
+use mur_lang::Value;
+
+const DOUBLE: &str = r#"
+argl grrrfnrrg double(n) mrgl
+    grrrtn n * 2
+grl
+"#;
+
+#[test]
+fn awaiting_a_named_future_gives_its_result() {
+    let source = format!("{}\ngrrr fut = argl grrrblbl double(21)\ngrrr result = mrgargl fut", DOUBLE);
+    assert_eq!(global(&source, "result"), Value::Number(42));
+}
+
+#[test]
+fn awaiting_a_call_gives_its_result() {
+    let source = format!("{}\ngrrr result = mrgargl grrrblbl double(4)", DOUBLE);
+    assert_eq!(global(&source, "result"), Value::Number(8));
+}
+
+#[test]
+fn a_named_future_can_be_awaited_as_a_statement() {
+    let source = format!("{}\ngrrr fut = argl grrrblbl double(1)\nmrgargl fut\ngrrr done = mrgtru", DOUBLE);
+    assert_eq!(global(&source, "done"), Value::Bool(true));
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::{run_with_input, text};
+use mur_lang::interpreter::ErrorValue;
+use mur_lang::Value;
+
+#[test]
+fn reads_lines_in_order() {
+    let runtime = run_with_input("mrglisn first\nmrglisn second", "Mrgl\nBlub\n").unwrap();
+    assert_eq!(runtime.env.get_var("first").unwrap(), text("Mrgl"));
+    assert_eq!(runtime.env.get_var("second").unwrap(), text("Blub"));
+}
+
+#[test]
+fn reads_with_and_without_a_prompt() {
+    let source = "grrr name = mrglisn\ngrrr age = grrrblbl to_number(mrglisn \"Age? \")";
+    let runtime = run_with_input(source, "Grrgl\r\n42\n").unwrap();
+    assert_eq!(runtime.env.get_var("name").unwrap(), text("Grrgl"));
+    assert_eq!(runtime.env.get_var("age").unwrap(), Value::Number(42));
+}
+
+#[test]
+fn converts_text_to_numbers() {
+    let source = r#"
+grrr whole = grrrblbl to_whole("3.9")
+grrr fraction = grrrblbl to_number("2.5")
+grrr yes = grrrblbl is_number(" 7 ")
+grrr no = grrrblbl is_number("seven")
+"#;
+    let runtime = run_with_input(source, "").unwrap();
+    assert_eq!(runtime.env.get_var("whole").unwrap(), Value::Number(3));
+    assert_eq!(runtime.env.get_var("fraction").unwrap(), Value::Float(2.5));
+    assert_eq!(runtime.env.get_var("yes").unwrap(), Value::Bool(true));
+    assert_eq!(runtime.env.get_var("no").unwrap(), Value::Bool(false));
+}
+
+#[test]
+fn running_out_of_input_is_an_error() {
+    let errors = run_with_input("mrglisn first\nmrglisn second", "only line\n").err().unwrap();
+    let error = ErrorValue::from_error(&errors[0]);
+    assert_eq!(error.kind, "InvalidOperation");
+    assert!(error.message.contains("no more input"), "{}", error.message);
+}
+
+#[test]
+fn running_out_of_input_can_be_caught() {
+    let source = r#"
+grrr line = "none"
+mrglgl mrgl
+    line = mrglisn
+grl mrglurp (e) mrgl
+    line = e.kind
+grl
+"#;
+    let runtime = run_with_input(source, "").unwrap();
+    assert_eq!(runtime.env.get_var("line").unwrap(), text("InvalidOperation"));
+}
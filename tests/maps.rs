@@ -0,0 +1,67 @@
+mod common;
+
+use common::{error_kind, global, text};
+use mur_lang::Value;
+
+#[test]
+fn a_duplicate_key_in_a_literal_keeps_the_last_value_in_the_first_place() {
+    let source = r#"
+grrr m = { "a": 1, "b": 2, "a": 3 }
+grrr keys = ""
+mrrg k blgr m
+mrgl
+    keys = keys + k
+grl
+grrr a = m["a"]
+"#;
+    assert_eq!(global(source, "a"), Value::Number(3));
+    assert_eq!(global(source, "keys"), text("ab"));
+}
+
+#[test]
+fn keys_are_walked_in_the_order_they_were_added() {
+    let source = r#"
+grrr m = { "zed": 1 }
+m["amy"] = 2
+m["bob"] = 3
+m["zed"] = 4
+grrr order = ""
+mrrg k blgr m
+mrgl
+    order = order + k
+grl
+"#;
+    assert_eq!(global(source, "order"), text("zedamybob"));
+}
+
+#[test]
+fn indexing_a_missing_key_fails() {
+    assert_eq!(error_kind("grrr m = { \"a\": 1 }\ngrrr x = m[\"b\"]"), "IndexOutOfBounds");
+}
+
+#[test]
+fn grrmv_drops_a_key() {
+    let source = r#"
+grrr m = { "a": 1, "b": 2 }
+grrmv m["a"]
+grrr has_a = "a" blgr m
+grrr has_b = "b" blgr m
+grrr has_number = 1 blgr { 1: "one" }
+"#;
+    assert_eq!(global(source, "has_a"), Value::Bool(false));
+    assert_eq!(global(source, "has_b"), Value::Bool(true));
+    assert_eq!(global(source, "has_number"), Value::Bool(true));
+}
+
+#[test]
+fn two_loop_variables_get_each_key_and_value() {
+    let source = r#"
+grrr scores = { "bob": 3, "amy": 5 }
+grrr seen = ""
+mrrg name, score blgr scores
+mrgl
+    seen = seen + name + "=" + score + " "
+grl
+"#;
+    assert_eq!(global(source, "seen"), text("bob=3 amy=5 "));
+}
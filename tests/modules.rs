@@ -0,0 +1,112 @@
+mod common;
+
+use common::{reef, run_file};
+use mur_lang::interpreter::ErrorValue;
+use mur_lang::Value;
+
+const GEO: &str = r#"
+rrkgr Point
+mrgl
+    x: numblrr,
+    y: numblrr,
+grl
+
+grrrfnrrg area(side)
+mrgl
+    grrrtn side * side
+grl
+
+grrr hidden = 1
+
+shrglr Point
+shrglr area
+"#;
+
+#[test]
+fn named_and_namespace_imports() {
+    let dir = reef("imports", &[("geo.mur", GEO)]);
+    let source = r#"
+glurpin { area } frmgl "geo"
+glurpin * azgl geo frmgl "geo"
+
+grrr square = grrrblbl area(3)
+grrr also = grrrblbl geo.area(4)
+grrr corner = geo.Point { x: 1, y: 2 }
+grrr corner_x = corner.x
+"#;
+    let runtime = run_file(&dir.join("main.mur"), source, "").unwrap();
+    assert_eq!(runtime.env.get_var("square").unwrap(), Value::Number(9));
+    assert_eq!(runtime.env.get_var("also").unwrap(), Value::Number(16));
+    assert_eq!(runtime.env.get_var("corner_x").unwrap(), Value::Number(1));
+    assert!(matches!(runtime.env.get_var("corner").unwrap(), Value::Struct(name, _) if name == "geo.Point"));
+}
+
+#[test]
+fn unexported_names_stay_hidden() {
+    let dir = reef("hidden", &[("geo.mur", GEO)]);
+    let errors = run_file(&dir.join("main.mur"), "glurpin { hidden } frmgl \"geo\"", "").err().unwrap();
+    assert_eq!(ErrorValue::from_error(&errors[0]).kind, "InvalidOperation");
+}
+
+#[test]
+fn import_cycles_are_reported() {
+    let dir = reef("cycle", &[
+        ("a.mur", "glurpin { b } frmgl \"b\"\ngrrr a = 1\nshrglr a"),
+        ("b.mur", "glurpin { a } frmgl \"a\"\ngrrr b = 2\nshrglr b"),
+    ]);
+    let errors = run_file(&dir.join("main.mur"), "glurpin { a } frmgl \"a\"", "").err().unwrap();
+    let error = ErrorValue::from_error(&errors[0]);
+    assert_eq!(error.kind, "InvalidOperation");
+    assert!(error.message.contains("Import cycle"), "{}", error.message);
+}
+
+#[test]
+fn cycles_back_to_the_entry_file_are_reported() {
+    let main = "glurpin { a } frmgl \"a\"\ngrrr main = 0\nshrglr main";
+    let dir = reef("entry-cycle", &[
+        ("main.mur", main),
+        ("a.mur", "glurpin { b } frmgl \"b\"\ngrrr a = 1\nshrglr a"),
+        ("b.mur", "glurpin { main } frmgl \"main\"\ngrrr b = 2\nshrglr b"),
+    ]);
+    let errors = run_file(&dir.join("main.mur"), main, "").err().unwrap();
+    let message = ErrorValue::from_error(&errors[0]).message;
+    let chain: Vec<_> = message
+        .trim_start_matches("Import cycle in the tide: ")
+        .split(" -> ")
+        .map(|path| path.rsplit(['/', '\\']).next().unwrap())
+        .collect();
+    assert_eq!(chain, ["main.mur", "a.mur", "b.mur", "main.mur"], "{}", message);
+}
+
+#[test]
+fn shared_imports_are_not_cycles() {
+    let dir = reef("diamond", &[
+        ("base.mur", "grrr base = 1\nshrglr base"),
+        ("left.mur", "glurpin { base } frmgl \"base\"\ngrrr left = base + 1\nshrglr left"),
+        ("right.mur", "glurpin { base } frmgl \"base\"\ngrrr right = base + 2\nshrglr right"),
+    ]);
+    let source = "glurpin { left } frmgl \"left\"\nglurpin { right } frmgl \"right\"\ngrrr total = left + right";
+    let runtime = run_file(&dir.join("main.mur"), source, "").unwrap();
+    assert_eq!(runtime.env.get_var("total").unwrap(), Value::Number(5));
+}
+
+#[test]
+fn threads_importing_the_same_module_share_it() {
+    let dir = reef("threads", &[("geo.mur", GEO)]);
+    let source = r#"
+grrr first = splurg mrgl
+    glurpin { area } frmgl "geo"
+    grrrtn grrrblbl area(2)
+grl
+grrr second = splurg mrgl
+    glurpin { area } frmgl "geo"
+    grrrtn grrrblbl area(3)
+grl
+grrr results = mrgurl [first, second]
+"#;
+    let runtime = run_file(&dir.join("main.mur"), source, "").unwrap();
+    assert_eq!(
+        runtime.env.get_var("results").unwrap(),
+        Value::Array(vec![Value::Number(4), Value::Number(9)])
+    );
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::{syntax_error_lines, syntax_errors};
+use mur_lang::lexer::TokenStream;
+use mur_lang::{parse, tokenize, Statement};
+
+/// Statements of every kind, each starting on its own line from line 2,
+/// column 5 on, where they land once wrapped in a block below.
+const BODY: &str = "
+    grrr a = 1 + 2
+    a = a * 3
+    grlbrr a > 1 mrgl glglrr a grl blrrgl mrgl glglrr 0 grl
+    mrrg i = 0; i < 3; i = i + 1 mrgl flllurlog grl
+    gglrbl a < 0 mrgl flurg grl
+    murrrgh a mrgl grlblgl 5: glglrr \"five\" blrrghlt: glglrr \"other\" grl
+    mrglgl mrgl glglrr \"x\" grl mrglurp (e) mrgl glglrr e grl
+    grrrblbl print_all(a)";
+
+fn statements(source: &str) -> Vec<Statement> {
+    let tokens = tokenize(source).unwrap_or_else(|e| panic!("tokenizing failed: {:?}", e));
+    parse(TokenStream::new(tokens, "test.mur")).unwrap_or_else(|errors| panic!("parsing failed: {:?}", errors))
+}
+
+#[test]
+fn statements_parse_the_same_at_any_depth() {
+    let top_level = statements(BODY);
+    let wrappers = [
+        ("grlbrr 1 mrgl", "grl"),
+        ("gglrbl 0 mrgl", "grl"),
+        ("mrrg x blgr xs mrgl", "grl"),
+        ("grrrfnrrg f() mrgl", "grl"),
+        ("grlbrr 1 mrgl gglrbl 0 mrgl mrrg x blgr xs mrgl", "grl grl grl"),
+    ];
+    for (open, close) in wrappers {
+        let nested = format!("{:?}", statements(&format!("{}{}\n{}", open, BODY, close)));
+        for statement in &top_level {
+            let statement = format!("{:?}", statement);
+            assert!(nested.contains(&statement), "'{}' changed the parse of {}", open, statement);
+        }
+    }
+}
+
+#[test]
+fn stray_tokens_in_a_switch_body_are_rejected() {
+    let source = "murrrgh 1\nmrgl\n    5\n    grlblgl 1:\n        glglrr 1\ngrl";
+    assert_eq!(syntax_error_lines(source), vec![3]);
+    assert!(!syntax_errors("murrrgh 1\nmrgl\n    glglrr 1\ngrl").is_empty());
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{error_kind, global};
+use mur_lang::lexer::TokenStream;
+use mur_lang::{parse_expression, tokenize, Value};
+
+#[test]
+fn integers_widen_instead_of_overflowing() {
+    assert!(matches!(global("grrr x = 2147483647 + 1", "x"), Value::NumberI64(2147483648)));
+    assert!(matches!(global("grrr x = 9223372036854775807 * 2", "x"), Value::NumberBig(_)));
+}
+
+#[test]
+fn big_results_narrow_back_down() {
+    let x = global("grrr big = 9223372036854775807 + 1\ngrrr x = big - 9223372036854775800", "x");
+    assert!(matches!(x, Value::Number(8)), "{:?}", x);
+}
+
+#[test]
+fn factorial_grows_into_a_big_integer() {
+    let source = r#"
+grrr total = 1
+grrr i = 1
+gglrbl i <= 25 mrgl
+    total = total * i
+    i = i + 1
+grl
+"#;
+    assert_eq!(global(source, "total").to_string(), "15511210043330985984000000");
+}
+
+#[test]
+fn division_by_zero_is_an_error() {
+    assert_eq!(error_kind("grrr x = 1 / 0"), "DivisionByZero");
+    assert_eq!(error_kind("grrr x = 5 % 0"), "DivisionByZero");
+}
+
+#[test]
+fn constant_expressions_use_the_same_arithmetic() {
+    let tokens = TokenStream::new(tokenize("2147483647 + 1").unwrap(), "test.mur".to_string());
+    let expression = parse_expression(&tokens, &mut 0).unwrap();
+    let value = expression.eval(&HashMap::new()).unwrap();
+    assert!(matches!(value, Value::NumberI64(2147483648)), "{:?}", value);
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::{error_kind, global};
+use mur_lang::Value;
+
+const PACK: &str = r#"
+rrkgr Murloc
+mrgl
+    name: blbtxt,
+    scores: grrip<numblrr>,
+grl
+grrr clan = [Murloc { name: "Grrmrgl", scores: [1, 2] }, Murloc { name: "Blrgl", scores: [3, 4] }]
+"#;
+
+fn with_pack(rest: &str) -> String {
+    format!("{}{}", PACK, rest)
+}
+
+#[test]
+fn assignment_reaches_through_indexes_and_fields() {
+    let source = with_pack("clan[1].scores[0] = 9\ngrrr changed = clan[1].scores\ngrrr untouched = clan[0].scores");
+    assert_eq!(global(&source, "changed"), Value::Array(vec![Value::Number(9), Value::Number(4)]));
+    assert_eq!(global(&source, "untouched"), Value::Array(vec![Value::Number(1), Value::Number(2)]));
+}
+
+#[test]
+fn an_index_past_the_end_is_out_of_bounds() {
+    assert_eq!(error_kind(&with_pack("clan[2].name = \"Mrrgl\"")), "IndexOutOfBounds");
+    assert_eq!(error_kind(&with_pack("clan[0].scores[2] = 5")), "IndexOutOfBounds");
+}
+
+#[test]
+fn an_undeclared_field_cannot_be_assigned() {
+    assert_eq!(error_kind(&with_pack("clan[0].level = 3")), "InvalidOperation");
+}
+
+#[test]
+fn fields_only_take_values_of_their_type() {
+    assert_eq!(error_kind(&with_pack("clan[0].name = 3")), "TypeError");
+    assert_eq!(error_kind(&with_pack("clan[0].scores = [1, \"two\"]")), "TypeError");
+    assert_eq!(error_kind(&with_pack("clan[0].scores[1] = \"two\"")), "TypeError");
+}
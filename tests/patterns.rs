@@ -0,0 +1,157 @@
+mod common;
+
+use common::{error_kind, error_message, global, text};
+use mur_lang::Value;
+
+const SHAPES: &str = r#"
+rrknum Shape
+mrgl
+    Circle(radius),
+    Rect(width, height),
+    Empty,
+grl
+"#;
+
+/// The message of the single error `source` fails with.
+#[test]
+fn variant_patterns_bind_their_payload() {
+    let source = format!("{}{}", SHAPES, r#"
+grrr area = 0
+murrrgh grrrblbl Shape.Rect(3, 4)
+mrgl
+    grlblgl Shape.Circle(r):
+        area = r * r * 3
+    grlblgl Shape.Rect(w, h):
+        area = w * h
+    grlblgl Shape.Empty:
+        area = 0
+grl
+"#);
+    assert_eq!(global(&source, "area"), Value::Number(12));
+}
+
+#[test]
+fn struct_patterns_match_fields_and_bind_the_rest() {
+    let source = r#"
+rrkgr Point
+mrgl
+    x: numblrr,
+    y: numblrr,
+grl
+grrr found = ""
+murrrgh Point { x: 0, y: 7 }
+mrgl
+    grlblgl Point { x: 1, y }:
+        found = "x is one"
+    grlblgl Point { x: 0, y }:
+        found = "on the axis at " + y
+grl
+"#;
+    assert_eq!(global(source, "found"), text("on the axis at 7"));
+}
+
+#[test]
+fn array_patterns_bind_the_rest() {
+    let source = r#"
+grrr first = 0
+grrr rest = []
+murrrgh [1, 2, 3]
+mrgl
+    grlblgl []:
+        first = -1
+    grlblgl [head, ..tail]:
+        first = head
+        rest = tail
+grl
+"#;
+    assert_eq!(global(source, "first"), Value::Number(1));
+    assert_eq!(global(source, "rest"), Value::Array(vec![Value::Number(2), Value::Number(3)]));
+}
+
+#[test]
+fn alternatives_ranges_literals_and_wildcards() {
+    let source = r#"
+grrrfnrrg describe(n)
+mrgl
+    murrrgh n
+    mrgl
+        grlblgl 0:
+            grrrtn "zero"
+        grlblgl 1, 2, 3:
+            grrrtn "few"
+        grlblgl 4..10:
+            grrrtn "some"
+        grlblgl _:
+            grrrtn "many"
+    grl
+grl
+grrr described = [grrrblbl describe(0), grrrblbl describe(2), grrrblbl describe(10), grrrblbl describe(11)]
+"#;
+    assert_eq!(
+        global(source, "described"),
+        Value::Array(vec![text("zero"), text("few"), text("some"), text("many")])
+    );
+}
+
+#[test]
+fn a_guard_that_fails_falls_through_to_the_next_arm() {
+    let source = format!("{}{}", SHAPES, r#"
+grrr kind = ""
+murrrgh grrrblbl Shape.Rect(3, 4)
+mrgl
+    grlblgl Shape.Rect(w, h) grlbrr w == h:
+        kind = "square"
+    grlblgl Shape.Rect(w, h):
+        kind = "rectangle"
+    blrrghlt:
+        kind = "other"
+grl
+"#);
+    assert_eq!(global(&source, "kind"), text("rectangle"));
+}
+
+#[test]
+fn a_non_exhaustive_match_is_rejected_before_anything_runs() {
+    // Were the check left to the murrrgh itself, the division would fail first.
+    let source = format!("{}{}", SHAPES, r#"
+grrr ratio = 1 / 0
+murrrgh Shape.Empty
+mrgl
+    grlblgl Shape.Circle(r):
+        glglrr r
+    grlblgl Shape.Empty:
+        glglrr "nothing"
+grl
+"#);
+    assert_eq!(error_kind(&source), "InvalidOperation");
+    let message = error_message(&source);
+    assert!(message.contains("does not handle Shape.Rect"), "{}", message);
+}
+
+#[test]
+fn matches_on_enums_declared_in_a_function_are_checked() {
+    let source = r#"
+grrrfnrrg light()
+mrgl
+    rrknum Light
+    mrgl
+        Red,
+        Green,
+    grl
+    murrrgh Light.Red
+    mrgl
+        grlblgl Light.Red:
+            grrrtn "stop"
+    grl
+grl
+grrr ratio = 1 / 0
+grrrblbl light()
+"#;
+    assert!(error_message(source).contains("does not handle Light.Green"));
+}
+
+#[test]
+fn a_variant_with_a_payload_must_be_built_with_a_call() {
+    let source = format!("{}grrr s = Shape.Circle", SHAPES);
+    assert!(error_message(&source).contains("build it with 'grrrblbl Shape.Circle(...)'"));
+}
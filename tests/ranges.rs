@@ -0,0 +1,60 @@
+mod common;
+
+use common::{error_kind, global, text};
+use mur_lang::Value;
+
+/// The values `mrrg` walks for `iterable`, joined with spaces.
+fn walk(iterable: &str) -> Value {
+    let source = format!("grrr seen = \"\"\nmrrg i blgr {}\nmrgl\n    seen = seen + i + \" \"\ngrl", iterable);
+    global(&source, "seen")
+}
+
+#[test]
+fn ranges_count_through_both_ends() {
+    assert_eq!(walk("1..5 brrp 2"), text("1 3 5 "));
+    assert_eq!(walk("3..1"), text("3 2 1 "));
+}
+
+#[test]
+fn negative_steps_count_down() {
+    assert_eq!(walk("10..0 brrp -4"), text("10 6 2 "));
+}
+
+#[test]
+fn float_steps_count_in_fractions() {
+    assert_eq!(walk("0..1 brrp 0.5"), text("0 0.5 1.0 "));
+}
+
+#[test]
+fn a_step_away_from_the_end_gives_an_empty_range() {
+    assert_eq!(walk("1..5 brrp -1"), text(""));
+}
+
+#[test]
+fn a_zero_step_is_an_error() {
+    let source = "mrrg i blgr 1..5 brrp 0\nmrgl\n    glglrr i\ngrl";
+    assert_eq!(error_kind(source), "InvalidOperation");
+    let source = "mrrg i blgr 1..5 brrp 0.0\nmrgl\n    glglrr i\ngrl";
+    assert_eq!(error_kind(source), "InvalidOperation");
+}
+
+#[test]
+fn text_is_walked_by_character() {
+    assert_eq!(walk("\"mrgl\""), text("m r g l "));
+}
+
+#[test]
+fn any_expression_can_be_walked() {
+    let source = r#"
+grrrfnrrg evens(n)
+mrgl
+    grrrtn [n, n + 2]
+grl
+grrr total = 0
+mrrg x blgr grrrblbl evens(4)
+mrgl
+    total = total + x
+grl
+"#;
+    assert_eq!(global(source, "total"), Value::Number(10));
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::{global, run, text};
+use mur_lang::diagnostics::{Diagnostic, Emitter};
+use mur_lang::interpreter::MAX_RECURSION_DEPTH;
+use mur_lang::MurlocRuntime;
+
+#[test]
+fn hitting_the_depth_limit_can_be_caught() {
+    let source = r#"
+grrrfnrrg forever(n)
+mrgl
+    grrrtn grrrblbl forever(n + 1)
+grl
+grrr kind = ""
+mrglgl mrgl
+    grrrblbl forever(0)
+grl mrglurp (e: RecursionLimit) mrgl
+    kind = e.kind
+grl
+"#;
+    assert_eq!(global(source, "kind"), text("RecursionLimit"));
+}
+
+#[test]
+fn repeated_frames_are_folded_in_the_trace() {
+    let source = r#"
+grrrfnrrg down(n)
+mrgl
+    grlbrr (n == 0)
+    mrgl
+        grrrtn 1 / 0
+    grl
+    grrrtn grrrblbl down(n - 1)
+grl
+grrrblbl down(5)
+"#;
+    let errors = run(source).err().expect("program was expected to fail");
+    let rendered = Emitter::new(false).render(&Diagnostic::from(&errors[0]));
+    assert!(rendered.contains("... the same call 4 more times"), "{}", rendered);
+}
+
+#[test]
+fn the_depth_must_be_between_one_and_the_maximum() {
+    assert!(MurlocRuntime::with_max_recursion_depth(0).is_err());
+    assert!(MurlocRuntime::with_max_recursion_depth(MAX_RECURSION_DEPTH + 1).is_err());
+    assert!(MurlocRuntime::with_max_recursion_depth(MAX_RECURSION_DEPTH).is_ok());
+}
+
+#[test]
+fn the_interpreter_rejects_a_max_depth_out_of_range() {
+    let program = std::env::temp_dir().join(format!("murlang-test-{}-depth.mur", std::process::id()));
+    std::fs::write(&program, "glglrr 1").unwrap();
+    for depth in ["0".to_string(), (MAX_RECURSION_DEPTH + 1).to_string()] {
+        let status = std::process::Command::new(env!("CARGO_BIN_EXE_mur_lang"))
+            .arg(&program)
+            .args(["--max-depth", &depth])
+            .output()
+            .unwrap()
+            .status;
+        assert!(!status.success(), "--max-depth {} was accepted", depth);
+    }
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::{error_kind, global};
+use mur_lang::Value;
+
+#[test]
+fn assigning_inside_a_function_updates_the_global() {
+    let source = r#"
+grrr count = 0
+grrrfnrrg bump()
+mrgl
+    count = count + 1
+grl
+grrrblbl bump()
+grrrblbl bump()
+"#;
+    assert_eq!(global(source, "count"), Value::Number(2));
+}
+
+#[test]
+fn grrr_in_a_block_shadows_instead_of_assigning() {
+    let source = r#"
+grrr count = 0
+grrr inner = 0
+grlbrr (count == 0)
+mrgl
+    grrr count = 99
+    inner = count
+grl
+"#;
+    assert_eq!(global(source, "count"), Value::Number(0));
+    assert_eq!(global(source, "inner"), Value::Number(99));
+}
+
+#[test]
+fn the_loop_variable_does_not_outlive_the_loop() {
+    let source = r#"
+mrrg i blgr [1, 2, 3]
+mrgl
+    glglrr i
+grl
+grrr after = i
+"#;
+    assert_eq!(error_kind(source), "UndefinedVariable");
+}
+
+#[test]
+fn a_function_cannot_see_its_callers_locals() {
+    let source = r#"
+grrrfnrrg peek()
+mrgl
+    grrrtn secret
+grl
+grrrfnrrg caller()
+mrgl
+    grrr secret = 42
+    grrrtn grrrblbl peek()
+grl
+grrr seen = grrrblbl caller()
+"#;
+    assert_eq!(error_kind(source), "UndefinedVariable");
+}
+
+#[test]
+fn assigning_an_undeclared_name_fails() {
+    assert_eq!(error_kind("missing = 1"), "UndefinedVariable");
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use common::{run, syntax_errors};
+use mur_lang::ParseError;
+
+/// The line and column the single error of `errors` points at.
+fn location(errors: Vec<ParseError>) -> (usize, usize) {
+    assert_eq!(errors.len(), 1, "expected one error, got {:?}", errors);
+    let span = errors[0].span().expect("the error has a location");
+    (span.line, span.column)
+}
+
+#[test]
+fn syntax_errors_point_at_the_offending_token() {
+    assert_eq!(location(syntax_errors("grrr a = 1\ngrrr b = )")), (2, 10));
+    assert_eq!(location(syntax_errors("glglrr 1\n\n    grrr = 5")), (3, 10));
+}
+
+#[test]
+fn runtime_errors_point_at_the_failing_expression() {
+    assert_eq!(location(run("grrr a = 1\n  grrr b = missing").err().unwrap()), (2, 12));
+    assert_eq!(location(run("grrr a = 1\nglglrr a + missing").err().unwrap()), (2, 12));
+    assert_eq!(location(run("grrr a = 1\ngrrr b = a / 0").err().unwrap()), (2, 10));
+}
+
+#[test]
+fn errors_in_function_bodies_point_into_the_body() {
+    let source = "grrrfnrrg f()\nmrgl\n    grrrtn 1 / 0\ngrl\ngrrrblbl f()";
+    assert_eq!(location(run(source).err().unwrap()), (3, 12));
+}
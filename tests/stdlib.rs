@@ -0,0 +1,60 @@
+mod common;
+
+use common::{reef, run_file, text};
+use mur_lang::Value;
+
+#[test]
+fn standard_library_modules_are_bundled() {
+    let dir = reef("std", &[]);
+    let source = r#"
+glurpin * azgl math frmgl "std/math"
+glurpin { surround, quote } frmgl "std/strings"
+glurpin { contains, excludes } frmgl "std/collections"
+
+grrip catch ["cod", "eel"]
+
+grrr biggest = grrrblbl math.max(3, 9)
+grrr squared = grrrblbl math.square(7)
+grrr framed = grrrblbl surround("eel", "~")
+grrr quoted = grrrblbl quote("cod")
+grrr has_eel = grrrblbl contains(catch, "eel")
+grrr lacks_ray = grrrblbl excludes(catch, "ray")
+"#;
+    let runtime = run_file(&dir.join("main.mur"), source, "").unwrap();
+    assert_eq!(runtime.env.get_var("biggest").unwrap(), Value::Number(9));
+    assert_eq!(runtime.env.get_var("squared").unwrap(), Value::Number(49));
+    assert_eq!(runtime.env.get_var("framed").unwrap(), text("~eel~"));
+    assert_eq!(runtime.env.get_var("quoted").unwrap(), text("\"cod\""));
+    assert_eq!(runtime.env.get_var("has_eel").unwrap(), Value::Bool(true));
+    assert_eq!(runtime.env.get_var("lacks_ray").unwrap(), Value::Bool(true));
+}
+
+#[test]
+fn standard_library_helpers_take_arrays() {
+    let dir = reef("std-arrays", &[]);
+    let source = r#"
+glurpin { join } frmgl "std/strings"
+glurpin { contains_all, contains_any } frmgl "std/collections"
+
+grrip catch ["cod", "eel", "ray"]
+grrip nothing []
+grrip pair ["cod", "eel"]
+grrip swapped ["eel", "cod"]
+grrip mixed ["eel", "ray"]
+grrip stranger ["ray"]
+
+grrr joined = grrrblbl join(catch, ", ")
+grrr empty = grrrblbl join(nothing, ", ")
+grrr all = grrrblbl contains_all(pair, swapped)
+grrr not_all = grrrblbl contains_all(pair, mixed)
+grrr any = grrrblbl contains_any(pair, mixed)
+grrr none = grrrblbl contains_any(pair, stranger)
+"#;
+    let runtime = run_file(&dir.join("main.mur"), source, "").unwrap();
+    assert_eq!(runtime.env.get_var("joined").unwrap(), text("cod, eel, ray"));
+    assert_eq!(runtime.env.get_var("empty").unwrap(), text(""));
+    assert_eq!(runtime.env.get_var("all").unwrap(), Value::Bool(true));
+    assert_eq!(runtime.env.get_var("not_all").unwrap(), Value::Bool(false));
+    assert_eq!(runtime.env.get_var("any").unwrap(), Value::Bool(true));
+    assert_eq!(runtime.env.get_var("none").unwrap(), Value::Bool(false));
+}
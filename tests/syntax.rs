@@ -0,0 +1,68 @@
+mod common;
+
+use common::syntax_error_lines;
+use mur_lang::tokenize;
+
+#[test]
+fn unfinished_literals_are_reported() {
+    assert_eq!(syntax_error_lines("grrr p = P { a: 1"), vec![1]);
+    assert_eq!(syntax_error_lines("grrip n [[1"), vec![1]);
+}
+
+#[test]
+fn unclosed_call_arguments_are_reported() {
+    assert_eq!(syntax_error_lines("grrr x = grrrblbl f(1, 2"), vec![1]);
+    assert_eq!(syntax_error_lines("grrrblbl f(1, 2"), vec![1]);
+}
+
+#[test]
+fn an_error_on_the_next_line_is_not_skipped() {
+    assert_eq!(syntax_error_lines("grrr b =\ngrrr c = 5 +"), vec![2, 2]);
+}
+
+#[test]
+fn keywords_inside_expressions_do_not_start_statements() {
+    let source = "grrr a = (1 2 glglrr 3)\ngrrr b = 2\ngrrr c = *";
+    assert_eq!(syntax_error_lines(source), vec![1, 3]);
+}
+
+#[test]
+fn errors_in_every_branch_are_reported() {
+    let source = r#"
+grlbrr 1 > mrgl
+    grrr a = *
+grl blrrgl mrgl
+    grrr b = )
+grl
+mrglgl mrgl
+    grrr c = ]
+grl mrglurp (e) mrgl
+    grrr d = ,
+grl mrglast mrgl
+    grrr e = *
+grl
+grrr ok = 1
+grrr f = *
+"#;
+    assert_eq!(syntax_error_lines(source), vec![2, 3, 5, 8, 10, 12, 15]);
+}
+
+#[test]
+fn an_unterminated_string_is_reported_where_it_starts() {
+    let err = tokenize("grrr a = 1\ngrrr b = \"no end").unwrap_err();
+    assert_eq!(err.message, "Unterminated string literal");
+    assert_eq!((err.line, err.column), (2, 10));
+}
+
+#[test]
+fn every_error_in_an_anonymous_function_is_reported() {
+    let source = r#"
+grrr f = grrrfnrrg(x) mrgl
+    grrr a = *
+    grrr b = 1
+    grrr c = )
+grl
+grrr d = *
+"#;
+    assert_eq!(syntax_error_lines(source), vec![3, 5, 7]);
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::global;
+use mur_lang::Value;
+
+const DOUBLE: &str = r#"
+argl grrrfnrrg double(n) mrgl
+    grrrtn n * 2
+grl
+"#;
+
+#[test]
+fn async_calls_give_thread_handles() {
+    let source = format!("{}\ngrrr fut = argl grrrblbl double(5)\ngrrr result = mrgurl fut", DOUBLE);
+    assert_eq!(global(&source, "result"), Value::Number(10));
+}
+
+#[test]
+fn spawned_threads_can_be_awaited() {
+    let source = "grrr t = splurg mrgl grrrtn 7 grl\ngrrr result = mrgargl t";
+    assert_eq!(global(source, "result"), Value::Number(7));
+}
+
+#[test]
+fn handles_started_either_way_join_together() {
+    let source = format!(r#"{}
+grrr fut = argl grrrblbl double(3)
+grrr t = splurg mrgl grrrtn 4 grl
+grrr results = mrgurl [fut, t]
+"#, DOUBLE);
+    assert_eq!(global(&source, "results"), Value::Array(vec![Value::Number(6), Value::Number(4)]));
+}
+
+#[test]
+fn awaiting_a_call_that_returns_a_handle_waits_for_it() {
+    let source = r#"
+grrrfnrrg start() mrgl
+    grrrtn splurg mrgl grrrtn 9 grl
+grl
+grrr result = mrgargl grrrblbl start()
+"#;
+    assert_eq!(global(source, "result"), Value::Number(9));
+}
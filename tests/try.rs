@@ -0,0 +1,93 @@
+mod common;
+
+use common::{error_kind, global, text};
+use mur_lang::Value;
+
+#[test]
+fn the_first_handler_that_fits_runs() {
+    let source = r#"
+grrr handled = ""
+mrglgl mrgl
+    grrr ratio = 10 / 0
+grl mrglurp (err: TypeError) mrgl
+    handled = "type"
+grl mrglurp (err: UndefinedVariable, DivisionByZero) mrgl
+    handled = "division"
+grl mrglurp (err) mrgl
+    handled = "any"
+grl
+"#;
+    assert_eq!(global(source, "handled"), text("division"));
+}
+
+#[test]
+fn an_error_no_handler_fits_keeps_going() {
+    let source = r#"
+mrglgl mrgl
+    grrr ratio = 10 / 0
+grl mrglurp (err: TypeError) mrgl
+    glglrr "not here"
+grl
+"#;
+    assert_eq!(error_kind(source), "DivisionByZero");
+}
+
+#[test]
+fn finally_runs_after_success_failure_and_return() {
+    let source = r#"
+grrr log = ""
+grrrfnrrg leave() mrgl
+    mrglgl mrgl
+        grrrtn 1
+    grl mrglast mrgl
+        log = log + "return "
+    grl
+grl
+grrrblbl leave()
+mrglgl mrgl
+    log = log + "ok "
+grl mrglast mrgl
+    log = log + "finally "
+grl
+mrglgl mrgl
+    blurgh "boom"
+grl mrglurp (err) mrgl
+    log = log + "caught "
+grl mrglast mrgl
+    log = log + "finally"
+grl
+"#;
+    assert_eq!(global(source, "log"), text("return ok finally caught finally"));
+}
+
+#[test]
+fn finally_runs_when_the_error_escapes() {
+    let source = r#"
+grrr cleaned = mrgfls
+mrglgl mrgl
+    mrglgl mrgl
+        blurgh "boom"
+    grl mrglast mrgl
+        cleaned = mrgtru
+    grl
+grl mrglurp (err) mrgl
+grl
+"#;
+    assert_eq!(global(source, "cleaned"), Value::Bool(true));
+}
+
+#[test]
+fn loop_signals_are_not_caught() {
+    let source = r#"
+grrr count = 0
+mrrg i blgr 1..5 mrgl
+    mrglgl mrgl
+        flurg
+    grl mrglurp (err) mrgl
+        count = 100
+    grl
+    count = count + 1
+grl
+"#;
+    assert_eq!(global(source, "count"), Value::Number(0));
+}
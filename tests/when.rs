@@ -0,0 +1,40 @@
+mod common;
+
+use common::{global, text};
+use mur_lang::Value;
+
+/// Runs a `wrrgl` over `hp` and `poisoned` and gives back what it did.
+fn react(hp: i32, poisoned: &str) -> Value {
+    let source = format!(r#"
+grrr hp = {}
+grrr poisoned = {}
+grrr done = ""
+wrrgl
+mrgl
+    grlblgl hp < 20:
+        done = done + "flee "
+        poisoned = mrgfls
+    grlblgl poisoned:
+        done = done + "antidote "
+    blrrghlt:
+        done = done + "fight "
+grl
+"#, hp, poisoned);
+    global(&source, "done")
+}
+
+#[test]
+fn every_branch_that_holds_runs_in_order() {
+    assert_eq!(react(10, "mrgtru"), text("flee antidote "));
+}
+
+#[test]
+fn only_the_branches_that_hold_run() {
+    assert_eq!(react(10, "mrgfls"), text("flee "));
+    assert_eq!(react(50, "mrgtru"), text("antidote "));
+}
+
+#[test]
+fn the_fallback_runs_only_when_nothing_holds() {
+    assert_eq!(react(50, "mrgfls"), text("fight "));
+}